@@ -0,0 +1,408 @@
+use crate::{
+    tuning::{self, Fopdt, Gains, Lambda},
+    ConfigError, ControlLoop, Controller, ControllerAction, Float, OutputLimits,
+};
+
+// The longest dead time the adaptive controller can delay its input
+// regressor by, in samples.
+pub const MAX_DEAD_TIME_SAMPLES: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecursiveLeastSquares<T: Float> {
+    parameters: [T; 2],
+    covariance: [[T; 2]; 2],
+    initial_covariance: T,
+    forgetting_factor: T,
+}
+impl<T: Float> RecursiveLeastSquares<T> {
+    pub fn new(initial_covariance: T, forgetting_factor: T) -> Self {
+        Self {
+            parameters: [T::zero(); 2],
+            covariance: [
+                [initial_covariance, T::zero()],
+                [T::zero(), initial_covariance],
+            ],
+            initial_covariance,
+            forgetting_factor,
+        }
+    }
+    pub fn init(&mut self) {
+        self.parameters = [T::zero(); 2];
+        self.covariance = [
+            [self.initial_covariance, T::zero()],
+            [T::zero(), self.initial_covariance],
+        ];
+    }
+    pub fn parameters(&self) -> [T; 2] {
+        self.parameters
+    }
+    pub fn update(&mut self, regressor: [T; 2], observation: T) {
+        let p = &self.covariance;
+        let p_phi = [
+            p[0][0] * regressor[0] + p[0][1] * regressor[1],
+            p[1][0] * regressor[0] + p[1][1] * regressor[1],
+        ];
+        let denominator =
+            self.forgetting_factor + regressor[0] * p_phi[0] + regressor[1] * p_phi[1];
+        let gain = [p_phi[0] / denominator, p_phi[1] / denominator];
+        let prediction_error =
+            observation - (regressor[0] * self.parameters[0] + regressor[1] * self.parameters[1]);
+        self.parameters[0] = self.parameters[0] + gain[0] * prediction_error;
+        self.parameters[1] = self.parameters[1] + gain[1] * prediction_error;
+
+        // P = (P - K phi' P) / lambda, where phi' P = (P phi)' as P is symmetric.
+        let mut covariance = [[T::zero(); 2]; 2];
+        for (row, covariance_row) in covariance.iter_mut().enumerate() {
+            for (column, value) in covariance_row.iter_mut().enumerate() {
+                *value = (p[row][column] - gain[row] * p_phi[column]) / self.forgetting_factor;
+            }
+        }
+        self.covariance = covariance;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveController<T: Float> {
+    controller: Controller<T>,
+    estimator: RecursiveLeastSquares<T>,
+    rule: Lambda,
+    closed_loop_time_constant: T,
+    dead_time: T,
+    process_gain_band: OutputLimits<T>,
+    max_gain_ratio: T,
+    retune_interval: usize,
+    samples: usize,
+    previous_measurement: Option<T>,
+    // Outputs of the last `delay + 1` updates, the oldest at `head`, and how
+    // many have been recorded.
+    previous_outputs: [T; MAX_DEAD_TIME_SAMPLES + 1],
+    head: usize,
+    delay: usize,
+    recorded: usize,
+}
+impl<T: Float> AdaptiveController<T> {
+    pub fn new(
+        controller: Controller<T>,
+        estimator: RecursiveLeastSquares<T>,
+        rule: Lambda,
+        closed_loop_time_constant: T,
+        retune_interval: usize,
+    ) -> Self {
+        Self {
+            controller,
+            estimator,
+            rule,
+            closed_loop_time_constant,
+            dead_time: T::zero(),
+            process_gain_band: OutputLimits::Unbounded,
+            max_gain_ratio: T::double(),
+            retune_interval,
+            samples: 0,
+            previous_measurement: None,
+            previous_outputs: [T::zero(); MAX_DEAD_TIME_SAMPLES + 1],
+            head: 0,
+            delay: 0,
+            recorded: 0,
+        }
+    }
+    // The output reaches the measurement this much later, so the model is
+    // fitted against the output from that many samples back, rounded to the
+    // controller's current sample time.
+    pub fn set_dead_time(&mut self, dead_time: T) -> Result<(), ConfigError> {
+        if !dead_time.is_finite() {
+            return Err(ConfigError::NonFiniteTimeConstant);
+        }
+        if dead_time < T::zero() {
+            return Err(ConfigError::NegativeTimeConstant);
+        }
+        let samples = dead_time / T::from_duration(&self.controller.sample_time);
+        let Some(delay) =
+            (0..=MAX_DEAD_TIME_SAMPLES).find(|&delay| T::from_f64(delay as f64 + 0.5) > samples)
+        else {
+            return Err(ConfigError::DeadTimeTooLong);
+        };
+        self.dead_time = dead_time;
+        self.delay = delay;
+        self.head = 0;
+        self.recorded = 0;
+        Ok(())
+    }
+    // Identified models must have the static gain sign the controller's
+    // action expects, positive for direct and negative for reverse action,
+    // and are discarded when the gain falls outside this band.
+    pub fn set_process_gain_band(
+        &mut self,
+        process_gain_band: impl Into<OutputLimits<T>>,
+    ) -> Result<(), ConfigError> {
+        let process_gain_band = process_gain_band.into();
+        if process_gain_band.is_inverted() {
+            return Err(ConfigError::InvertedGainLimit);
+        }
+        self.process_gain_band = process_gain_band;
+        Ok(())
+    }
+    pub fn set_max_gain_ratio(&mut self, max_gain_ratio: T) {
+        self.max_gain_ratio = max_gain_ratio;
+    }
+    pub fn controller(&self) -> &Controller<T> {
+        &self.controller
+    }
+    pub fn controller_mut(&mut self) -> &mut Controller<T> {
+        &mut self.controller
+    }
+    pub fn init(&mut self) {
+        self.controller.init();
+        self.estimator.init();
+        self.samples = 0;
+        self.previous_measurement = None;
+        self.head = 0;
+        self.recorded = 0;
+    }
+    // Fits y[k] = a y[k-1] + b u[k-1-d], which is the zero-order-hold
+    // discretization of a first-order lag with a = exp(-dt / tau),
+    // b = K (1 - a) and a dead time of d samples.
+    pub fn model(&self) -> Option<Fopdt<T>> {
+        let [a, b] = self.estimator.parameters();
+        if a <= T::zero() || a >= T::one() {
+            return None;
+        }
+        let gain = b / (T::one() - a);
+        let expected_sign = match self.controller.action {
+            ControllerAction::Direct => gain > T::zero(),
+            ControllerAction::Reverse => gain < T::zero(),
+        };
+        if !expected_sign
+            || self.process_gain_band.is_above(gain)
+            || self.process_gain_band.is_below(gain)
+        {
+            return None;
+        }
+        let dt = T::from_duration(&self.controller.sample_time);
+        Some(Fopdt::new(
+            gain,
+            T::negative() * dt / a.ln(),
+            self.dead_time,
+        ))
+    }
+    fn retune(&mut self) {
+        let Some(model) = self.model() else {
+            return;
+        };
+        // Reverse action negates the error, so the rule is applied to the
+        // magnitude of a negative process gain.
+        let model = Fopdt {
+            gain: model.gain.abs(),
+            ..model
+        };
+        let target = tuning::lambda(&model, self.closed_loop_time_constant, self.rule);
+        // A gain that is not positive has no ratio to limit against, so it
+        // starts from the target reduced by the maximum ratio.
+        let limit = |current: T, target: T| {
+            if current <= T::zero() {
+                target / self.max_gain_ratio
+            } else if target > current * self.max_gain_ratio {
+                current * self.max_gain_ratio
+            } else if target < current / self.max_gain_ratio {
+                current / self.max_gain_ratio
+            } else {
+                target
+            }
+        };
+        let gains = Gains::new(
+            limit(self.controller.p.gain, target.kp),
+            limit(self.controller.i.gain, target.ki),
+            limit(self.controller.d.gain, target.kd),
+        );
+        self.controller.set_gains(&gains);
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        // Nothing is fitted until the output from `delay` samples back exists.
+        let previous_measurement = self
+            .previous_measurement
+            .filter(|_| self.recorded > self.delay);
+        if let Some(previous_measurement) = previous_measurement {
+            let delayed_output = self.previous_outputs[self.head];
+            self.estimator
+                .update([previous_measurement, delayed_output], measurement);
+            self.samples += 1;
+            if self.retune_interval > 0 && self.samples.is_multiple_of(self.retune_interval) {
+                self.retune();
+            }
+        }
+        let output = self.controller.update(setpoint, measurement);
+        self.previous_measurement = Some(measurement);
+        self.previous_outputs[self.head] = output;
+        self.head = (self.head + 1) % (self.delay + 1);
+        self.recorded = self.recorded.saturating_add(1);
+        output
+    }
+}
+impl<T: Float> ControlLoop<T> for AdaptiveController<T> {
+    fn init(&mut self) {
+        AdaptiveController::init(self);
+    }
+    fn output(&self) -> T {
+        self.controller.output()
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        AdaptiveController::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::{Differentiator, Integrator, OutputLimits, Proportional};
+
+    fn adaptive(retune_interval: usize) -> AdaptiveController<f64> {
+        let controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(0.5),
+            Integrator::new(0.5, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        AdaptiveController::new(
+            controller,
+            RecursiveLeastSquares::new(1e6, 1.0),
+            Lambda::PI,
+            0.1,
+            retune_interval,
+        )
+    }
+
+    // y[k] = 0.9 y[k-1] + b u[k-1-delay] under a setpoint square wave.
+    fn simulate(adaptive: &mut AdaptiveController<f64>, steps: usize, b: f64, delay: usize) {
+        let mut measurement = 0.0;
+        let mut outputs = [0.0; 8];
+        for step in 0..steps {
+            let setpoint = if (step / 50) % 2 == 0 { 1.0 } else { 0.0 };
+            outputs.rotate_right(1);
+            outputs[0] = adaptive.update(setpoint, measurement);
+            measurement = 0.9 * measurement + b * outputs[delay];
+        }
+    }
+
+    fn run(adaptive: &mut AdaptiveController<f64>, steps: usize) {
+        simulate(adaptive, steps, 0.2, 0);
+    }
+
+    #[test]
+    fn least_squares_recovers_a_first_order_model() {
+        let mut estimator = RecursiveLeastSquares::new(1e6, 1.0);
+        let (mut y, mut u) = (0.0, 1.0);
+        for step in 0..200 {
+            let next = 0.8 * y + 0.3 * u;
+            estimator.update([y, u], next);
+            y = next;
+            if step % 7 == 0 {
+                u = -u;
+            }
+        }
+        let [a, b] = estimator.parameters();
+        assert!((a - 0.8).abs() < 1e-6);
+        assert!((b - 0.3).abs() < 1e-6);
+        estimator.init();
+        assert_eq!(estimator.parameters(), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn identified_model_is_the_plant() {
+        let mut adaptive = adaptive(0);
+        run(&mut adaptive, 400);
+        let model = adaptive.model().unwrap();
+        assert!((model.gain - 2.0).abs() < 1e-6);
+        assert!((model.time_constant + 0.01 / 0.9f64.ln()).abs() < 1e-6);
+        // Without a retune interval the gains are left alone.
+        assert_eq!(adaptive.controller().p.gain, 0.5);
+        assert_eq!(adaptive.controller().i.gain, 0.5);
+
+        adaptive.set_process_gain_band(5.0..=10.0).unwrap();
+        assert!(adaptive.model().is_none());
+        adaptive.init();
+        assert!(adaptive.model().is_none());
+    }
+
+    #[test]
+    fn retuning_is_rate_limited() {
+        // lambda asks for ki = 5 from a plant with tau = 0.095 s, but a
+        // single retune may at most double the current 0.5.
+        let mut adaptive = adaptive(400);
+        run(&mut adaptive, 401);
+        assert!((adaptive.controller().i.gain - 1.0).abs() < 1e-12);
+        let tau = -0.01 / 0.9f64.ln();
+        assert!((adaptive.controller().p.gain - tau / 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reverse_acting_plants_are_identified_and_tuned() {
+        let mut adaptive = adaptive(400);
+        adaptive
+            .controller_mut()
+            .set_action(ControllerAction::Reverse);
+        simulate(&mut adaptive, 401, -0.2, 0);
+        let model = adaptive.model().unwrap();
+        assert!((model.gain + 2.0).abs() < 1e-6);
+        assert!((adaptive.controller().i.gain - 1.0).abs() < 1e-12);
+        assert!(adaptive.controller().p.gain > 0.0);
+
+        // The band applies to the signed gain.
+        adaptive.set_process_gain_band(-3.0..=-1.0).unwrap();
+        assert!(adaptive.model().is_some());
+        adaptive.set_process_gain_band(1.0..=3.0).unwrap();
+        assert!(adaptive.model().is_none());
+        adaptive
+            .set_process_gain_band(OutputLimits::Unbounded)
+            .unwrap();
+        adaptive
+            .controller_mut()
+            .set_action(ControllerAction::Direct);
+        assert!(adaptive.model().is_none());
+        assert_eq!(
+            adaptive.set_process_gain_band(3.0..=1.0),
+            Err(ConfigError::InvertedGainLimit)
+        );
+    }
+
+    #[test]
+    fn dead_time_delays_the_input_regressor() {
+        let mut delayed = adaptive(0);
+        delayed.set_dead_time(0.03).unwrap();
+        simulate(&mut delayed, 400, 0.2, 3);
+        let model = delayed.model().unwrap();
+        assert!((model.gain - 2.0).abs() < 1e-6);
+        assert_eq!(model.dead_time, 0.03);
+
+        // Fitted against the undelayed output, the same data gives the wrong
+        // model.
+        let mut undelayed = adaptive(0);
+        simulate(&mut undelayed, 400, 0.2, 3);
+        assert!(undelayed
+            .model()
+            .is_none_or(|model| (model.gain - 2.0).abs() > 0.1));
+
+        assert_eq!(
+            delayed.set_dead_time(-0.01),
+            Err(ConfigError::NegativeTimeConstant)
+        );
+        assert_eq!(
+            delayed.set_dead_time(1.0),
+            Err(ConfigError::DeadTimeTooLong)
+        );
+        assert_eq!(
+            delayed.set_dead_time(f64::NAN),
+            Err(ConfigError::NonFiniteTimeConstant)
+        );
+    }
+
+    #[test]
+    fn retuning_from_zero_gain_is_rate_limited() {
+        let mut adaptive = adaptive(400);
+        adaptive.controller_mut().set_ki(0.0);
+        run(&mut adaptive, 401);
+        // Half of the ki = 5 that lambda asks for.
+        assert!((adaptive.controller().i.gain - 2.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,202 @@
+use core::time::Duration;
+
+use crate::{ConfigError, ControlLoop, Float, OutputLimits, Timestep};
+
+// First-order linear active disturbance rejection control. The plant is taken
+// as y' = b0 u + f, where f lumps together everything else: unmodeled
+// dynamics, load and parameter errors. An extended state observer tracks y
+// and f, the control law cancels the estimated f and closes a proportional
+// loop on the estimated y, leaving a first-order closed loop with the
+// controller bandwidth. A first-order plant needs no derivative action; the
+// observer's estimate of f takes the place of the integral term.
+//
+// Only b0 needs to be known, and only roughly. The observer bandwidth is
+// typically three to ten times the controller bandwidth; the observer is
+// discretized with explicit Euler and needs observer bandwidth * dt < 1.
+// It is fed the limited output, so saturation cannot wind it up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adrc<T: Float> {
+    output_limit: OutputLimits<T>,
+    sample_time: Duration,
+    b0: T,
+    controller_bandwidth: T,
+    observer_bandwidth: T,
+    // Estimated output and total disturbance.
+    estimate: Option<(T, T)>,
+    out: T,
+}
+impl<T: Float> Adrc<T> {
+    pub fn new(
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        b0: T,
+        controller_bandwidth: T,
+        observer_bandwidth: T,
+    ) -> Self {
+        Self {
+            output_limit: output_limit.into(),
+            sample_time,
+            b0,
+            controller_bandwidth,
+            observer_bandwidth,
+            estimate: None,
+            out: T::zero(),
+        }
+    }
+    pub fn try_new(
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        b0: T,
+        controller_bandwidth: T,
+        observer_bandwidth: T,
+    ) -> Result<Self, ConfigError> {
+        let output_limit = output_limit.into();
+        if output_limit.is_inverted() {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        if sample_time.is_zero() {
+            return Err(ConfigError::ZeroSampleTime);
+        }
+        if !(b0.is_finite() && controller_bandwidth.is_finite()) || b0 == T::zero() {
+            return Err(ConfigError::NonFiniteGain);
+        }
+        let step = observer_bandwidth * T::from_duration(&sample_time);
+        if !(observer_bandwidth > T::zero() && step < T::one()) {
+            return Err(ConfigError::UnstableEstimator);
+        }
+        Ok(Self::new(
+            output_limit,
+            sample_time,
+            b0,
+            controller_bandwidth,
+            observer_bandwidth,
+        ))
+    }
+    pub fn set_bandwidths(&mut self, controller_bandwidth: T, observer_bandwidth: T) {
+        self.controller_bandwidth = controller_bandwidth;
+        self.observer_bandwidth = observer_bandwidth;
+    }
+    pub fn set_b0(&mut self, b0: T) {
+        self.b0 = b0;
+    }
+    pub fn set_output_limits(&mut self, output_limit: impl Into<OutputLimits<T>>) {
+        self.output_limit = output_limit.into();
+        self.out = self.output_limit.clamp(self.out);
+    }
+    pub fn init(&mut self) {
+        self.estimate = None;
+        self.out = T::zero();
+    }
+    pub fn output(&self) -> T {
+        self.out
+    }
+    // The estimated total disturbance f, in units of the measurement's rate.
+    pub fn disturbance(&self) -> Option<T> {
+        self.estimate.map(|(_, disturbance)| disturbance)
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        let dt = T::from_duration(&self.sample_time);
+        self.step(setpoint, measurement, dt)
+    }
+    pub fn update_with_dt<D: Timestep<T>>(&mut self, setpoint: T, measurement: T, dt: D) -> T {
+        self.step(setpoint, measurement, dt.seconds())
+    }
+    fn step(&mut self, setpoint: T, measurement: T, dt: T) -> T {
+        // The observer starts on the first measurement with no disturbance.
+        let (position, disturbance) = match self.estimate {
+            Some((position, disturbance)) => {
+                let residual = measurement - position;
+                let beta1 = T::double() * self.observer_bandwidth;
+                let beta2 = self.observer_bandwidth * self.observer_bandwidth;
+                (
+                    position + dt * (disturbance + self.b0 * self.out + beta1 * residual),
+                    disturbance + dt * beta2 * residual,
+                )
+            }
+            None => (measurement, T::zero()),
+        };
+        self.estimate = Some((position, disturbance));
+        let law = self.controller_bandwidth * (setpoint - position);
+        self.out = self.output_limit.clamp((law - disturbance) / self.b0);
+        self.out
+    }
+}
+impl<T: Float> ControlLoop<T> for Adrc<T> {
+    fn init(&mut self) {
+        Adrc::init(self);
+    }
+    fn output(&self) -> T {
+        Adrc::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        Adrc::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // y' = gain u + load, integrated over each 10 ms sample.
+    fn settle(adrc: &mut Adrc<f64>, gain: f64, load: f64) -> f64 {
+        let mut measurement = 0.0;
+        for _ in 0..1000 {
+            let output = adrc.update(1.0, measurement);
+            measurement += 0.01 * (gain * output + load);
+        }
+        measurement
+    }
+
+    fn controller(b0: f64) -> Adrc<f64> {
+        Adrc::try_new(-10.0..=10.0, Duration::from_millis(10), b0, 5.0, 20.0).unwrap()
+    }
+
+    #[test]
+    fn load_is_estimated_and_rejected() {
+        let mut adrc = controller(2.0);
+        assert_eq!(adrc.disturbance(), None);
+        let measurement = settle(&mut adrc, 2.0, -1.0);
+        assert!((measurement - 1.0).abs() < 1e-6);
+        assert!((adrc.disturbance().unwrap() + 1.0).abs() < 1e-6);
+        assert!((adrc.output() - 0.5).abs() < 1e-6);
+        adrc.init();
+        assert_eq!(adrc.disturbance(), None);
+    }
+
+    #[test]
+    fn rough_b0_still_settles() {
+        // The gain error is lumped into the disturbance.
+        let mut adrc = controller(2.0);
+        assert!((settle(&mut adrc, 3.0, -1.0) - 1.0).abs() < 1e-6);
+        let mut limited = controller(2.0);
+        limited.set_output_limits(-0.2..=0.2);
+        // Saturated at 0.2, the plant cannot overcome the load.
+        assert!(settle(&mut limited, 2.0, -1.0) < 0.0);
+        assert_eq!(limited.output(), 0.2);
+    }
+
+    #[test]
+    fn invalid_configuration_is_rejected() {
+        let sample_time = Duration::from_millis(10);
+        assert_eq!(
+            Adrc::try_new(1.0..=-1.0, sample_time, 1.0, 1.0, 10.0),
+            Err(ConfigError::InvertedOutputLimit)
+        );
+        assert_eq!(
+            Adrc::try_new(.., Duration::ZERO, 1.0, 1.0, 10.0),
+            Err(ConfigError::ZeroSampleTime)
+        );
+        assert_eq!(
+            Adrc::try_new(.., sample_time, 0.0, 1.0, 10.0),
+            Err(ConfigError::NonFiniteGain)
+        );
+        assert_eq!(
+            Adrc::try_new(.., sample_time, 1.0, 1.0, 100.0),
+            Err(ConfigError::UnstableEstimator)
+        );
+        assert_eq!(
+            Adrc::try_new(.., sample_time, 1.0, 1.0, f64::NAN),
+            Err(ConfigError::UnstableEstimator)
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use core::time::Duration;
+
+use crate::Float;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UltimateGain<T: Float> {
+    pub gain: T,
+    pub period: T,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayAutotune<T: Float> {
+    setpoint: T,
+    high_output: T,
+    low_output: T,
+    hysteresis: T,
+    cycles: usize,
+    relay_high: bool,
+    elapsed: T,
+    last_rising_switch: Option<T>,
+    max_measurement: Option<T>,
+    min_measurement: Option<T>,
+    measured_cycles: usize,
+    period_sum: T,
+    amplitude_sum: T,
+}
+impl<T: Float> RelayAutotune<T> {
+    pub fn new(setpoint: T, high_output: T, low_output: T, hysteresis: T, cycles: usize) -> Self {
+        Self {
+            setpoint,
+            high_output,
+            low_output,
+            hysteresis,
+            cycles,
+            relay_high: true,
+            elapsed: T::zero(),
+            last_rising_switch: None,
+            max_measurement: None,
+            min_measurement: None,
+            measured_cycles: 0,
+            period_sum: T::zero(),
+            amplitude_sum: T::zero(),
+        }
+    }
+    pub fn init(&mut self) {
+        self.relay_high = true;
+        self.elapsed = T::zero();
+        self.last_rising_switch = None;
+        self.max_measurement = None;
+        self.min_measurement = None;
+        self.measured_cycles = 0;
+        self.period_sum = T::zero();
+        self.amplitude_sum = T::zero();
+    }
+    pub fn is_finished(&self) -> bool {
+        self.measured_cycles >= self.cycles
+    }
+    pub fn update(&mut self, measurement: T, sample_time: &Duration) -> T {
+        self.elapsed = self.elapsed + T::from_duration(sample_time);
+        match self.max_measurement {
+            Some(max) if max >= measurement => {}
+            _ => self.max_measurement = Some(measurement),
+        }
+        match self.min_measurement {
+            Some(min) if min <= measurement => {}
+            _ => self.min_measurement = Some(measurement),
+        }
+
+        let error = self.setpoint - measurement;
+        if self.relay_high && error < T::negative() * self.hysteresis {
+            self.relay_high = false;
+        } else if !self.relay_high && error > self.hysteresis {
+            self.relay_high = true;
+            self.rising_switch();
+        }
+
+        if self.relay_high {
+            self.high_output
+        } else {
+            self.low_output
+        }
+    }
+    // One full oscillation lies between consecutive rising switches. The first
+    // one only marks the end of the start-up transient.
+    fn rising_switch(&mut self) {
+        if let (Some(last), Some(max), Some(min)) = (
+            self.last_rising_switch,
+            self.max_measurement,
+            self.min_measurement,
+        ) {
+            if !self.is_finished() {
+                self.period_sum = self.period_sum + (self.elapsed - last);
+                self.amplitude_sum = self.amplitude_sum + T::half() * (max - min);
+                self.measured_cycles += 1;
+            }
+        }
+        self.last_rising_switch = Some(self.elapsed);
+        self.max_measurement = None;
+        self.min_measurement = None;
+    }
+    // The describing function of a relay with hysteresis e gives
+    // Ku = 4 d / (pi sqrt(a^2 - e^2)); an oscillation no larger than the
+    // hysteresis carries no information.
+    pub fn result(&self) -> Option<UltimateGain<T>> {
+        if self.measured_cycles == 0 || !self.is_finished() {
+            return None;
+        }
+        let cycles = T::from_f64(self.measured_cycles as f64);
+        let amplitude = self.amplitude_sum / cycles;
+        let hysteresis = self.hysteresis.abs();
+        if amplitude <= hysteresis {
+            return None;
+        }
+        let relay_amplitude = T::half() * (self.high_output - self.low_output);
+        let effective_amplitude = (amplitude * amplitude - hysteresis * hysteresis).sqrt();
+        Some(UltimateGain {
+            gain: T::from_f64(4.0 / core::f64::consts::PI) * relay_amplitude / effective_amplitude,
+            period: self.period_sum / cycles,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_TIME: Duration = Duration::from_millis(1);
+
+    // Runs the relay on y' = u(t - 0.1 s) until it finishes.
+    fn run(relay: &mut RelayAutotune<f64>) {
+        let mut delay = [0.0; 100];
+        let mut measurement: f64 = 0.0;
+        let mut steps = 0;
+        while !relay.is_finished() {
+            assert!(relay.result().is_none());
+            let output = relay.update(measurement, &SAMPLE_TIME);
+            let delayed = delay[steps % 100];
+            delay[steps % 100] = output;
+            measurement += delayed * 0.001;
+            steps += 1;
+            assert!(steps < 10_000, "relay never finished");
+        }
+    }
+
+    // Under relay feedback of amplitude d around zero the measurement is a
+    // triangle wave of amplitude 0.1 d and period 0.4 s.
+    #[test]
+    fn measures_period_and_amplitude_of_the_relay_oscillation() {
+        let mut relay = RelayAutotune::new(0.0, 1.0, -1.0, 0.0, 4);
+        run(&mut relay);
+        let result = relay.result().unwrap();
+        assert!((result.period - 0.4).abs() < 0.005, "{result:?}");
+        let expected_gain = 4.0 / core::f64::consts::PI / 0.1;
+        assert!(
+            (result.gain / expected_gain - 1.0).abs() < 0.03,
+            "{result:?}"
+        );
+
+        relay.init();
+        assert!(!relay.is_finished());
+        assert!(relay.result().is_none());
+    }
+
+    // With hysteresis e the relay switches e past the setpoint, so the
+    // triangle grows to an amplitude of e + 0.1 d and a period of 4 (e + 0.1).
+    #[test]
+    fn hysteresis_is_taken_out_of_the_ultimate_gain() {
+        let mut relay = RelayAutotune::new(0.0, 1.0, -1.0, 0.05, 4);
+        run(&mut relay);
+        let result = relay.result().unwrap();
+        assert!((result.period - 0.6).abs() < 0.005, "{result:?}");
+        let amplitude: f64 = 0.15;
+        let expected_gain =
+            4.0 / (core::f64::consts::PI * (amplitude * amplitude - 0.05 * 0.05).sqrt());
+        assert!(
+            (result.gain / expected_gain - 1.0).abs() < 0.03,
+            "{result:?}"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use core::time::Duration;
+
+use crate::{tuning::Gains, ConfigError, Controller, Float, OutputLimits, Timestep};
+
+// N independent PID loops sharing a sample time, stored as one array per
+// quantity so each step of the update is a straight loop over the lanes with
+// no branches that depend on lane data. The compiler vectorizes these loops
+// into f32x4/f32x8 operations on targets that have them. Derivative is on
+// measurement and the integrator is clamped to the output limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControllerBank<T: Float, const N: usize> {
+    sample_time: Duration,
+    kp: [T; N],
+    ki: [T; N],
+    kd: [T; N],
+    lower: [T; N],
+    upper: [T; N],
+    integral: [T; N],
+    previous_measurement: [T; N],
+    initialized: bool,
+    out: [T; N],
+}
+impl<T: Float, const N: usize> ControllerBank<T, N> {
+    // All lanes start with zero gains and no output limits.
+    pub fn new(sample_time: Duration) -> Self {
+        Self {
+            sample_time,
+            kp: [T::zero(); N],
+            ki: [T::zero(); N],
+            kd: [T::zero(); N],
+            lower: [T::zero() - T::infinity(); N],
+            upper: [T::infinity(); N],
+            integral: [T::zero(); N],
+            previous_measurement: [T::zero(); N],
+            initialized: false,
+            out: [T::zero(); N],
+        }
+    }
+    pub fn set_lane(
+        &mut self,
+        lane: usize,
+        gains: &Gains<T>,
+        output_limit: impl Into<OutputLimits<T>>,
+    ) -> Result<(), ConfigError> {
+        let output_limit = output_limit.into();
+        if output_limit.is_inverted() {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        self.kp[lane] = gains.kp;
+        self.ki[lane] = gains.ki;
+        self.kd[lane] = gains.kd;
+        self.lower[lane] = output_limit.lower().unwrap_or(T::zero() - T::infinity());
+        self.upper[lane] = output_limit.upper().unwrap_or(T::infinity());
+        self.integral[lane] = clamp(self.integral[lane], self.lower[lane], self.upper[lane]);
+        Ok(())
+    }
+    pub fn set_sample_time(&mut self, sample_time: Duration) -> Result<(), ConfigError> {
+        if sample_time.is_zero() {
+            return Err(ConfigError::ZeroSampleTime);
+        }
+        self.sample_time = sample_time;
+        Ok(())
+    }
+    pub fn init(&mut self) {
+        self.integral = [T::zero(); N];
+        self.previous_measurement = [T::zero(); N];
+        self.initialized = false;
+        self.out = [T::zero(); N];
+    }
+    pub fn output(&self) -> &[T; N] {
+        &self.out
+    }
+    pub fn integral(&self) -> &[T; N] {
+        &self.integral
+    }
+    pub fn update(&mut self, setpoints: &[T; N], measurements: &[T; N]) -> &[T; N] {
+        let dt = T::from_duration(&self.sample_time);
+        self.step(setpoints, measurements, dt)
+    }
+    pub fn update_with_dt<D: Timestep<T>>(
+        &mut self,
+        setpoints: &[T; N],
+        measurements: &[T; N],
+        dt: D,
+    ) -> &[T; N] {
+        self.step(setpoints, measurements, dt.seconds())
+    }
+    fn step(&mut self, setpoints: &[T; N], measurements: &[T; N], dt: T) -> &[T; N] {
+        // The first update has no previous measurement to difference against.
+        if !self.initialized {
+            self.previous_measurement = *measurements;
+            self.initialized = true;
+        }
+        for lane in 0..N {
+            let error = setpoints[lane] - measurements[lane];
+            let integral = (self.ki[lane] * dt).mul_add(error, self.integral[lane]);
+            self.integral[lane] = clamp(integral, self.lower[lane], self.upper[lane]);
+            let rate = (self.previous_measurement[lane] - measurements[lane]) / dt;
+            let output = self.kp[lane] * error + self.integral[lane] + self.kd[lane] * rate;
+            self.out[lane] = clamp(output, self.lower[lane], self.upper[lane]);
+        }
+        self.previous_measurement = *measurements;
+        &self.out
+    }
+}
+
+// N full controllers stored inline, each with its own configuration, updated
+// together. Unlike `ControllerBank` every lane has all of the controller's
+// features, at the cost of a per-lane update.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Controllers<T: Float, const N: usize> {
+    controllers: [Controller<T>; N],
+}
+impl<T: Float, const N: usize> Controllers<T, N> {
+    pub fn new(controllers: [Controller<T>; N]) -> Self {
+        Self { controllers }
+    }
+    pub fn controllers(&self) -> &[Controller<T>; N] {
+        &self.controllers
+    }
+    pub fn controllers_mut(&mut self) -> &mut [Controller<T>; N] {
+        &mut self.controllers
+    }
+    pub fn init(&mut self) {
+        for controller in &mut self.controllers {
+            controller.init();
+        }
+    }
+    pub fn output(&self) -> [T; N] {
+        core::array::from_fn(|lane| self.controllers[lane].output())
+    }
+    pub fn update(&mut self, setpoints: &[T; N], measurements: &[T; N]) -> [T; N] {
+        core::array::from_fn(|lane| {
+            self.controllers[lane].update(setpoints[lane], measurements[lane])
+        })
+    }
+    pub fn update_with_dt<D: Timestep<T>>(
+        &mut self,
+        setpoints: &[T; N],
+        measurements: &[T; N],
+        dt: D,
+    ) -> [T; N] {
+        let dt = dt.seconds();
+        core::array::from_fn(|lane| {
+            self.controllers[lane].update_with_dt(setpoints[lane], measurements[lane], dt)
+        })
+    }
+}
+
+fn clamp<T: Float>(value: T, lower: T, upper: T) -> T {
+    let value = if value < lower { lower } else { value };
+    if value > upper {
+        upper
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Differentiator, Integrator, Proportional};
+
+    fn bank() -> ControllerBank<f64, 3> {
+        let mut bank = ControllerBank::new(Duration::from_millis(100));
+        bank.set_lane(0, &Gains::new(2.0, 0.0, 0.0), ..).unwrap();
+        bank.set_lane(1, &Gains::new(0.0, 10.0, 0.0), 0.0..=1.0)
+            .unwrap();
+        bank.set_lane(2, &Gains::new(0.0, 0.0, 1.0), ..).unwrap();
+        bank
+    }
+
+    #[test]
+    fn lanes_run_independently() {
+        let mut bank = bank();
+        assert_eq!(bank.update(&[1.0; 3], &[0.0; 3]), &[2.0, 1.0, 0.0]);
+        assert_eq!(bank.update(&[1.0; 3], &[0.0; 3]), &[2.0, 1.0, 0.0]);
+        // The saturated integrator does not wind up, so it leaves the limit
+        // as soon as the error reverses.
+        let output = *bank.update(&[1.0, -1.0, 1.0], &[0.0, 0.0, 0.5]);
+        assert_eq!(output[1], 0.0);
+        assert!((output[2] + 5.0).abs() < 1e-12);
+        bank.init();
+        assert_eq!(bank.output(), &[0.0; 3]);
+        assert_eq!(bank.integral(), &[0.0; 3]);
+    }
+
+    #[test]
+    fn invalid_configuration_is_rejected() {
+        let mut bank = bank();
+        assert_eq!(
+            bank.set_lane(0, &Gains::new(1.0, 0.0, 0.0), 1.0..=-1.0),
+            Err(ConfigError::InvertedOutputLimit)
+        );
+        assert_eq!(
+            bank.set_sample_time(Duration::ZERO),
+            Err(ConfigError::ZeroSampleTime)
+        );
+    }
+
+    #[test]
+    fn controllers_match_their_lanes_run_alone() {
+        let controller = |kp: f64| {
+            Controller::new(
+                OutputLimits::Inclusive(-5.0, 5.0),
+                Duration::from_millis(10),
+                Proportional::new(kp),
+                Integrator::new(1.0, OutputLimits::Unbounded),
+                Differentiator::new(0.1, 0.01),
+            )
+        };
+        let mut controllers = Controllers::new([controller(1.0), controller(3.0)]);
+        let (mut first, mut second) = (controller(1.0), controller(3.0));
+        for step in 0..20 {
+            let measurement = step as f64 * 0.05;
+            let outputs = controllers.update(&[1.0, 2.0], &[measurement, -measurement]);
+            assert_eq!(outputs[0], first.update(1.0, measurement));
+            assert_eq!(outputs[1], second.update(2.0, -measurement));
+        }
+        assert_eq!(controllers.output(), [first.output(), second.output()]);
+    }
+}
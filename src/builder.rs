@@ -0,0 +1,139 @@
+use core::time::Duration;
+
+use crate::{
+    tuning::{self, Gains, StandardGains},
+    ConfigError, Controller, Differentiator, Float, Integrator, OutputLimits, Proportional,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControllerBuilder<T: Float> {
+    kp: T,
+    ki: T,
+    kd: T,
+    derivative_time_constant: T,
+    derivative_filter: Option<T>,
+    output_limit: OutputLimits<T>,
+    integral_limit: Option<OutputLimits<T>>,
+    sample_time: Option<Duration>,
+}
+impl<T: Float> ControllerBuilder<T> {
+    pub fn new() -> Self {
+        Self {
+            kp: T::zero(),
+            ki: T::zero(),
+            kd: T::zero(),
+            derivative_time_constant: T::zero(),
+            derivative_filter: None,
+            output_limit: OutputLimits::Unbounded,
+            integral_limit: None,
+            sample_time: None,
+        }
+    }
+    pub fn kp(mut self, kp: T) -> Self {
+        self.kp = kp;
+        self
+    }
+    pub fn ki(mut self, ki: T) -> Self {
+        self.ki = ki;
+        self
+    }
+    pub fn kd(mut self, kd: T) -> Self {
+        self.kd = kd;
+        self
+    }
+    pub fn standard(self, kc: T, integral_time: T, derivative_time: T) -> Self {
+        let gains = Gains::from(StandardGains::new(kc, integral_time, derivative_time));
+        self.kp(gains.kp).ki(gains.ki).kd(gains.kd)
+    }
+    pub fn derivative_time_constant(mut self, derivative_time_constant: T) -> Self {
+        self.derivative_time_constant = derivative_time_constant;
+        self
+    }
+    // Takes precedence over `derivative_time_constant`.
+    pub fn derivative_filter(mut self, n: T) -> Self {
+        self.derivative_filter = Some(n);
+        self
+    }
+    pub fn limits(mut self, output_limit: impl Into<OutputLimits<T>>) -> Self {
+        self.output_limit = output_limit.into();
+        self
+    }
+    // Defaults to the output limits when not set.
+    pub fn integral_limits(mut self, integral_limit: impl Into<OutputLimits<T>>) -> Self {
+        self.integral_limit = Some(integral_limit.into());
+        self
+    }
+    pub fn sample_time(mut self, sample_time: Duration) -> Self {
+        self.sample_time = Some(sample_time);
+        self
+    }
+    pub fn build(self) -> Result<Controller<T>, ConfigError> {
+        // Checked up front so a default integral limit inherited from an
+        // inverted output limit is reported against the output limit.
+        if self.output_limit.is_inverted() {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        let integral_limit = self.integral_limit.unwrap_or(self.output_limit);
+        let sample_time = self.sample_time.ok_or(ConfigError::MissingSampleTime)?;
+        let derivative_time_constant = match self.derivative_filter {
+            Some(n) => tuning::derivative_filter_time_constant(self.kp, self.kd, n)?,
+            None => self.derivative_time_constant,
+        };
+        Controller::try_new(
+            self.output_limit,
+            sample_time,
+            Proportional::try_new(self.kp)?,
+            Integrator::try_new(self.ki, integral_limit)?,
+            Differentiator::try_new(self.kd, derivative_time_constant)?,
+        )
+    }
+}
+impl<T: Float> Default for ControllerBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_same_controller_as_the_constructor() {
+        let built = ControllerBuilder::new()
+            .standard(2.0, 4.0, 0.5)
+            .derivative_filter(10.0)
+            .limits(-1.0..=1.0)
+            .sample_time(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let expected = Controller::new(
+            -1.0..=1.0,
+            Duration::from_millis(10),
+            Proportional::new(2.0),
+            Integrator::new(0.5, -1.0..=1.0),
+            Differentiator::new(1.0, 0.05),
+        );
+        assert_eq!(built, expected);
+        assert_eq!(built.gains(), Gains::new(2.0, 0.5, 1.0));
+    }
+
+    #[test]
+    fn invalid_configuration_is_rejected() {
+        let builder = ControllerBuilder::new().kp(1.0).kd(0.1);
+        assert_eq!(builder.clone().build(), Err(ConfigError::MissingSampleTime));
+        let builder = builder.sample_time(Duration::from_millis(10));
+        assert_eq!(
+            builder.clone().limits(1.0..=-1.0).build(),
+            Err(ConfigError::InvertedOutputLimit)
+        );
+        assert_eq!(
+            builder.clone().derivative_filter(0.0).build(),
+            Err(ConfigError::DerivativeFilterOutOfRange)
+        );
+        assert_eq!(
+            builder.kp(f64::NAN).build(),
+            Err(ConfigError::NonFiniteGain)
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{ControlOutput, Controller, ControllerAction, Float, Mode};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CascadeOutput<T: Float> {
+    pub primary: ControlOutput<T>,
+    pub secondary: ControlOutput<T>,
+}
+
+// Two loops in cascade: the primary (outer) controller's output is the
+// setpoint of the secondary (inner) controller, whose output drives the
+// actuator.
+//
+// Whenever the inner loop does not follow the outer output, the outer loop
+// tracks what it does follow instead of winding up: the secondary measurement
+// on the first update after `init` and in manual mode, and the local setpoint
+// while the cascade is broken. While the inner output is saturated, outer
+// integration that would drive it further into the limit is undone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cascade<T: Float> {
+    primary: Controller<T>,
+    secondary: Controller<T>,
+    local_setpoint: Option<T>,
+    mode: Mode,
+    initialized: bool,
+}
+impl<T: Float> Cascade<T> {
+    pub fn new(primary: Controller<T>, secondary: Controller<T>) -> Self {
+        Self {
+            primary,
+            secondary,
+            local_setpoint: None,
+            mode: Mode::Auto,
+            initialized: false,
+        }
+    }
+    pub fn primary(&self) -> &Controller<T> {
+        &self.primary
+    }
+    pub fn primary_mut(&mut self) -> &mut Controller<T> {
+        &mut self.primary
+    }
+    pub fn secondary(&self) -> &Controller<T> {
+        &self.secondary
+    }
+    pub fn secondary_mut(&mut self) -> &mut Controller<T> {
+        &mut self.secondary
+    }
+    // The mode is applied to the secondary controller, which drives the
+    // actuator; outside auto the primary tracks the secondary measurement.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.secondary.set_mode(mode);
+    }
+    pub fn set_manual_output(&mut self, output: T) {
+        self.secondary.set_manual_output(output);
+    }
+    // Breaks the cascade, running the secondary controller on a local
+    // setpoint, or closes it again with `None`.
+    pub fn set_local_setpoint(&mut self, local_setpoint: Option<T>) {
+        self.local_setpoint = local_setpoint;
+    }
+    pub fn output(&self) -> T {
+        self.secondary.output()
+    }
+    // The inner loop is initialized first; the outer loop then starts from the
+    // secondary measurement on the next update.
+    pub fn init(&mut self) {
+        self.secondary.init();
+        self.primary.init();
+        self.initialized = false;
+    }
+    pub fn update(&mut self, setpoint: T, primary_measurement: T, secondary_measurement: T) -> T {
+        self.update_detailed(setpoint, primary_measurement, secondary_measurement)
+            .secondary
+            .output
+    }
+    pub fn update_detailed(
+        &mut self,
+        setpoint: T,
+        primary_measurement: T,
+        secondary_measurement: T,
+    ) -> CascadeOutput<T> {
+        // A held primary stays frozen rather than tracking, and a faulted one
+        // keeps its fail-safe output.
+        let tracking = if matches!(self.primary.mode, Mode::Hold | Mode::Fault) {
+            None
+        } else if !self.initialized || self.mode != Mode::Auto {
+            Some(secondary_measurement)
+        } else {
+            self.local_setpoint
+        };
+        let previous_integral = self.primary.integral();
+        let primary = match tracking {
+            Some(tracking) => {
+                self.primary
+                    .update_detailed_tracked(setpoint, primary_measurement, tracking)
+            }
+            None => self.primary.update_detailed(setpoint, primary_measurement),
+        };
+        let secondary_setpoint = self.local_setpoint.unwrap_or(primary.output);
+        let secondary = self
+            .secondary
+            .update_detailed(secondary_setpoint, secondary_measurement);
+        if tracking.is_none() && secondary.saturated {
+            // A larger secondary setpoint raises the output of a direct-acting
+            // inner loop and lowers that of a reverse-acting one.
+            let push = match self.secondary.action {
+                ControllerAction::Direct => self.primary.integral() - previous_integral,
+                ControllerAction::Reverse => previous_integral - self.primary.integral(),
+            };
+            let above = secondary.unclamped > secondary.output;
+            if (above && push > T::zero()) || (!above && push < T::zero()) {
+                self.primary.set_integral(previous_integral);
+            }
+        }
+        self.initialized = true;
+        CascadeOutput { primary, secondary }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::{Differentiator, FaultAction, Integrator, OutputLimits, Proportional};
+
+    fn pi_controller() -> Controller<f64> {
+        Controller::new(
+            OutputLimits::Inclusive(-100.0, 100.0),
+            Duration::from_millis(100),
+            Proportional::new(1.0),
+            Integrator::new(1.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn primary_tracks_the_secondary_measurement_in_manual() {
+        let mut cascade = Cascade::new(pi_controller(), pi_controller());
+        cascade.set_mode(Mode::Manual);
+        cascade.set_manual_output(20.0);
+        let report = cascade.update_detailed(10.0, 3.0, 7.0);
+        assert_eq!(report.primary.output, 7.0);
+        assert_eq!(cascade.primary().mode(), Mode::Auto);
+        assert_eq!(cascade.output(), 20.0);
+
+        // Back in auto the primary continues from the tracked value, moved
+        // only by one integration step of the error.
+        cascade.set_mode(Mode::Auto);
+        let report = cascade.update_detailed(10.0, 3.0, 7.0);
+        assert!((report.primary.output - 7.7).abs() < 1e-12);
+    }
+
+    #[test]
+    fn faulted_primary_keeps_its_fail_safe_output() {
+        let mut primary = pi_controller();
+        primary.set_fault_action(FaultAction::Output(-5.0));
+        let mut cascade = Cascade::new(primary, pi_controller());
+        cascade.primary_mut().set_mode(Mode::Fault);
+        cascade.set_mode(Mode::Manual);
+        let report = cascade.update_detailed(10.0, 3.0, 7.0);
+        assert_eq!(report.primary.output, -5.0);
+        assert_eq!(cascade.primary().mode(), Mode::Fault);
+    }
+
+    #[cfg(feature = "trace-hook")]
+    #[test]
+    fn tracking_reports_no_mode_changes() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::trace::{TraceEvent, Tracer};
+
+        static MODE_CHANGES: AtomicUsize = AtomicUsize::new(0);
+        fn count(event: &TraceEvent<f64>) {
+            if let TraceEvent::ModeChange { .. } = event {
+                MODE_CHANGES.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        let mut primary = pi_controller();
+        primary.set_tracer(Some(Tracer::new(count)));
+        let mut cascade = Cascade::new(primary, pi_controller());
+        for _ in 0..5 {
+            cascade.update(10.0, 3.0, 7.0);
+        }
+        assert_eq!(MODE_CHANGES.load(Ordering::Relaxed), 0);
+    }
+}
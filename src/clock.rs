@@ -0,0 +1,114 @@
+use core::time::Duration;
+
+use crate::Float;
+
+pub trait Clock {
+    fn now(&self) -> u64;
+    fn tick_period(&self) -> Duration;
+    fn elapsed(&self, from: u64, to: u64) -> Duration {
+        let nanos = self
+            .tick_period()
+            .as_nanos()
+            .saturating_mul(to.wrapping_sub(from) as u128);
+        Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+    }
+}
+
+// A monotonic timestamp measured from a fixed epoch, such as the value of
+// `duration_since_epoch()` on an embedded-time `Instant`. Wrapping such an
+// instant in a newtype implementing this trait lets HAL clocks drive
+// `Controller::update_at_timestamp` directly.
+pub trait Timestamp {
+    fn since_epoch(&self) -> Duration;
+}
+impl Timestamp for Duration {
+    fn since_epoch(&self) -> Duration {
+        *self
+    }
+}
+
+pub trait Timestep<T: Float> {
+    fn seconds(&self) -> T;
+    // The step as a `Duration`, for steps that have one.
+    fn duration(&self) -> Option<Duration> {
+        None
+    }
+}
+// A bare float is taken as a step in seconds, or in whatever dimensionless
+// unit a simulation chooses to integrate over.
+impl<T: Float> Timestep<T> for T {
+    fn seconds(&self) -> T {
+        *self
+    }
+}
+impl<T: Float> Timestep<T> for Duration {
+    fn seconds(&self) -> T {
+        T::from_duration(self)
+    }
+    fn duration(&self) -> Option<Duration> {
+        Some(*self)
+    }
+}
+
+// A tick count at a fixed rate of NOM / DENOM seconds per tick, matching the
+// representation used by fugit and most RTOS timer APIs, so their durations
+// convert without a round trip through `Duration`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ticks<const NOM: u32, const DENOM: u32>(pub u64);
+impl<const NOM: u32, const DENOM: u32> Ticks<NOM, DENOM> {
+    pub const fn new(ticks: u64) -> Self {
+        Self(ticks)
+    }
+    pub const fn ticks(&self) -> u64 {
+        self.0
+    }
+}
+impl<T: Float, const NOM: u32, const DENOM: u32> Timestep<T> for Ticks<NOM, DENOM> {
+    fn seconds(&self) -> T {
+        T::from_f64(self.0 as f64 * NOM as f64 / DENOM as f64)
+    }
+    fn duration(&self) -> Option<Duration> {
+        let nanos = self.0 as u128 * NOM as u128 * 1_000_000_000 / DENOM as u128;
+        Some(Duration::from_nanos(nanos.min(u64::MAX as u128) as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter(u64);
+    impl Clock for Counter {
+        fn now(&self) -> u64 {
+            self.0
+        }
+        fn tick_period(&self) -> Duration {
+            Duration::from_micros(100)
+        }
+    }
+
+    #[test]
+    fn elapsed_survives_counter_wraparound() {
+        let clock = Counter(5);
+        assert_eq!(clock.elapsed(0, clock.now()), Duration::from_micros(500));
+        assert_eq!(clock.elapsed(u64::MAX - 4, 5), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn ticks_convert_at_their_rate() {
+        let ticks = Ticks::<1, 32_768>::new(16_384);
+        assert_eq!(ticks.ticks(), 16_384);
+        assert_eq!(Timestep::<f64>::seconds(&ticks), 0.5);
+        assert_eq!(
+            Timestep::<f64>::duration(&ticks),
+            Some(Duration::from_millis(500))
+        );
+        let millis = Ticks::<1, 1_000>::new(250);
+        assert_eq!(Timestep::<f32>::seconds(&millis), 0.25);
+        assert_eq!(Timestep::<f64>::duration(&0.25), None);
+        assert_eq!(
+            Timestep::<f64>::duration(&Duration::from_millis(3)),
+            Some(Duration::from_millis(3))
+        );
+    }
+}
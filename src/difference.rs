@@ -0,0 +1,157 @@
+use core::time::Duration;
+
+use crate::{tuning::Gains, ControlLoop, Float, OutputLimits};
+
+// A PID compiled for a fixed sample time into
+//
+//   u[k] = a1 u[k-1] + a2 u[k-2] + b0 e[k] + b1 e[k-1] + b2 e[k-2]
+//
+// using the same discretization as `Controller`: trapezoidal integration and
+// a bilinear first-order derivative filter, here acting on the error. The
+// second output tap only appears with a derivative filter. Storing the
+// clamped output back into the history gives velocity-form anti-windup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifferenceEquation<T: Float> {
+    a: [T; 2],
+    b: [T; 3],
+    output_limit: OutputLimits<T>,
+    errors: [T; 2],
+    outputs: [T; 2],
+}
+impl<T: Float> DifferenceEquation<T> {
+    pub fn new(
+        gains: &Gains<T>,
+        derivative_time_constant: T,
+        sample_time: Duration,
+        output_limit: impl Into<OutputLimits<T>>,
+    ) -> Self {
+        let dt = T::from_duration(&sample_time);
+        let h = T::half() * gains.ki * dt;
+        // Filter pole p and gain c, so that D[k] = p D[k-1] + c (e[k] - e[k-1]).
+        let (p, c) = if derivative_time_constant == T::zero() {
+            (T::zero(), gains.kd / dt)
+        } else {
+            let denominator = T::double() * derivative_time_constant + dt;
+            (
+                (T::double() * derivative_time_constant - dt) / denominator,
+                T::double() * gains.kd / denominator,
+            )
+        };
+        let kp = gains.kp;
+        Self {
+            a: [T::one() + p, T::zero() - p],
+            b: [
+                kp + h + c,
+                h * (T::one() - p) - kp * (T::one() + p) - T::double() * c,
+                kp * p - h * p + c,
+            ],
+            output_limit: output_limit.into(),
+            errors: [T::zero(); 2],
+            outputs: [T::zero(); 2],
+        }
+    }
+    pub fn a(&self) -> [T; 2] {
+        self.a
+    }
+    pub fn b(&self) -> [T; 3] {
+        self.b
+    }
+    pub fn init(&mut self) {
+        self.errors = [T::zero(); 2];
+        self.outputs = [T::zero(); 2];
+    }
+    pub fn output(&self) -> T {
+        self.outputs[0]
+    }
+    // Seeds the history as if the loop had been settled at `output` with zero
+    // error, for a bumpless start.
+    pub fn set_output(&mut self, output: T) {
+        self.errors = [T::zero(); 2];
+        self.outputs = [output; 2];
+    }
+    pub fn set_output_limits(&mut self, output_limit: impl Into<OutputLimits<T>>) {
+        self.output_limit = output_limit.into();
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        let error = setpoint - measurement;
+        let output = self.b[2].mul_add(
+            self.errors[1],
+            self.b[1].mul_add(
+                self.errors[0],
+                self.b[0].mul_add(
+                    error,
+                    self.a[1].mul_add(self.outputs[1], self.a[0] * self.outputs[0]),
+                ),
+            ),
+        );
+        let output = self.output_limit.clamp(output);
+        self.errors = [error, self.errors[0]];
+        self.outputs = [output, self.outputs[0]];
+        output
+    }
+}
+impl<T: Float> ControlLoop<T> for DifferenceEquation<T> {
+    fn init(&mut self) {
+        DifferenceEquation::init(self);
+    }
+    fn output(&self) -> T {
+        DifferenceEquation::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        DifferenceEquation::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pi() -> DifferenceEquation<f64> {
+        DifferenceEquation::new(
+            &Gains::new(1.0, 2.0, 0.0),
+            0.0,
+            Duration::from_millis(100),
+            OutputLimits::Unbounded,
+        )
+    }
+
+    #[test]
+    fn pi_coefficients_and_step_response() {
+        let mut pi = pi();
+        assert_eq!(pi.a(), [1.0, 0.0]);
+        let [b0, b1, b2] = pi.b();
+        assert!((b0 - 1.1).abs() < 1e-12 && (b1 + 0.9).abs() < 1e-12 && b2 == 0.0);
+        // Trapezoidal integration adds ki dt per step once the error is
+        // constant.
+        for expected in [1.1, 1.3, 1.5] {
+            assert!((pi.update(1.0, 0.0) - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn clamped_history_prevents_windup() {
+        let mut pi = pi();
+        pi.set_output_limits(..=1.2);
+        for _ in 0..100 {
+            pi.update(1.0, 0.0);
+        }
+        assert_eq!(pi.output(), 1.2);
+        assert!((pi.update(0.0, 0.0) - 0.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn seeded_output_holds_at_zero_error() {
+        let mut pid = DifferenceEquation::new(
+            &Gains::new(1.0, 2.0, 0.5),
+            0.05,
+            Duration::from_millis(10),
+            OutputLimits::Unbounded,
+        );
+        pid.set_output(5.0);
+        for _ in 0..10 {
+            assert!((pid.update(1.0, 1.0) - 5.0).abs() < 1e-12);
+        }
+        pid.init();
+        assert_eq!(pid.output(), 0.0);
+    }
+}
@@ -0,0 +1,133 @@
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    InvertedOutputLimit,
+    InvertedIntegralLimit,
+    InvertedSetpointLimit,
+    InvertedRatioLimit,
+    InvertedGainLimit,
+    InvertedMeasurementRange,
+    MissingSampleTime,
+    ZeroSampleTime,
+    NonFiniteGain,
+    NonFiniteTimeConstant,
+    NegativeTimeConstant,
+    InvalidDerivativeWindow,
+    NonPositiveGainScale,
+    DerivativeFilterOutOfRange,
+    ZeroProportionalGain,
+    InvalidFilterFrequency,
+    InvalidFilterQ,
+    UnstableEstimator,
+    InvalidMotionLimits,
+    InvalidWrapPeriod,
+    InvalidErrorSpan,
+    InvalidSplitRange,
+    InvalidSchedule,
+    InvalidHysteresis,
+    InvalidTimeProportioning,
+    EmptySwarm,
+    InvalidFilterWindow,
+    FilterChainFull,
+    TooManyHarmonics,
+    NotCompilable,
+    DeadTimeTooLong,
+    MaxGapOverflow,
+    InvalidRampRate,
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvertedOutputLimit => write!(f, "output limit end is below its start"),
+            ConfigError::InvertedIntegralLimit => {
+                write!(f, "integral limit end is below its start")
+            }
+            ConfigError::InvertedSetpointLimit => {
+                write!(f, "setpoint limit end is below its start")
+            }
+            ConfigError::InvertedRatioLimit => write!(f, "ratio limit end is below its start"),
+            ConfigError::InvertedGainLimit => write!(f, "gain limit end is below its start"),
+            ConfigError::InvertedMeasurementRange => {
+                write!(f, "measurement range end is below its start")
+            }
+            ConfigError::MissingSampleTime => write!(f, "no sample time was configured"),
+            ConfigError::ZeroSampleTime => write!(f, "sample time must be non-zero"),
+            ConfigError::NonFiniteGain => write!(f, "gains must be finite"),
+            ConfigError::NonFiniteTimeConstant => write!(f, "time constants must be finite"),
+            ConfigError::NegativeTimeConstant => write!(f, "time constants must not be negative"),
+            ConfigError::InvalidDerivativeWindow => write!(
+                f,
+                "derivative window must be between 1 and {}",
+                crate::MAX_DERIVATIVE_WINDOW
+            ),
+            ConfigError::NonPositiveGainScale => write!(f, "gain scale must be positive"),
+            ConfigError::DerivativeFilterOutOfRange => write!(
+                f,
+                "derivative filter N must be between {} and {}",
+                crate::tuning::MIN_DERIVATIVE_FILTER,
+                crate::tuning::MAX_DERIVATIVE_FILTER
+            ),
+            ConfigError::ZeroProportionalGain => {
+                write!(f, "derivative filter N needs a non-zero proportional gain")
+            }
+            ConfigError::InvalidFilterFrequency => {
+                write!(
+                    f,
+                    "filter frequency must be between zero and the Nyquist frequency"
+                )
+            }
+            ConfigError::InvalidFilterQ => write!(f, "filter Q must be positive and finite"),
+            ConfigError::UnstableEstimator => {
+                write!(f, "estimator gains are outside the stable region")
+            }
+            ConfigError::InvalidMotionLimits => {
+                write!(f, "motion limits must be positive and finite")
+            }
+            ConfigError::InvalidWrapPeriod => write!(f, "wrap period must be positive and finite"),
+            ConfigError::InvalidErrorSpan => write!(f, "error span must be positive and finite"),
+            ConfigError::InvalidSplitRange => write!(
+                f,
+                "split range needs bounded limits with the deadband strictly inside"
+            ),
+            ConfigError::InvalidSchedule => write!(
+                f,
+                "gain schedule needs finite, strictly increasing breakpoints"
+            ),
+            ConfigError::InvalidHysteresis => {
+                write!(f, "hysteresis must be non-negative and finite")
+            }
+            ConfigError::InvalidTimeProportioning => write!(
+                f,
+                "time proportioning needs bounded limits and minimum times within the window"
+            ),
+            ConfigError::EmptySwarm => write!(f, "particle swarm needs at least one particle"),
+            ConfigError::InvalidFilterWindow => {
+                write!(
+                    f,
+                    "filter window must be between 1 and the filter's capacity"
+                )
+            }
+            ConfigError::FilterChainFull => write!(f, "filter chain has no free stage"),
+            ConfigError::TooManyHarmonics => write!(
+                f,
+                "resonant term supports at most {} harmonics",
+                crate::resonant::MAX_HARMONICS
+            ),
+            ConfigError::NotCompilable => write!(
+                f,
+                "controller uses features a difference equation cannot represent"
+            ),
+            ConfigError::DeadTimeTooLong => write!(
+                f,
+                "dead time must be at most {} samples",
+                crate::adaptive::MAX_DEAD_TIME_SAMPLES
+            ),
+            ConfigError::MaxGapOverflow => {
+                write!(f, "max gap overflows when scaled to the new sample time")
+            }
+            ConfigError::InvalidRampRate => write!(f, "ramp rate must be positive and finite"),
+        }
+    }
+}
+impl core::error::Error for ConfigError {}
@@ -0,0 +1,143 @@
+use crate::{filter::Filter, ConfigError, Float};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate<T: Float> {
+    pub position: T,
+    pub velocity: T,
+}
+
+// Alpha-beta tracker: a steady-state Kalman filter for a constant-velocity
+// model. Each update predicts the position forward by the current velocity
+// and corrects both by the residual, alpha for the position and beta / dt for
+// the velocity. Smaller gains filter more and lag more.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphaBeta<T: Float> {
+    alpha: T,
+    beta: T,
+    estimate: Option<Estimate<T>>,
+}
+impl<T: Float> AlphaBeta<T> {
+    pub const fn new(alpha: T, beta: T) -> Self {
+        Self {
+            alpha,
+            beta,
+            estimate: None,
+        }
+    }
+    // The tracker is stable for 0 < alpha <= 1 and 0 < beta < 4 - 2 alpha.
+    pub fn try_new(alpha: T, beta: T) -> Result<Self, ConfigError> {
+        let stable = alpha > T::zero()
+            && alpha <= T::one()
+            && beta > T::zero()
+            && beta < T::from_f64(4.0) - T::double() * alpha;
+        if !stable {
+            return Err(ConfigError::UnstableEstimator);
+        }
+        Ok(Self::new(alpha, beta))
+    }
+    // Gains for a critically damped tracker with the given smoothing factor
+    // theta in (0, 1); larger theta filters more.
+    pub fn critically_damped(theta: T) -> Result<Self, ConfigError> {
+        let complement = T::one() - theta;
+        Self::try_new(T::one() - theta * theta, complement * complement)
+    }
+    pub fn init(&mut self) {
+        self.estimate = None;
+    }
+    pub fn estimate(&self) -> Option<Estimate<T>> {
+        self.estimate
+    }
+    // The first measurement is taken as the position with zero velocity.
+    pub fn update(&mut self, measurement: T, dt: T) -> Estimate<T> {
+        let estimate = match self.estimate {
+            Some(Estimate { position, velocity }) => {
+                let predicted = position + dt * velocity;
+                let residual = measurement - predicted;
+                Estimate {
+                    position: predicted + self.alpha * residual,
+                    velocity: velocity + self.beta / dt * residual,
+                }
+            }
+            None => Estimate {
+                position: measurement,
+                velocity: T::zero(),
+            },
+        };
+        self.estimate = Some(estimate);
+        estimate
+    }
+}
+// As a filter stage the tracker passes on its position estimate.
+impl<T: Float> Filter<T> for AlphaBeta<T> {
+    fn init(&mut self) {
+        self.estimate = None;
+    }
+    fn reset_to(&mut self, output: T) {
+        self.estimate = Some(Estimate {
+            position: output,
+            velocity: T::zero(),
+        });
+    }
+    fn update(&mut self, input: T, dt: T) -> T {
+        AlphaBeta::update(self, input, dt).position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_a_ramp_without_lag() {
+        let mut tracker = AlphaBeta::critically_damped(0.5).unwrap();
+        assert_eq!(
+            tracker.update(1.0, 0.1),
+            Estimate {
+                position: 1.0,
+                velocity: 0.0
+            }
+        );
+        let mut estimate = tracker.estimate().unwrap();
+        for step in 1..200 {
+            estimate = tracker.update(1.0 + 3.0 * step as f64 * 0.1, 0.1);
+        }
+        assert!((estimate.position - (1.0 + 3.0 * 19.9)).abs() < 1e-9);
+        assert!((estimate.velocity - 3.0).abs() < 1e-9);
+        tracker.init();
+        assert_eq!(tracker.estimate(), None);
+    }
+
+    #[test]
+    fn smooths_measurement_noise() {
+        let mut tracker = AlphaBeta::critically_damped(0.8).unwrap();
+        let mut worst: f64 = 0.0;
+        for step in 0..400 {
+            let noise = if step % 2 == 0 { 1.0 } else { -1.0 };
+            let position = Filter::update(&mut tracker, 5.0 + noise, 0.01);
+            if step >= 200 {
+                worst = worst.max((position - 5.0).abs());
+            }
+        }
+        assert!(worst < 0.5);
+        Filter::reset_to(&mut tracker, 2.0);
+        assert_eq!(
+            tracker.estimate(),
+            Some(Estimate {
+                position: 2.0,
+                velocity: 0.0
+            })
+        );
+    }
+
+    #[test]
+    fn unstable_gains_are_rejected() {
+        for (alpha, beta) in [(0.0, 0.5), (1.5, 0.5), (0.5, 0.0), (0.5, 3.0)] {
+            assert_eq!(
+                AlphaBeta::try_new(alpha, beta),
+                Err(ConfigError::UnstableEstimator)
+            );
+        }
+        assert!(AlphaBeta::try_new(1.0, 1.9).is_ok());
+        assert!(AlphaBeta::<f64>::critically_damped(1.0).is_err());
+    }
+}
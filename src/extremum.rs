@@ -0,0 +1,173 @@
+use core::time::Duration;
+
+use crate::{
+    filter::{Filter, LowPass},
+    math, ConfigError, Float, OutputLimits,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    Maximize,
+    Minimize,
+}
+
+// Perturb-and-observe extremum seeking, e.g. for maximum power point
+// tracking: a sinusoidal dither is added to the input estimate, the measured
+// performance is high-passed to remove its mean, demodulated by the dither to
+// estimate the local gradient, and the gradient is integrated into the
+// estimate. The dither frequency should be well below the sample rate and
+// slow enough for the plant to respond quasi-statically.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtremumSeeker<T: Float> {
+    sample_time: Duration,
+    objective: Objective,
+    amplitude: T,
+    gain: T,
+    output_limit: OutputLimits<T>,
+    washout: LowPass<T>,
+    // The dither phase as a unit vector, rotated by `step` each sample.
+    dither: (T, T),
+    step: (T, T),
+    estimate: T,
+    out: T,
+}
+impl<T: Float> ExtremumSeeker<T> {
+    // The performance is washed out with a time constant of one dither
+    // period, and the search starts from `initial`.
+    pub fn new(
+        sample_time: Duration,
+        objective: Objective,
+        frequency: f64,
+        amplitude: T,
+        gain: T,
+        initial: T,
+    ) -> Result<Self, ConfigError> {
+        let sample_rate = 1.0 / sample_time.as_secs_f64();
+        if !(frequency > 0.0 && frequency < sample_rate / 2.0) {
+            return Err(ConfigError::InvalidFilterFrequency);
+        }
+        if !(amplitude.is_finite() && gain.is_finite()) {
+            return Err(ConfigError::NonFiniteGain);
+        }
+        let (sin, cos) = math::sin_cos(core::f64::consts::TAU * frequency / sample_rate);
+        Ok(Self {
+            sample_time,
+            objective,
+            amplitude,
+            gain,
+            output_limit: OutputLimits::Unbounded,
+            washout: LowPass::new(T::from_f64(1.0 / frequency)),
+            dither: (T::zero(), T::one()),
+            step: (T::from_f64(sin), T::from_f64(cos)),
+            estimate: initial,
+            out: initial,
+        })
+    }
+    // Limits the estimate; the dither is added on top.
+    pub fn set_output_limits(&mut self, output_limit: impl Into<OutputLimits<T>>) {
+        self.output_limit = output_limit.into();
+        self.estimate = self.output_limit.clamp(self.estimate);
+    }
+    pub fn set_gain(&mut self, gain: T) {
+        self.gain = gain;
+    }
+    pub fn set_amplitude(&mut self, amplitude: T) {
+        self.amplitude = amplitude;
+    }
+    pub fn estimate(&self) -> T {
+        self.estimate
+    }
+    pub fn output(&self) -> T {
+        self.out
+    }
+    pub fn init(&mut self, initial: T) {
+        self.washout.init();
+        self.dither = (T::zero(), T::one());
+        self.estimate = self.output_limit.clamp(initial);
+        self.out = self.estimate;
+    }
+    // Takes the performance measured for the last output and returns the
+    // next one.
+    pub fn update(&mut self, performance: T) -> T {
+        let dt = T::from_duration(&self.sample_time);
+        let varying = performance - self.washout.update(performance, dt);
+        let gradient = varying * self.dither.0;
+        let rate = match self.objective {
+            Objective::Maximize => self.gain * gradient,
+            Objective::Minimize => T::zero() - self.gain * gradient,
+        };
+        self.estimate = self.output_limit.clamp(self.estimate + dt * rate);
+
+        let (sin, cos) = self.dither;
+        let (step_sin, step_cos) = self.step;
+        let (sin, cos) = (
+            sin * step_cos + cos * step_sin,
+            cos * step_cos - sin * step_sin,
+        );
+        // Renormalize so rounding does not make the amplitude drift.
+        let norm = (sin * sin + cos * cos).sqrt();
+        self.dither = (sin / norm, cos / norm);
+        self.out = self.estimate + self.amplitude * self.dither.0;
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeker(objective: Objective) -> ExtremumSeeker<f64> {
+        ExtremumSeeker::new(Duration::from_millis(10), objective, 1.0, 0.1, 10.0, 0.0).unwrap()
+    }
+
+    // Runs for 60 s against a performance peaked or dipped at 3.
+    fn seek(seeker: &mut ExtremumSeeker<f64>, sign: f64) -> f64 {
+        let mut input = seeker.output();
+        for _ in 0..6000 {
+            let performance = sign * (input - 3.0) * (input - 3.0);
+            input = seeker.update(performance);
+        }
+        seeker.estimate()
+    }
+
+    #[test]
+    fn finds_the_extremum() {
+        assert!((seek(&mut seeker(Objective::Maximize), -1.0) - 3.0).abs() < 0.05);
+        assert!((seek(&mut seeker(Objective::Minimize), 1.0) - 3.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn estimate_stays_within_its_limits() {
+        let mut seeker = seeker(Objective::Maximize);
+        seeker.set_output_limits(..=2.0);
+        // The demodulated gradient ripples, so the estimate rides just
+        // below the limit.
+        let estimate = seek(&mut seeker, -1.0);
+        assert!(estimate <= 2.0 && estimate > 1.95);
+        seeker.init(5.0);
+        assert_eq!(seeker.estimate(), 2.0);
+        assert_eq!(seeker.output(), 2.0);
+    }
+
+    #[test]
+    fn dither_must_be_below_nyquist() {
+        let sample_time = Duration::from_millis(10);
+        for frequency in [0.0, 50.0, f64::NAN] {
+            assert_eq!(
+                ExtremumSeeker::new(sample_time, Objective::Maximize, frequency, 0.1, 1.0, 0.0),
+                Err(ConfigError::InvalidFilterFrequency)
+            );
+        }
+        assert_eq!(
+            ExtremumSeeker::new(
+                sample_time,
+                Objective::Maximize,
+                1.0,
+                f64::INFINITY,
+                1.0,
+                0.0
+            ),
+            Err(ConfigError::NonFiniteGain)
+        );
+    }
+}
@@ -0,0 +1,796 @@
+use core::time::Duration;
+
+use crate::{estimator::AlphaBeta, math, ConfigError, ControllerComponent, Float, Timestep};
+
+pub trait Filter<T: Float> {
+    fn init(&mut self);
+    // Puts the filter in the steady state that produces `output`. Filters
+    // without a meaningful steady state just start over.
+    fn reset_to(&mut self, output: T) {
+        let _ = output;
+        self.init();
+    }
+    fn update(&mut self, input: T, dt: T) -> T;
+}
+
+// Window capacity of the median and moving-average filters a chain can hold;
+// the window itself is picked at run time up to this many samples.
+pub const MAX_STAGE_WINDOW: usize = 8;
+
+// A filter held inline by a `FilterChain`, so chains and the controller that
+// owns them need no allocator.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stage<T: Float> {
+    LowPass(LowPass<T>),
+    LeadLag(LeadLag<T>),
+    Biquad(Biquad<T>),
+    Median(MedianFilter<T, MAX_STAGE_WINDOW>),
+    MovingAverage(MovingAverage<T, MAX_STAGE_WINDOW>),
+    AlphaBeta(AlphaBeta<T>),
+}
+impl<T: Float> Stage<T> {
+    pub fn median(window: usize) -> Result<Self, ConfigError> {
+        MedianFilter::with_window(window).map(Stage::Median)
+    }
+    pub fn moving_average(window: usize) -> Result<Self, ConfigError> {
+        MovingAverage::with_window(window).map(Stage::MovingAverage)
+    }
+    fn filter(&mut self) -> &mut dyn Filter<T> {
+        match self {
+            Stage::LowPass(filter) => filter,
+            Stage::LeadLag(filter) => filter,
+            Stage::Biquad(filter) => filter,
+            Stage::Median(filter) => filter,
+            Stage::MovingAverage(filter) => filter,
+            Stage::AlphaBeta(filter) => filter,
+        }
+    }
+}
+impl<T: Float> Filter<T> for Stage<T> {
+    fn init(&mut self) {
+        self.filter().init();
+    }
+    fn reset_to(&mut self, output: T) {
+        self.filter().reset_to(output);
+    }
+    fn update(&mut self, input: T, dt: T) -> T {
+        self.filter().update(input, dt)
+    }
+}
+impl<T: Float> From<LowPass<T>> for Stage<T> {
+    fn from(filter: LowPass<T>) -> Self {
+        Stage::LowPass(filter)
+    }
+}
+impl<T: Float> From<LeadLag<T>> for Stage<T> {
+    fn from(filter: LeadLag<T>) -> Self {
+        Stage::LeadLag(filter)
+    }
+}
+impl<T: Float> From<Biquad<T>> for Stage<T> {
+    fn from(filter: Biquad<T>) -> Self {
+        Stage::Biquad(filter)
+    }
+}
+impl<T: Float> From<MedianFilter<T, MAX_STAGE_WINDOW>> for Stage<T> {
+    fn from(filter: MedianFilter<T, MAX_STAGE_WINDOW>) -> Self {
+        Stage::Median(filter)
+    }
+}
+impl<T: Float> From<MovingAverage<T, MAX_STAGE_WINDOW>> for Stage<T> {
+    fn from(filter: MovingAverage<T, MAX_STAGE_WINDOW>) -> Self {
+        Stage::MovingAverage(filter)
+    }
+}
+impl<T: Float> From<AlphaBeta<T>> for Stage<T> {
+    fn from(filter: AlphaBeta<T>) -> Self {
+        Stage::AlphaBeta(filter)
+    }
+}
+
+// Up to N filters applied in the order they were pushed. An empty chain
+// passes its input through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterChain<T: Float, const N: usize = 4> {
+    stages: [Option<Stage<T>>; N],
+    len: usize,
+}
+impl<T: Float, const N: usize> FilterChain<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            stages: [const { None }; N],
+            len: 0,
+        }
+    }
+    pub fn push(&mut self, stage: impl Into<Stage<T>>) -> Result<(), ConfigError> {
+        let slot = self
+            .stages
+            .get_mut(self.len)
+            .ok_or(ConfigError::FilterChainFull)?;
+        *slot = Some(stage.into());
+        self.len += 1;
+        Ok(())
+    }
+    pub fn with(mut self, stage: impl Into<Stage<T>>) -> Result<Self, ConfigError> {
+        self.push(stage)?;
+        Ok(self)
+    }
+    pub fn clear(&mut self) {
+        self.stages = [const { None }; N];
+        self.len = 0;
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    fn stages_mut(&mut self) -> impl Iterator<Item = &mut Stage<T>> {
+        self.stages.iter_mut().flatten()
+    }
+}
+impl<T: Float, const N: usize> Filter<T> for FilterChain<T, N> {
+    fn init(&mut self) {
+        for stage in self.stages_mut() {
+            stage.init();
+        }
+    }
+    // Every stage is settled at the same value, which is exact for stages
+    // with unity DC gain.
+    fn reset_to(&mut self, output: T) {
+        for stage in self.stages_mut() {
+            stage.reset_to(output);
+        }
+    }
+    fn update(&mut self, input: T, dt: T) -> T {
+        self.stages_mut()
+            .fold(input, |value, stage| stage.update(value, dt))
+    }
+}
+impl<T: Float, const N: usize> Default for FilterChain<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// First-order lag y' = (x - y) / time_constant, discretized with backward
+// Euler so it stays stable for any sample time. The first sample passes
+// through unchanged so the filter does not start with a ramp up from zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LowPass<T: Float> {
+    time_constant: T,
+    value: Option<T>,
+}
+impl<T: Float> LowPass<T> {
+    pub const fn new(time_constant: T) -> Self {
+        Self {
+            time_constant,
+            value: None,
+        }
+    }
+    pub fn try_new(time_constant: T) -> Result<Self, ConfigError> {
+        if !time_constant.is_finite() {
+            return Err(ConfigError::NonFiniteTimeConstant);
+        }
+        if time_constant < T::zero() {
+            return Err(ConfigError::NegativeTimeConstant);
+        }
+        Ok(Self::new(time_constant))
+    }
+    pub fn value(&self) -> Option<T> {
+        self.value
+    }
+    pub fn set_time_constant(&mut self, time_constant: T) {
+        self.time_constant = time_constant;
+    }
+}
+impl<T: Float> Filter<T> for LowPass<T> {
+    fn init(&mut self) {
+        self.value = None;
+    }
+    fn reset_to(&mut self, output: T) {
+        self.value = Some(output);
+    }
+    fn update(&mut self, input: T, dt: T) -> T {
+        let value = match self.value {
+            Some(value) => value + dt / (self.time_constant + dt) * (input - value),
+            None => input,
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+// Lead-lag compensator gain * (lead s + 1) / (lag s + 1), discretized with
+// backward Euler like `LowPass`. A lead longer than the lag adds phase lead;
+// the reverse smooths like a low-pass with a partial step through. It starts
+// in the steady state of its first input.
+//
+// As a controller component it acts on setpoint - measurement, so a zero
+// measurement turns it into dynamic feedforward on the setpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeadLag<T: Float> {
+    gain: T,
+    lead: T,
+    lag: T,
+    // Previous input and output.
+    state: Option<(T, T)>,
+}
+impl<T: Float> LeadLag<T> {
+    pub const fn new(gain: T, lead: T, lag: T) -> Self {
+        Self {
+            gain,
+            lead,
+            lag,
+            state: None,
+        }
+    }
+    pub fn try_new(gain: T, lead: T, lag: T) -> Result<Self, ConfigError> {
+        if !gain.is_finite() {
+            return Err(ConfigError::NonFiniteGain);
+        }
+        if !(lead.is_finite() && lag.is_finite()) {
+            return Err(ConfigError::NonFiniteTimeConstant);
+        }
+        if lead < T::zero() || lag < T::zero() {
+            return Err(ConfigError::NegativeTimeConstant);
+        }
+        Ok(Self::new(gain, lead, lag))
+    }
+    pub fn set_time_constants(&mut self, lead: T, lag: T) {
+        self.lead = lead;
+        self.lag = lag;
+    }
+}
+impl<T: Float> Filter<T> for LeadLag<T> {
+    fn init(&mut self) {
+        self.state = None;
+    }
+    fn reset_to(&mut self, output: T) {
+        if self.gain == T::zero() {
+            self.state = None;
+        } else {
+            self.state = Some((output / self.gain, output));
+        }
+    }
+    fn update(&mut self, input: T, dt: T) -> T {
+        let output = match self.state {
+            Some((previous_input, previous_output)) => {
+                let drive = self.gain * (self.lead * (input - previous_input) + dt * input);
+                (self.lag * previous_output + drive) / (self.lag + dt)
+            }
+            None => self.gain * input,
+        };
+        self.state = Some((input, output));
+        output
+    }
+}
+impl<T: Float, D: Timestep<T>> ControllerComponent<T, D> for LeadLag<T> {
+    fn init(&mut self) {
+        Filter::init(self);
+    }
+    fn update(&mut self, setpoint: T, measurement: T, sample_time: &D) -> T {
+        Filter::update(self, setpoint - measurement, sample_time.seconds())
+    }
+}
+
+// Median of the last N samples, which passes steps through after N / 2
+// samples but removes isolated spikes up to (N - 1) / 2 samples long entirely.
+// Until N samples have been seen the median of those available is used.
+// `with_window` uses only the first `window` of the N slots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MedianFilter<T: Float, const N: usize> {
+    samples: [T; N],
+    window: usize,
+    len: usize,
+    head: usize,
+}
+impl<T: Float, const N: usize> MedianFilter<T, N> {
+    pub fn new() -> Self {
+        const { assert!(N > 0, "median filter window must not be empty") };
+        Self {
+            samples: [T::zero(); N],
+            window: N,
+            len: 0,
+            head: 0,
+        }
+    }
+    pub fn with_window(window: usize) -> Result<Self, ConfigError> {
+        if window == 0 || window > N {
+            return Err(ConfigError::InvalidFilterWindow);
+        }
+        Ok(Self {
+            window,
+            ..Self::new()
+        })
+    }
+}
+impl<T: Float, const N: usize> Filter<T> for MedianFilter<T, N> {
+    fn init(&mut self) {
+        self.len = 0;
+        self.head = 0;
+    }
+    fn reset_to(&mut self, output: T) {
+        self.samples = [output; N];
+        self.len = self.window;
+        self.head = 0;
+    }
+    fn update(&mut self, input: T, _: T) -> T {
+        self.samples[self.head] = input;
+        self.head = (self.head + 1) % self.window;
+        self.len = (self.len + 1).min(self.window);
+
+        // Insertion sort of a copy; N is small and this keeps it allocation free.
+        let mut sorted = self.samples;
+        let sorted = &mut sorted[..self.len];
+        for i in 1..sorted.len() {
+            let mut j = i;
+            while j > 0 && sorted[j] < sorted[j - 1] {
+                sorted.swap(j, j - 1);
+                j -= 1;
+            }
+        }
+        let middle = sorted.len() / 2;
+        if sorted.len() % 2 == 1 {
+            sorted[middle]
+        } else {
+            T::half() * (sorted[middle - 1] + sorted[middle])
+        }
+    }
+}
+impl<T: Float, const N: usize> Default for MedianFilter<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Mean of the last N samples, or of all samples seen so far until there are
+// N of them. The running sum is recomputed from the window once per cycle so
+// rounding errors cannot accumulate. `with_window` uses only the first
+// `window` of the N slots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovingAverage<T: Float, const N: usize> {
+    samples: [T; N],
+    window: usize,
+    sum: T,
+    len: usize,
+    head: usize,
+}
+impl<T: Float, const N: usize> MovingAverage<T, N> {
+    pub fn new() -> Self {
+        const { assert!(N > 0, "moving average window must not be empty") };
+        Self {
+            samples: [T::zero(); N],
+            window: N,
+            sum: T::zero(),
+            len: 0,
+            head: 0,
+        }
+    }
+    pub fn with_window(window: usize) -> Result<Self, ConfigError> {
+        if window == 0 || window > N {
+            return Err(ConfigError::InvalidFilterWindow);
+        }
+        Ok(Self {
+            window,
+            ..Self::new()
+        })
+    }
+}
+impl<T: Float, const N: usize> Filter<T> for MovingAverage<T, N> {
+    fn init(&mut self) {
+        self.sum = T::zero();
+        self.len = 0;
+        self.head = 0;
+    }
+    fn reset_to(&mut self, output: T) {
+        self.samples = [output; N];
+        self.sum = output * T::from_f64(self.window as f64);
+        self.len = self.window;
+        self.head = 0;
+    }
+    fn update(&mut self, input: T, _: T) -> T {
+        if self.len == self.window {
+            self.sum = self.sum - self.samples[self.head];
+        }
+        self.samples[self.head] = input;
+        self.sum = self.sum + input;
+        self.head = (self.head + 1) % self.window;
+        self.len = (self.len + 1).min(self.window);
+        if self.head == 0 {
+            self.sum = self.samples[..self.window]
+                .iter()
+                .fold(T::zero(), |sum, &sample| sum + sample);
+        }
+        self.sum / T::from_f64(self.len as f64)
+    }
+}
+impl<T: Float, const N: usize> Default for MovingAverage<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Second-order section y[k] = b0 x[k] + b1 x[k-1] + b2 x[k-2]
+//                             - a1 y[k-1] - a2 y[k-2], in direct form I.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Biquad<T: Float> {
+    b: [T; 3],
+    a: [T; 2],
+    inputs: [T; 2],
+    outputs: [T; 2],
+}
+impl<T: Float> Biquad<T> {
+    // Coefficients normalized so that a0 = 1.
+    pub fn new(b: [T; 3], a: [T; 2]) -> Self {
+        Self {
+            b,
+            a,
+            inputs: [T::zero(); 2],
+            outputs: [T::zero(); 2],
+        }
+    }
+    // Band-stop with unity gain away from the center frequency, from the
+    // bilinear transform of s^2 + w0^2 / (s^2 + s w0 / Q + w0^2). Higher Q
+    // gives a narrower notch. The design is done once in f64.
+    pub fn notch(
+        center_frequency: f64,
+        q: f64,
+        sample_time: Duration,
+    ) -> Result<Self, ConfigError> {
+        let sample_rate = 1.0 / sample_time.as_secs_f64();
+        if !(center_frequency > 0.0 && center_frequency < sample_rate / 2.0) {
+            return Err(ConfigError::InvalidFilterFrequency);
+        }
+        if !(q > 0.0 && q.is_finite()) {
+            return Err(ConfigError::InvalidFilterQ);
+        }
+        let omega = core::f64::consts::TAU * center_frequency / sample_rate;
+        let (sin, cos) = math::sin_cos(omega);
+        let alpha = sin / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        let b0 = T::from_f64(1.0 / a0);
+        let b1 = T::from_f64(-2.0 * cos / a0);
+        Ok(Self::new(
+            [b0, b1, b0],
+            [b1, T::from_f64((1.0 - alpha) / a0)],
+        ))
+    }
+    // Band-pass with gain `gain` at the center frequency, falling off on
+    // either side, from the bilinear transform of
+    // gain (s w0 / Q) / (s^2 + s w0 / Q + w0^2), prewarped so the peak lands
+    // exactly on the center frequency.
+    pub fn resonant(
+        gain: f64,
+        center_frequency: f64,
+        q: f64,
+        sample_time: Duration,
+    ) -> Result<Self, ConfigError> {
+        let sample_rate = 1.0 / sample_time.as_secs_f64();
+        if !(center_frequency > 0.0 && center_frequency < sample_rate / 2.0) {
+            return Err(ConfigError::InvalidFilterFrequency);
+        }
+        if !(q > 0.0 && q.is_finite()) {
+            return Err(ConfigError::InvalidFilterQ);
+        }
+        let omega = core::f64::consts::TAU * center_frequency;
+        let (sin, cos) = math::sin_cos(omega / sample_rate / 2.0);
+        let k = omega * cos / sin;
+        let bandwidth = omega / q;
+        let a0 = k * k + bandwidth * k + omega * omega;
+        let b0 = gain * bandwidth * k / a0;
+        Ok(Self::new(
+            [T::from_f64(b0), T::zero(), T::from_f64(-b0)],
+            [
+                T::from_f64(2.0 * (omega * omega - k * k) / a0),
+                T::from_f64((k * k - bandwidth * k + omega * omega) / a0),
+            ],
+        ))
+    }
+    fn dc_gain(&self) -> T {
+        (self.b[0] + self.b[1] + self.b[2]) / (T::one() + self.a[0] + self.a[1])
+    }
+}
+impl<T: Float> Filter<T> for Biquad<T> {
+    fn init(&mut self) {
+        self.inputs = [T::zero(); 2];
+        self.outputs = [T::zero(); 2];
+    }
+    fn reset_to(&mut self, output: T) {
+        let dc_gain = self.dc_gain();
+        let input = if dc_gain == T::zero() {
+            T::zero()
+        } else {
+            output / dc_gain
+        };
+        self.inputs = [input; 2];
+        self.outputs = [output; 2];
+    }
+    fn update(&mut self, input: T, _: T) -> T {
+        let output = self.b[0] * input + self.b[1] * self.inputs[0] + self.b[2] * self.inputs[1]
+            - self.a[0] * self.outputs[0]
+            - self.a[1] * self.outputs[1];
+        self.inputs = [input, self.inputs[0]];
+        self.outputs = [output, self.outputs[0]];
+        output
+    }
+}
+
+// Flags inputs that change faster than `max_rate` per second since the last
+// accepted value and, when clipping, limits them to that rate. Clipped
+// values are what the next sample is compared against, so a genuine step
+// is followed at the maximum rate rather than rejected forever.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlewLimiter<T: Float> {
+    max_rate: T,
+    clip: bool,
+    previous: Option<T>,
+}
+impl<T: Float> SlewLimiter<T> {
+    pub const fn new(max_rate: T, clip: bool) -> Self {
+        Self {
+            max_rate,
+            clip,
+            previous: None,
+        }
+    }
+    pub fn init(&mut self) {
+        self.previous = None;
+    }
+    // Returns the passed or clipped value and whether the limit was exceeded.
+    pub fn update(&mut self, input: T, dt: T) -> (T, bool) {
+        let Some(previous) = self.previous else {
+            self.previous = Some(input);
+            return (input, false);
+        };
+        let max_change = self.max_rate * dt;
+        let change = input - previous;
+        let exceeded = change.abs() > max_change;
+        let output = if exceeded && self.clip {
+            if change > T::zero() {
+                previous + max_change
+            } else {
+                previous - max_change
+            }
+        } else {
+            input
+        };
+        self.previous = Some(output);
+        (output, exceeded)
+    }
+}
+
+// Accumulate-and-dump averaging for sensors sampled faster than the loop
+// runs: every raw sample between two control updates is summed and the
+// update uses their mean, a boxcar filter that is matched to the update
+// period and so rejects noise that would otherwise alias into the loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decimator<T: Float> {
+    sum: T,
+    count: u32,
+    last: Option<T>,
+}
+impl<T: Float> Decimator<T> {
+    pub fn new() -> Self {
+        Self::with_zero(T::zero())
+    }
+    // `new` for the const controller constructor.
+    pub(crate) const fn with_zero(zero: T) -> Self {
+        Self {
+            sum: zero,
+            count: 0,
+            last: None,
+        }
+    }
+    pub fn init(&mut self) {
+        *self = Self::new();
+    }
+    pub fn push(&mut self, sample: T) {
+        self.sum = self.sum + sample;
+        self.count += 1;
+    }
+    // Samples accumulated since the last `take`.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+    // The mean of the samples since the last call, or the previous mean if no
+    // new samples arrived; None until the first sample.
+    pub fn take(&mut self) -> Option<T> {
+        if self.count > 0 {
+            self.last = Some(self.sum / T::from_f64(self.count as f64));
+            self.sum = T::zero();
+            self.count = 0;
+        }
+        self.last
+    }
+}
+impl<T: Float> Default for Decimator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Controller, Differentiator, Integrator, OutputLimits, Proportional};
+
+    #[test]
+    fn chain_applies_stages_in_order_until_full() {
+        let mut chain = FilterChain::<f64, 2>::new();
+        chain.push(LeadLag::new(2.0, 0.0, 0.0)).unwrap();
+        chain.push(LeadLag::new(0.5, 0.0, 0.0)).unwrap();
+        assert_eq!(
+            chain.push(LowPass::new(1.0)),
+            Err(ConfigError::FilterChainFull)
+        );
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.update(3.0, 0.1), 3.0);
+
+        let copy = chain.clone();
+        assert_eq!(copy, chain);
+        chain.clear();
+        assert!(chain.is_empty());
+        assert_eq!(chain.update(3.0, 0.1), 3.0);
+        assert_ne!(copy, chain);
+    }
+
+    #[test]
+    fn median_window_removes_spikes_shorter_than_half() {
+        let mut chain = FilterChain::<f64>::new()
+            .with(Stage::median(3).unwrap())
+            .unwrap();
+        let outputs: [f64; 5] = [1.0, 1.0, 9.0, 1.0, 1.0].map(|x| chain.update(x, 0.1));
+        assert_eq!(outputs, [1.0, 1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(
+            Stage::<f64>::median(0),
+            Err(ConfigError::InvalidFilterWindow)
+        );
+        assert_eq!(
+            Stage::<f64>::median(MAX_STAGE_WINDOW + 1),
+            Err(ConfigError::InvalidFilterWindow)
+        );
+    }
+
+    #[test]
+    fn moving_average_uses_its_window() {
+        let mut filter = MovingAverage::<f64, 8>::with_window(2).unwrap();
+        assert_eq!(filter.update(2.0, 0.1), 2.0);
+        assert_eq!(filter.update(4.0, 0.1), 3.0);
+        assert_eq!(filter.update(8.0, 0.1), 6.0);
+        filter.reset_to(5.0);
+        assert_eq!(filter.update(7.0, 0.1), 6.0);
+    }
+
+    #[test]
+    fn low_pass_settles_on_a_step() {
+        let mut filter = LowPass::new(1.0);
+        assert_eq!(filter.update(0.0, 0.1), 0.0);
+        let mut value = 0.0;
+        for _ in 0..200 {
+            value = filter.update(1.0, 0.1);
+        }
+        assert!((value - 1.0_f64).abs() < 1e-6);
+    }
+
+    // Peak output over the last second of a unit sine at `frequency` Hz,
+    // sampled at 1 kHz for two seconds.
+    fn sine_amplitude(filter: &mut Biquad<f64>, frequency: f64) -> f64 {
+        let mut peak: f64 = 0.0;
+        for k in 0..2000 {
+            let (sin, _) = math::sin_cos(core::f64::consts::TAU * frequency * k as f64 / 1000.0);
+            let output = filter.update(sin, 0.001);
+            if k >= 1000 {
+                peak = peak.max(output.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn notch_removes_the_center_frequency_only() {
+        let notch = Biquad::notch(50.0, 2.0, Duration::from_millis(1)).unwrap();
+        assert!(sine_amplitude(&mut notch.clone(), 50.0) < 1e-3);
+        assert!(sine_amplitude(&mut notch.clone(), 5.0) > 0.99);
+        assert!(sine_amplitude(&mut notch.clone(), 250.0) > 0.99);
+        assert_eq!(
+            Biquad::<f64>::notch(500.0, 2.0, Duration::from_millis(1)),
+            Err(ConfigError::InvalidFilterFrequency)
+        );
+        assert_eq!(
+            Biquad::<f64>::notch(50.0, 0.0, Duration::from_millis(1)),
+            Err(ConfigError::InvalidFilterQ)
+        );
+    }
+
+    #[test]
+    fn slew_limiter_clips_in_both_directions() {
+        // At most 0.1 per 0.1 s step.
+        let mut limiter = SlewLimiter::new(1.0, true);
+        assert_eq!(limiter.update(1.0, 0.1), (1.0, false));
+        let (output, exceeded) = limiter.update(2.0, 0.1);
+        assert!((output - 1.1).abs() < 1e-12 && exceeded);
+        let (output, exceeded) = limiter.update(0.0, 0.1);
+        assert!((output - 1.0).abs() < 1e-12 && exceeded);
+        let (output, exceeded) = limiter.update(0.95, 0.1);
+        assert!((output - 0.95).abs() < 1e-12 && !exceeded);
+
+        // Without clipping the limit is only reported.
+        let mut limiter = SlewLimiter::new(1.0, false);
+        limiter.update(1.0, 0.1);
+        assert_eq!(limiter.update(-1.0, 0.1), (-1.0, true));
+        assert_eq!(limiter.update(-1.05, 0.1), (-1.05, false));
+        limiter.init();
+        assert_eq!(limiter.update(5.0, 0.1), (5.0, false));
+    }
+
+    #[test]
+    fn decimator_averages_each_batch_and_holds_between() {
+        let mut decimator = Decimator::new();
+        assert_eq!(decimator.take(), None);
+        // Four raw samples per update, with noise alternating in sign.
+        for sample in [1.5, 0.5, 1.25, 0.75] {
+            decimator.push(sample);
+        }
+        assert_eq!(decimator.count(), 4);
+        assert_eq!(decimator.take(), Some(1.0));
+        assert_eq!(decimator.count(), 0);
+        assert_eq!(decimator.take(), Some(1.0));
+        for sample in [3.0, 2.0] {
+            decimator.push(sample);
+        }
+        assert_eq!(decimator.take(), Some(2.5));
+        decimator.init();
+        assert_eq!(decimator.take(), None);
+    }
+
+    #[test]
+    fn decimated_controller_holds_until_the_first_sample() {
+        let mut controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(2.0),
+            Integrator::new(0.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        assert_eq!(controller.update_decimated(1.0), 0.0);
+        for sample in [0.0, 0.5, 0.25, 0.25] {
+            controller.push_measurement(sample);
+        }
+        assert_eq!(controller.update_decimated(1.0), 1.5);
+        assert_eq!(controller.update_decimated(2.0), 3.5);
+    }
+
+    #[test]
+    fn lead_lag_settles_on_its_gain() {
+        let dt = 0.01;
+        // Lead: the step overshoots by lead / lag before settling.
+        let lead: &mut dyn Filter<f64> = &mut LeadLag::new(2.0, 0.5, 0.1);
+        assert_eq!(lead.update(0.0, dt), 0.0);
+        let first = lead.update(1.0, dt);
+        assert!((first - 2.0 * 0.51 / 0.11).abs() < 1e-12);
+        let mut previous = first;
+        for _ in 0..200 {
+            let output = lead.update(1.0, dt);
+            assert!(output < previous && output > 2.0);
+            previous = output;
+        }
+        assert!((previous - 2.0).abs() < 1e-6);
+
+        // Lag: a partial step that rises to the gain.
+        let lag: &mut dyn Filter<f64> = &mut LeadLag::new(2.0, 0.1, 0.5);
+        lag.update(0.0, dt);
+        let first = lag.update(1.0, dt);
+        assert!((first - 2.0 * 0.11 / 0.51).abs() < 1e-12);
+        for _ in 0..2000 {
+            previous = lag.update(1.0, dt);
+        }
+        assert!((previous - 2.0).abs() < 1e-6);
+
+        lag.reset_to(3.0);
+        assert!((lag.update(1.5, dt) - 3.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{tuning::Gains, ControlLoop, ControlOutput, Controller, Float};
+
+// Rule tables indexed by the error set and then the error-rate set, each
+// negative, zero or positive. Entries are gain adjustments from -1 to 1,
+// scaled by the supervisor's adjustment range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FuzzyRules<T: Float> {
+    pub kp: [[T; 3]; 3],
+    pub ki: [[T; 3]; 3],
+    pub kd: [[T; 3]; 3],
+}
+impl<T: Float> FuzzyRules<T> {
+    // The usual heuristics: far from the setpoint raise kp and lower ki to
+    // close the gap quickly without winding up; near it lower kp and raise ki
+    // to settle without overshoot. kd is raised when the error is moving away
+    // from zero and lowered when it is already closing.
+    pub fn standard() -> Self {
+        let (n, z, p) = (T::negative(), T::zero(), T::one());
+        let h = T::half();
+        Self {
+            kp: [[p, h, z], [z, n, z], [z, h, p]],
+            ki: [[n, n, z], [z, p, z], [z, n, n]],
+            kd: [[p, z, n], [z, z, z], [n, z, p]],
+        }
+    }
+}
+
+// Triangular memberships of negative, zero and positive over a value
+// normalized to [-1, 1], saturating outside it; they always sum to one.
+fn memberships<T: Float>(value: T) -> [T; 3] {
+    let value = if value > T::one() {
+        T::one()
+    } else if value < T::negative() {
+        T::negative()
+    } else {
+        value
+    };
+    if value < T::zero() {
+        [T::zero() - value, T::one() + value, T::zero()]
+    } else {
+        [T::zero(), T::one() - value, value]
+    }
+}
+
+// A fuzzy supervisor over a controller: before each update it fuzzifies the
+// error and its rate, evaluates the rule tables with product inference and
+// weighted-average defuzzification, and sets each gain to its base value
+// times one plus the result times the adjustment range. Gain changes go
+// through the controller's bumpless setters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzySupervisor<T: Float> {
+    controller: Controller<T>,
+    base: Gains<T>,
+    rules: FuzzyRules<T>,
+    error_scale: T,
+    rate_scale: T,
+    range: T,
+    previous_error: Option<T>,
+}
+impl<T: Float> FuzzySupervisor<T> {
+    // The scales are the error and error rate considered large; the base
+    // gains are taken from the controller.
+    pub fn new(controller: Controller<T>, error_scale: T, rate_scale: T) -> Self {
+        let base = Gains::new(controller.p.gain, controller.i.gain, controller.d.gain);
+        Self {
+            controller,
+            base,
+            rules: FuzzyRules::standard(),
+            error_scale,
+            rate_scale,
+            range: T::half(),
+            previous_error: None,
+        }
+    }
+    pub fn controller(&self) -> &Controller<T> {
+        &self.controller
+    }
+    pub fn controller_mut(&mut self) -> &mut Controller<T> {
+        &mut self.controller
+    }
+    pub fn set_base_gains(&mut self, base: Gains<T>) {
+        self.base = base;
+    }
+    pub fn set_rules(&mut self, rules: FuzzyRules<T>) {
+        self.rules = rules;
+    }
+    // The largest relative change of any gain, 0.5 by default.
+    pub fn set_adjustment_range(&mut self, range: T) {
+        self.range = range;
+    }
+    pub fn init(&mut self) {
+        self.controller.init();
+        self.controller.set_gains(&self.base);
+        self.previous_error = None;
+    }
+    pub fn gains(&self, error: T, rate: T) -> Gains<T> {
+        let error = memberships(error / self.error_scale);
+        let rate = memberships(rate / self.rate_scale);
+        let infer = |table: &[[T; 3]; 3]| {
+            let mut adjustment = T::zero();
+            for (row, &error) in error.iter().enumerate() {
+                for (column, &rate) in rate.iter().enumerate() {
+                    adjustment = adjustment + error * rate * table[row][column];
+                }
+            }
+            T::one() + self.range * adjustment
+        };
+        Gains::new(
+            self.base.kp * infer(&self.rules.kp),
+            self.base.ki * infer(&self.rules.ki),
+            self.base.kd * infer(&self.rules.kd),
+        )
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        self.update_detailed(setpoint, measurement).output
+    }
+    pub fn update_detailed(&mut self, setpoint: T, measurement: T) -> ControlOutput<T> {
+        let error = setpoint - measurement;
+        let dt = T::from_duration(&self.controller.sample_time);
+        let rate = match self.previous_error.replace(error) {
+            Some(previous_error) => (error - previous_error) / dt,
+            None => T::zero(),
+        };
+        let gains = self.gains(error, rate);
+        self.controller.set_gains(&gains);
+        self.controller.update_detailed(setpoint, measurement)
+    }
+}
+impl<T: Float> ControlLoop<T> for FuzzySupervisor<T> {
+    fn init(&mut self) {
+        FuzzySupervisor::init(self);
+    }
+    fn output(&self) -> T {
+        self.controller.output()
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        FuzzySupervisor::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::{Differentiator, Integrator, OutputLimits, Proportional};
+
+    fn supervisor() -> FuzzySupervisor<f64> {
+        let controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(100),
+            Proportional::new(2.0),
+            Integrator::new(1.0, OutputLimits::Unbounded),
+            Differentiator::new(0.4, 0.0),
+        );
+        FuzzySupervisor::new(controller, 1.0, 10.0)
+    }
+
+    #[test]
+    fn memberships_partition_the_range() {
+        assert_eq!(memberships(-2.0), [1.0, 0.0, 0.0]);
+        assert_eq!(memberships(-0.25), [0.25, 0.75, 0.0]);
+        assert_eq!(memberships(0.0), [0.0, 1.0, 0.0]);
+        assert_eq!(memberships(0.5), [0.0, 0.5, 0.5]);
+        assert_eq!(memberships(3.0), [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn rules_adjust_the_base_gains() {
+        let supervisor = supervisor();
+        // Settled: lower kp, raise ki.
+        assert_eq!(supervisor.gains(0.0, 0.0), Gains::new(1.0, 1.5, 0.4));
+        // Far away and not moving: raise kp, lower ki.
+        assert_eq!(supervisor.gains(5.0, 0.0), Gains::new(2.5, 0.5, 0.4));
+        // Far away and closing: lower kd.
+        assert_eq!(supervisor.gains(-5.0, 20.0), Gains::new(2.0, 1.0, 0.2));
+        // Halfway, the rules blend.
+        assert_eq!(supervisor.gains(0.5, 0.0).kp, 2.0 * 0.875);
+    }
+
+    #[test]
+    fn updates_apply_the_inferred_gains() {
+        let mut supervisor = supervisor();
+        supervisor.update(1.0, 0.0);
+        let rate = (0.5 - 1.0) / 0.1;
+        let expected = supervisor.gains(0.5, rate);
+        supervisor.update(1.0, 0.5);
+        assert_eq!(supervisor.controller().p.gain, expected.kp);
+        assert_eq!(supervisor.controller().i.gain, expected.ki);
+        assert_eq!(supervisor.controller().d.gain, expected.kd);
+        supervisor.init();
+        assert_eq!(supervisor.controller().p.gain, 2.0);
+    }
+}
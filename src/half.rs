@@ -0,0 +1,275 @@
+use core::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+    time::Duration,
+};
+
+use crate::{ConstFloat, Float};
+
+// Rounds an f64 to the nearest value with the given exponent and mantissa
+// widths, ties to even, in a single step so there is no double rounding
+// through f32.
+fn narrow(value: f64, exponent_bits: u32, mantissa_bits: u32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 63) as u16) << 15;
+    let exponent = ((bits >> 52) & 0x7ff) as i64;
+    let mantissa = bits & ((1 << 52) - 1);
+    let max_exponent = (1i64 << exponent_bits) - 1;
+    let infinity = (max_exponent as u16) << mantissa_bits;
+    if exponent == 0x7ff {
+        let quiet = if mantissa == 0 {
+            0
+        } else {
+            1 << (mantissa_bits - 1)
+        };
+        return sign | infinity | quiet;
+    }
+    let exponent = exponent - 1023 + (max_exponent >> 1);
+    if exponent >= max_exponent {
+        return sign | infinity;
+    }
+    // Results below the normal range become subnormals: the implicit leading
+    // bit is made explicit and shifted further right.
+    let (significand, shift) = if exponent <= 0 {
+        (mantissa | 1 << 52, 52 - mantissa_bits as i64 + 1 - exponent)
+    } else {
+        (mantissa, 52 - mantissa_bits as i64)
+    };
+    if shift > 53 {
+        return sign;
+    }
+    let shift = shift as u32;
+    let truncated = significand >> shift;
+    let remainder = significand & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round_up = remainder > halfway || (remainder == halfway && truncated & 1 == 1);
+    let biased = if exponent <= 0 {
+        0
+    } else {
+        (exponent as u64) << mantissa_bits
+    };
+    // A carry out of the mantissa correctly bumps the exponent, up to infinity.
+    sign | (biased + truncated + round_up as u64) as u16
+}
+
+fn widen(bits: u16, exponent_bits: u32, mantissa_bits: u32) -> f64 {
+    let negative = bits >> 15 == 1;
+    let max_exponent = (1u64 << exponent_bits) - 1;
+    let exponent = (bits as u64 >> mantissa_bits) & max_exponent;
+    let mantissa = bits as u64 & ((1 << mantissa_bits) - 1);
+    let bias = (max_exponent >> 1) as i64;
+    let magnitude = if exponent == max_exponent {
+        if mantissa == 0 {
+            f64::INFINITY
+        } else {
+            f64::NAN
+        }
+    } else if exponent == 0 {
+        let scale = f64::from_bits(((1 - bias - mantissa_bits as i64 + 1023) as u64) << 52);
+        mantissa as f64 * scale
+    } else {
+        f64::from_bits(
+            ((exponent as i64 - bias + 1023) as u64) << 52 | mantissa << (52 - mantissa_bits),
+        )
+    };
+    if negative {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+macro_rules! half_float {
+    ($name:ident, $exponent_bits:expr, $mantissa_bits:expr) => {
+        #[derive(Clone, Copy, Default)]
+        pub struct $name(u16);
+        impl $name {
+            pub const fn from_bits(bits: u16) -> Self {
+                Self(bits)
+            }
+            pub const fn to_bits(self) -> u16 {
+                self.0
+            }
+            pub fn from_f32(value: f32) -> Self {
+                Self(narrow(value as f64, $exponent_bits, $mantissa_bits))
+            }
+            pub fn to_f32(self) -> f32 {
+                self.to_f64() as f32
+            }
+            pub fn to_f64(self) -> f64 {
+                widen(self.0, $exponent_bits, $mantissa_bits)
+            }
+        }
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.to_f32() == other.to_f32()
+            }
+        }
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                self.to_f32().partial_cmp(&other.to_f32())
+            }
+        }
+        // Each operation is carried out in f32 and rounded once; f32 has more
+        // than twice the precision, so the result is correctly rounded.
+        impl Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                Self::from_f32(self.to_f32() + rhs.to_f32())
+            }
+        }
+        impl Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                Self::from_f32(self.to_f32() - rhs.to_f32())
+            }
+        }
+        impl Mul for $name {
+            type Output = Self;
+            fn mul(self, rhs: Self) -> Self {
+                Self::from_f32(self.to_f32() * rhs.to_f32())
+            }
+        }
+        impl Div for $name {
+            type Output = Self;
+            fn div(self, rhs: Self) -> Self {
+                Self::from_f32(self.to_f32() / rhs.to_f32())
+            }
+        }
+        impl Neg for $name {
+            type Output = Self;
+            fn neg(self) -> Self {
+                Self(self.0 ^ 0x8000)
+            }
+        }
+        impl Float for $name {
+            fn negative() -> Self {
+                Self::from_f64(-1.0)
+            }
+            fn double() -> Self {
+                Self::from_f64(2.0)
+            }
+            fn one() -> Self {
+                Self::from_f64(1.0)
+            }
+            fn half() -> Self {
+                Self::from_f64(0.5)
+            }
+            fn zero() -> Self {
+                Self(0)
+            }
+            fn from_f64(value: f64) -> Self {
+                Self(narrow(value, $exponent_bits, $mantissa_bits))
+            }
+            fn ln(self) -> Self {
+                Self::from_f64(Float::ln(self.to_f64()))
+            }
+            fn sqrt(self) -> Self {
+                Self::from_f64(Float::sqrt(self.to_f64()))
+            }
+            fn floor(self) -> Self {
+                Self::from_f64(Float::floor(self.to_f64()))
+            }
+            fn abs(self) -> Self {
+                Self(self.0 & 0x7fff)
+            }
+            // A non-zero sample time must stay non-zero and finite, so it is
+            // clamped to the representable range instead of flushing to zero
+            // or overflowing to infinity.
+            fn from_duration(dur: &Duration) -> Self {
+                if dur.is_zero() {
+                    return Self(0);
+                }
+                let seconds = Self::from_f64(dur.as_secs_f64());
+                if seconds.0 == 0 {
+                    Self(1)
+                } else if !seconds.is_finite() {
+                    Self((((1u16 << $exponent_bits) - 1) << $mantissa_bits) - 1)
+                } else {
+                    seconds
+                }
+            }
+        }
+        impl ConstFloat for $name {
+            const ZERO: Self = Self(0);
+        }
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.to_f32(), f)
+            }
+        }
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.to_f32(), f)
+            }
+        }
+    };
+}
+
+// IEEE 754 binary16: 5 exponent bits, 10 mantissa bits, largest finite 65504.
+half_float!(F16, 5, 10);
+// bfloat16: the f32 exponent range with 7 mantissa bits.
+half_float!(Bf16, 8, 7);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary16_rounds_to_nearest_even() {
+        assert_eq!(F16::from_f64(1.0).to_bits(), 0x3c00);
+        assert_eq!(F16::from_f64(65504.0).to_bits(), 0x7bff);
+        // Halfway between the largest finite value and the next binade.
+        assert_eq!(F16::from_f64(65520.0).to_bits(), 0x7c00);
+        assert_eq!(F16::from_f64(-2f64.powi(-24)).to_bits(), 0x8001);
+        assert_eq!(F16::from_f64(2f64.powi(-25)).to_bits(), 0);
+        assert_eq!(F16::from_f64(3.0 * 2f64.powi(-26)).to_bits(), 0x0001);
+        assert_eq!(F16::from_f64(1.0 + 2f64.powi(-11)).to_bits(), 0x3c00);
+        assert_eq!(F16::from_f64(1.0 + 3.0 * 2f64.powi(-11)).to_bits(), 0x3c02);
+        assert!(F16::from_f64(f64::NAN).to_f64().is_nan());
+        assert_eq!(F16::from_f64(f64::NEG_INFINITY).to_f64(), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn bfloat16_rounds_to_nearest_even() {
+        assert_eq!(Bf16::from_f32(1.0).to_bits(), 0x3f80);
+        assert_eq!(Bf16::from_f32(1.0 + 2f32.powi(-8)).to_bits(), 0x3f80);
+        assert_eq!(Bf16::from_f32(1.0 + 3.0 * 2f32.powi(-8)).to_bits(), 0x3f82);
+        assert_eq!(Bf16::from_f32(f32::MAX).to_bits(), 0x7f80);
+        // The f32 range is kept at 8 bits of precision.
+        assert!((Bf16::from_f64(1e30).to_f64() / 1e30 - 1.0).abs() < 2f64.powi(-8));
+    }
+
+    #[test]
+    fn every_binary16_value_round_trips() {
+        for bits in 0..=u16::MAX {
+            let value = F16::from_bits(bits);
+            if value.to_f64().is_nan() {
+                continue;
+            }
+            assert_eq!(F16::from_f64(value.to_f64()).to_bits(), bits);
+        }
+    }
+
+    #[test]
+    fn arithmetic_and_sample_times_stay_representable() {
+        let sum = F16::from_f64(1.5) + F16::from_f64(2.25);
+        assert_eq!(sum.to_f32(), 3.75);
+        assert_eq!((F16::from_f64(3.0) / F16::from_f64(4.0)).to_f32(), 0.75);
+        assert_eq!((-F16::one()).to_f32(), -1.0);
+        assert_eq!(F16::negative().abs(), F16::one());
+        assert!(F16::from_f64(2.5).floor() == F16::double());
+
+        assert_eq!(F16::from_duration(&Duration::ZERO).to_bits(), 0);
+        assert_eq!(F16::from_duration(&Duration::from_nanos(1)).to_bits(), 1);
+        assert_eq!(
+            F16::from_duration(&Duration::from_secs(100_000)).to_f32(),
+            65504.0
+        );
+        assert_eq!(
+            F16::from_duration(&Duration::from_millis(250)).to_f32(),
+            0.25
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use core::time::Duration;
+
+use crate::{
+    telemetry::{Snapshot, TelemetrySink},
+    Float,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record<T: Float> {
+    // Time since the first update after the controller was initialized,
+    // including any stalls.
+    pub timestamp: Duration,
+    // The time step the update integrated over.
+    pub dt: Duration,
+    pub setpoint: T,
+    pub measurement: T,
+    pub p: T,
+    pub i: T,
+    pub d: T,
+    pub output: T,
+}
+
+// The last N updates, for post-mortem analysis of an incident. The records
+// live in an array, so nothing is allocated after construction and the
+// oldest record is overwritten once the history is full. Attach it with
+// `Controller::attach_sink`, or push records from any other update loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct History<T: Float, const N: usize> {
+    records: [Record<T>; N],
+    // Index of the oldest record.
+    head: usize,
+    len: usize,
+}
+impl<T: Float, const N: usize> History<T, N> {
+    pub fn new() -> Self {
+        Self {
+            records: [Record {
+                timestamp: Duration::ZERO,
+                dt: Duration::ZERO,
+                setpoint: T::zero(),
+                measurement: T::zero(),
+                p: T::zero(),
+                i: T::zero(),
+                d: T::zero(),
+                output: T::zero(),
+            }; N],
+            head: 0,
+            len: 0,
+        }
+    }
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+    pub fn push(&mut self, record: Record<T>) {
+        if N == 0 {
+            return;
+        }
+        if self.len < N {
+            self.records[(self.head + self.len) % N] = record;
+            self.len += 1;
+        } else {
+            self.records[self.head] = record;
+            self.head = (self.head + 1) % N;
+        }
+    }
+    // The record `index` places after the oldest one.
+    pub fn get(&self, index: usize) -> Option<&Record<T>> {
+        (index < self.len).then(|| &self.records[(self.head + index) % N])
+    }
+    pub fn oldest(&self) -> Option<&Record<T>> {
+        self.get(0)
+    }
+    pub fn latest(&self) -> Option<&Record<T>> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+    // Oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Record<T>> + '_ {
+        (0..self.len).map(|index| &self.records[(self.head + index) % N])
+    }
+    // The records at or after `timestamp`, oldest first.
+    pub fn since(&self, timestamp: Duration) -> impl Iterator<Item = &Record<T>> + '_ {
+        self.iter()
+            .skip_while(move |record| record.timestamp < timestamp)
+    }
+}
+impl<T: Float, const N: usize> Default for History<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T: Float, const N: usize> TelemetrySink<T> for History<T, N> {
+    fn record(&mut self, snapshot: &Snapshot<T>) {
+        let output = &snapshot.output;
+        self.push(Record {
+            timestamp: snapshot.time,
+            dt: snapshot.dt,
+            setpoint: output.setpoint,
+            measurement: output.measurement,
+            p: output.p,
+            i: output.i,
+            d: output.d,
+            output: output.output,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Controller, Differentiator, Integrator, OutputLimits, Proportional};
+
+    fn record(millis: u64) -> Record<f64> {
+        Record {
+            timestamp: Duration::from_millis(millis),
+            dt: Duration::from_millis(1),
+            setpoint: 1.0,
+            measurement: 0.0,
+            p: 0.0,
+            i: 0.0,
+            d: 0.0,
+            output: millis as f64,
+        }
+    }
+
+    #[test]
+    fn oldest_records_are_overwritten() {
+        let mut history = History::<f64, 3>::new();
+        assert!(history.is_empty());
+        assert_eq!(history.latest(), None);
+        for millis in 0..5 {
+            history.push(record(millis));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.capacity(), 3);
+        let outputs: [f64; 3] = core::array::from_fn(|index| history.get(index).unwrap().output);
+        assert_eq!(outputs, [2.0, 3.0, 4.0]);
+        assert_eq!(history.oldest(), Some(&record(2)));
+        assert_eq!(history.latest(), Some(&record(4)));
+        assert_eq!(history.get(3), None);
+        assert_eq!(history.iter().next_back(), Some(&record(4)));
+        assert_eq!(history.since(Duration::from_millis(3)).count(), 2);
+        history.clear();
+        assert_eq!(history.iter().count(), 0);
+
+        let mut empty = History::<f64, 0>::new();
+        empty.push(record(0));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn records_controller_updates_as_a_sink() {
+        let controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(2.0),
+            Integrator::new(0.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        let mut monitored = controller.attach_sink(History::<f64, 4>::new());
+        for step in 0..6 {
+            monitored.update(1.0, step as f64 * 0.1);
+        }
+        let history = monitored.sink();
+        assert_eq!(history.len(), 4);
+        let latest = history.latest().unwrap();
+        assert_eq!(latest.timestamp, Duration::from_millis(50));
+        assert!((latest.measurement - 0.5).abs() < 1e-12);
+        assert!((latest.output - 1.0).abs() < 1e-12);
+        assert_eq!(
+            history.oldest().unwrap().timestamp,
+            Duration::from_millis(20)
+        );
+    }
+}
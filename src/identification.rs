@@ -0,0 +1,308 @@
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::time::Duration;
+
+use crate::{simulation::Plant, tuning::Fopdt, Float};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepMethod {
+    Tangent,
+    TwoPoint,
+}
+
+// Time at which the normalized response first reaches `fraction`, linearly
+// interpolated between samples.
+fn crossing_time<T: Float>(time: &[T], response: &[T], fraction: T) -> Option<T> {
+    for k in 1..response.len() {
+        if response[k] >= fraction && response[k - 1] < fraction {
+            let ratio = (fraction - response[k - 1]) / (response[k] - response[k - 1]);
+            return Some(time[k - 1] + ratio * (time[k] - time[k - 1]));
+        }
+    }
+    None
+}
+
+pub fn identify_step<T: Float>(
+    time: &[T],
+    input: &[T],
+    output: &[T],
+    method: StepMethod,
+) -> Option<Fopdt<T>> {
+    let n = time.len().min(input.len()).min(output.len());
+    if n < 3 {
+        return None;
+    }
+    let step = (1..n).find(|&k| input[k] != input[0])?;
+    let input_change = input[n - 1] - input[0];
+    let initial = output[0];
+    let output_change = output[n - 1] - initial;
+    if input_change == T::zero() || output_change == T::zero() {
+        return None;
+    }
+
+    let time = &time[step..n];
+    let response: Vec<T> = output[step..n]
+        .iter()
+        .map(|&value| (value - initial) / output_change)
+        .collect();
+    let step_time = time[0];
+
+    let (dead_time, time_constant) = match method {
+        StepMethod::Tangent => {
+            let mut steepest: Option<(T, T, T)> = None;
+            for k in 0..response.len() - 1 {
+                let dt = time[k + 1] - time[k];
+                if dt <= T::zero() {
+                    continue;
+                }
+                let slope = (response[k + 1] - response[k]) / dt;
+                if steepest.is_none_or(|(best, _, _)| slope > best) {
+                    steepest = Some((
+                        slope,
+                        T::half() * (time[k] + time[k + 1]),
+                        T::half() * (response[k] + response[k + 1]),
+                    ));
+                }
+            }
+            let (slope, inflection_time, inflection_value) = steepest?;
+            if slope <= T::zero() {
+                return None;
+            }
+            let start = inflection_time - inflection_value / slope;
+            let end = inflection_time + (T::one() - inflection_value) / slope;
+            (start - step_time, end - start)
+        }
+        StepMethod::TwoPoint => {
+            let t28 = crossing_time(time, &response, T::from_f64(0.283))?;
+            let t63 = crossing_time(time, &response, T::from_f64(0.632))?;
+            let time_constant = T::from_f64(1.5) * (t63 - t28);
+            (t63 - time_constant - step_time, time_constant)
+        }
+    };
+
+    let dead_time = if dead_time < T::zero() {
+        T::zero()
+    } else {
+        dead_time
+    };
+    Some(Fopdt::new(
+        output_change / input_change,
+        time_constant,
+        dead_time,
+    ))
+}
+
+// Solves A x = b in place by Gaussian elimination with partial pivoting.
+fn solve<T: Float>(mut matrix: Vec<Vec<T>>, mut vector: Vec<T>) -> Option<Vec<T>> {
+    let n = vector.len();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|&a, &b| {
+            matrix[a][column]
+                .abs()
+                .partial_cmp(&matrix[b][column].abs())
+                .unwrap_or(core::cmp::Ordering::Equal)
+        })?;
+        if matrix[pivot][column] == T::zero() {
+            return None;
+        }
+        matrix.swap(column, pivot);
+        vector.swap(column, pivot);
+        let pivot_row = matrix[column].clone();
+        for row in column + 1..n {
+            let factor = matrix[row][column] / pivot_row[column];
+            for (value, pivot) in matrix[row].iter_mut().zip(&pivot_row).skip(column) {
+                *value = *value - factor * *pivot;
+            }
+            vector[row] = vector[row] - factor * vector[column];
+        }
+    }
+    let mut solution = vec![T::zero(); n];
+    for row in (0..n).rev() {
+        let mut sum = vector[row];
+        for k in row + 1..n {
+            sum = sum - matrix[row][k] * solution[k];
+        }
+        solution[row] = sum / matrix[row][row];
+    }
+    Some(solution)
+}
+
+// y[k] = a_1 y[k-1] + ... + a_na y[k-na] + b_0 u[k-delay] + ... + b_(nb-1) u[k-delay-nb+1]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arx<T: Float> {
+    a: Vec<T>,
+    b: Vec<T>,
+    delay: usize,
+    outputs: VecDeque<T>,
+    inputs: VecDeque<T>,
+}
+impl<T: Float> Arx<T> {
+    pub fn new(a: Vec<T>, b: Vec<T>, delay: usize) -> Self {
+        let delay = delay.max(1);
+        Self {
+            outputs: VecDeque::from(vec![T::zero(); a.len()]),
+            inputs: VecDeque::from(vec![T::zero(); delay + b.len() - 1]),
+            a,
+            b,
+            delay,
+        }
+    }
+    pub fn fit(input: &[T], output: &[T], na: usize, nb: usize, delay: usize) -> Option<Self> {
+        let n = input.len().min(output.len());
+        if nb == 0 || delay == 0 {
+            return None;
+        }
+        let start = na.max(delay + nb - 1);
+        let parameters = na + nb;
+        if n <= start + parameters {
+            return None;
+        }
+        let mut normal = vec![vec![T::zero(); parameters]; parameters];
+        let mut projection = vec![T::zero(); parameters];
+        let mut regressor = vec![T::zero(); parameters];
+        for k in start..n {
+            for i in 0..na {
+                regressor[i] = output[k - 1 - i];
+            }
+            for j in 0..nb {
+                regressor[na + j] = input[k - delay - j];
+            }
+            for row in 0..parameters {
+                for column in 0..parameters {
+                    normal[row][column] = normal[row][column] + regressor[row] * regressor[column];
+                }
+                projection[row] = projection[row] + regressor[row] * output[k];
+            }
+        }
+        let mut theta = solve(normal, projection)?;
+        let b = theta.split_off(na);
+        Some(Self::new(theta, b, delay))
+    }
+    pub fn a(&self) -> &[T] {
+        &self.a
+    }
+    pub fn b(&self) -> &[T] {
+        &self.b
+    }
+    pub fn delay(&self) -> usize {
+        self.delay
+    }
+}
+// The model runs at the rate it was identified at, so the simulation sample
+// time must match the logging interval.
+impl<T: Float> Plant<T> for Arx<T> {
+    fn init(&mut self) {
+        self.outputs.iter_mut().for_each(|value| *value = T::zero());
+        self.inputs.iter_mut().for_each(|value| *value = T::zero());
+    }
+    fn update(&mut self, input: T, _: &Duration) -> T {
+        self.inputs.push_front(input);
+        let mut output = T::zero();
+        for (coefficient, previous) in self.a.iter().zip(&self.outputs) {
+            output = output + *coefficient * *previous;
+        }
+        for (coefficient, previous) in self.b.iter().zip(self.inputs.iter().skip(self.delay - 1)) {
+            output = output + *coefficient * *previous;
+        }
+        self.inputs.truncate(self.delay + self.b.len() - 1);
+        self.outputs.push_front(output);
+        self.outputs.truncate(self.a.len());
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A unit step at t = 1 s into 2 e^(-0.5 s) / (s + 1), sampled every 10 ms.
+    fn step_response() -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let time: Vec<f64> = (0..1500).map(|k| k as f64 * 0.01).collect();
+        let input = time
+            .iter()
+            .map(|&t| if t >= 1.0 { 1.0 } else { 0.0 })
+            .collect();
+        let output = time
+            .iter()
+            .map(|&t| {
+                if t > 1.5 {
+                    2.0 * (1.0 - (1.5 - t).exp())
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        (time, input, output)
+    }
+
+    #[test]
+    fn step_methods_recover_a_first_order_model() {
+        let (time, input, output) = step_response();
+        for method in [StepMethod::Tangent, StepMethod::TwoPoint] {
+            let model = identify_step(&time, &input, &output, method).unwrap();
+            assert!((model.gain - 2.0).abs() < 1e-3);
+            assert!((model.time_constant - 1.0).abs() < 0.02);
+            assert!((model.dead_time - 0.5).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn step_identification_needs_a_step() {
+        let (time, input, output) = step_response();
+        let flat = vec![0.0; time.len()];
+        assert!(identify_step(&time, &flat, &output, StepMethod::TwoPoint).is_none());
+        assert!(identify_step(&time, &input, &flat, StepMethod::Tangent).is_none());
+        assert!(
+            identify_step(&time[..2], &input[..2], &output[..2], StepMethod::Tangent).is_none()
+        );
+    }
+
+    #[test]
+    fn arx_fit_recovers_the_model_and_replays_the_data() {
+        let mut seed = 12345u32;
+        // The first inputs are zero so the record starts at rest.
+        let input: Vec<f64> = (0..200)
+            .map(|k| {
+                if k < 3 {
+                    return 0.0;
+                }
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                if seed & 0x10000 == 0 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            })
+            .collect();
+        let mut output = vec![0.0; input.len()];
+        for k in 3..input.len() {
+            output[k] =
+                1.2 * output[k - 1] - 0.5 * output[k - 2] + 0.3 * input[k - 2] + 0.1 * input[k - 3];
+        }
+
+        let mut model = Arx::fit(&input, &output, 2, 2, 2).unwrap();
+        for (fitted, exact) in model.a().iter().zip([1.2, -0.5]) {
+            assert!((fitted - exact).abs() < 1e-9);
+        }
+        for (fitted, exact) in model.b().iter().zip([0.3, 0.1]) {
+            assert!((fitted - exact).abs() < 1e-9);
+        }
+        assert_eq!(model.delay(), 2);
+
+        // Feeding u[k] to the plant returns y[k + 1].
+        let sample_time = Duration::from_millis(10);
+        model.init();
+        for k in 0..input.len() - 1 {
+            let replayed = model.update(input[k], &sample_time);
+            assert!((replayed - output[k + 1]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn arx_fit_rejects_degenerate_requests() {
+        let input = [1.0, -1.0, 1.0, 1.0, -1.0, -1.0];
+        let output = [0.0; 6];
+        assert!(Arx::fit(&input, &output, 1, 0, 1).is_none());
+        assert!(Arx::fit(&input, &output, 1, 1, 0).is_none());
+        assert!(Arx::fit(&input[..3], &output[..3], 2, 2, 1).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::ConfigError;
+
+// Gains are integers in units of 1/scale, so with a scale of 1000 a kp of
+// 1500 means 1.5 output units per unit of error. The sample time is folded in
+// ahead of time: ki is the integral gain times the sample time and kd is the
+// derivative gain divided by it. All intermediate arithmetic is done in i64
+// with saturation, and the integral keeps its sub-unit remainder.
+//
+// The scale must be positive. `new` panics otherwise, which in a const item
+// is a compile error; `try_new` reports it instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegerGains {
+    kp: i32,
+    ki: i32,
+    kd: i32,
+    scale: i32,
+}
+impl IntegerGains {
+    pub const fn new(kp: i32, ki: i32, kd: i32, scale: i32) -> Self {
+        assert!(scale > 0, "gain scale must be positive");
+        Self { kp, ki, kd, scale }
+    }
+    pub const fn try_new(kp: i32, ki: i32, kd: i32, scale: i32) -> Result<Self, ConfigError> {
+        if scale <= 0 {
+            return Err(ConfigError::NonPositiveGainScale);
+        }
+        Ok(Self { kp, ki, kd, scale })
+    }
+    pub const fn kp(&self) -> i32 {
+        self.kp
+    }
+    pub const fn ki(&self) -> i32 {
+        self.ki
+    }
+    pub const fn kd(&self) -> i32 {
+        self.kd
+    }
+    pub const fn scale(&self) -> i32 {
+        self.scale
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegerController {
+    gains: IntegerGains,
+    lower: i32,
+    upper: i32,
+    // Accumulated integral action, in output units times the gain scale.
+    integral: i64,
+    previous_measurement: Option<i32>,
+    out: i32,
+}
+impl IntegerController {
+    pub fn new(gains: IntegerGains, lower: i32, upper: i32) -> Self {
+        Self {
+            gains,
+            lower,
+            upper,
+            integral: 0,
+            previous_measurement: None,
+            out: 0,
+        }
+    }
+    pub fn try_new(gains: IntegerGains, lower: i32, upper: i32) -> Result<Self, ConfigError> {
+        if upper < lower {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        Ok(Self::new(gains, lower, upper))
+    }
+    pub fn init(&mut self) {
+        self.integral = 0;
+        self.previous_measurement = None;
+        self.out = 0;
+    }
+    pub fn output(&self) -> i32 {
+        self.out
+    }
+    pub fn gains(&self) -> IntegerGains {
+        self.gains
+    }
+    // Rescales the stored integral so the integral action, and with it the
+    // output, does not jump when the scale changes.
+    pub fn set_gains(&mut self, gains: IntegerGains) {
+        self.integral = self
+            .integral
+            .saturating_mul(gains.scale as i64)
+            .saturating_div(self.gains.scale as i64);
+        self.gains = gains;
+    }
+    pub fn set_output_limits(&mut self, lower: i32, upper: i32) -> Result<(), ConfigError> {
+        if upper < lower {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        self.lower = lower;
+        self.upper = upper;
+        self.clamp_integral();
+        Ok(())
+    }
+    // Integral action in output units.
+    pub fn integral(&self) -> i32 {
+        self.descale(self.integral)
+    }
+    pub fn set_integral(&mut self, integral: i32) {
+        self.integral = (integral as i64).saturating_mul(self.gains.scale as i64);
+        self.clamp_integral();
+    }
+    fn clamp_integral(&mut self) {
+        let scale = self.gains.scale as i64;
+        self.integral = self
+            .integral
+            .clamp(self.lower as i64 * scale, self.upper as i64 * scale);
+    }
+    // Rounds to the nearest output unit, halves away from zero, and saturates
+    // to the output limits.
+    fn descale(&self, value: i64) -> i32 {
+        let scale = self.gains.scale as i64;
+        let half = scale / 2;
+        let rounded = if value < 0 {
+            value.saturating_sub(half) / scale
+        } else {
+            value.saturating_add(half) / scale
+        };
+        rounded.clamp(self.lower as i64, self.upper as i64) as i32
+    }
+    pub fn update(&mut self, setpoint: i32, measurement: i32) -> i32 {
+        let error = setpoint as i64 - measurement as i64;
+        let p = (self.gains.kp as i64).saturating_mul(error);
+        self.integral = self
+            .integral
+            .saturating_add((self.gains.ki as i64).saturating_mul(error));
+        self.clamp_integral();
+        // Derivative on measurement, skipped on the first update.
+        let d = match self.previous_measurement.replace(measurement) {
+            Some(previous) => {
+                (self.gains.kd as i64).saturating_mul(previous as i64 - measurement as i64)
+            }
+            None => 0,
+        };
+        self.out = self.descale(p.saturating_add(self.integral).saturating_add(d));
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_non_positive_scale() {
+        assert_eq!(
+            IntegerGains::try_new(1, 1, 1, 0),
+            Err(ConfigError::NonPositiveGainScale)
+        );
+        assert_eq!(
+            IntegerGains::try_new(1, 1, 1, -10),
+            Err(ConfigError::NonPositiveGainScale)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "gain scale must be positive")]
+    fn new_panics_on_zero_scale() {
+        let _ = IntegerGains::new(1, 1, 1, 0);
+    }
+
+    #[test]
+    fn proportional_action_rounds_to_output_units() {
+        // kp = 1.5
+        let mut controller = IntegerController::new(IntegerGains::new(1500, 0, 0, 1000), -100, 100);
+        assert_eq!(controller.update(3, 0), 5);
+        assert_eq!(controller.update(-3, 0), -5);
+        assert_eq!(controller.update(1000, 0), 100);
+    }
+
+    #[test]
+    fn integral_keeps_remainder_and_survives_rescaling() {
+        // ki * dt = 0.25 per unit of error per update.
+        let mut controller = IntegerController::new(IntegerGains::new(0, 250, 0, 1000), -100, 100);
+        for _ in 0..3 {
+            controller.update(1, 0);
+        }
+        assert_eq!(controller.integral(), 1);
+        controller.update(1, 0);
+        assert_eq!(controller.integral(), 1);
+        controller.update(1, 0);
+        controller.update(1, 0);
+        assert_eq!(controller.integral(), 2);
+
+        controller.set_gains(IntegerGains::new(0, 25, 0, 100));
+        assert_eq!(controller.update(0, 0), 2);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::Float;
+
+// Running statistics of the deviation of measured sample intervals from the
+// nominal sample time, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JitterStats<T: Float> {
+    count: u64,
+    mean: T,
+    max: T,
+    sum_of_squares: T,
+}
+impl<T: Float> JitterStats<T> {
+    pub fn new() -> Self {
+        Self::with_zero(T::zero())
+    }
+    // `new` for the const controller constructor.
+    pub(crate) const fn with_zero(zero: T) -> Self {
+        Self {
+            count: 0,
+            mean: zero,
+            max: zero,
+            sum_of_squares: zero,
+        }
+    }
+    pub fn init(&mut self) {
+        *self = Self::new();
+    }
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    pub fn mean(&self) -> T {
+        self.mean
+    }
+    // Largest absolute deviation seen.
+    pub fn max(&self) -> T {
+        self.max
+    }
+    pub fn variance(&self) -> T {
+        if self.count < 2 {
+            T::zero()
+        } else {
+            self.sum_of_squares / T::from_f64((self.count - 1) as f64)
+        }
+    }
+    pub fn record(&mut self, deviation: T) {
+        self.count += 1;
+        let delta = deviation - self.mean;
+        self.mean = self.mean + delta / T::from_f64(self.count as f64);
+        self.sum_of_squares = self.sum_of_squares + delta * (deviation - self.mean);
+        if deviation.abs() > self.max {
+            self.max = deviation.abs();
+        }
+    }
+}
+impl<T: Float> Default for JitterStats<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::{Controller, Differentiator, Integrator, OutputLimits, Proportional};
+
+    #[test]
+    fn statistics_match_the_batch_formulas() {
+        let mut stats = JitterStats::new();
+        assert_eq!(stats.variance(), 0.0);
+        for deviation in [1.0, 2.0, 3.0, 4.0, -6.0] {
+            stats.record(deviation);
+        }
+        assert_eq!(stats.count(), 5);
+        assert!((stats.mean() - 0.8).abs() < 1e-12);
+        assert!((stats.variance() - 15.7).abs() < 1e-12);
+        assert_eq!(stats.max(), 6.0);
+        stats.init();
+        assert_eq!(stats, JitterStats::default());
+    }
+
+    #[test]
+    fn late_samples_are_recorded_and_optionally_compensated() {
+        let run = |compensate_jitter: bool| {
+            let mut controller = Controller::new(
+                OutputLimits::Unbounded,
+                Duration::from_millis(10),
+                Proportional::new(0.0),
+                Integrator::new(1.0, OutputLimits::Unbounded),
+                Differentiator::new(0.0, 0.0),
+            );
+            controller.set_jitter_compensation(compensate_jitter);
+            let mut output = 0.0;
+            for millis in [0, 10, 30] {
+                output = controller.update_at_timestamp(1.0, 0.0, &Duration::from_millis(millis));
+            }
+            (output, *controller.jitter())
+        };
+        let (compensated, jitter) = run(true);
+        let (nominal, _) = run(false);
+        assert!((compensated - nominal - 0.01).abs() < 1e-12);
+        assert_eq!(jitter.count(), 2);
+        assert!((jitter.mean() - 0.005).abs() < 1e-12);
+        assert!((jitter.max() - 0.01).abs() < 1e-12);
+    }
+}
@@ -1,13 +1,80 @@
-use std::{ops::Range, time::Duration};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::{fmt, time::Duration};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+pub mod adaptive;
+pub mod adrc;
+pub mod autotune;
+pub mod bank;
+mod builder;
+pub mod cascade;
+mod clock;
+mod difference;
+mod error;
+pub mod estimator;
+pub mod extremum;
+pub mod filter;
+pub mod fuzzy;
+#[cfg(feature = "half")]
+pub mod half;
+pub mod history;
+#[cfg(feature = "alloc")]
+pub mod identification;
+pub mod integer;
+mod jitter;
+mod limits;
+#[cfg(feature = "log-hook")]
+pub mod log;
+mod math;
+pub mod mimo;
+pub mod mrac;
+pub mod onoff;
+pub mod optimize;
+pub mod ratio;
+pub mod reduced;
+pub mod resonant;
+#[cfg(feature = "alloc")]
+pub mod schedule;
+pub mod selector;
+pub mod setpoint;
+pub mod simulation;
+#[cfg(feature = "alloc")]
+pub mod smith;
+pub mod split_range;
+#[cfg(feature = "swarm")]
+pub mod swarm;
+pub mod telemetry;
+#[cfg(feature = "trace-hook")]
+pub mod trace;
+pub mod trajectory;
+pub mod tuning;
+pub mod voting;
+mod watchdog;
+mod wrapping;
+
+pub use builder::ControllerBuilder;
+pub use clock::{Clock, Ticks, Timestamp, Timestep};
+pub use difference::DifferenceEquation;
+pub use error::ConfigError;
+pub use filter::{Filter, FilterChain, Stage};
+pub use jitter::JitterStats;
+pub use limits::OutputLimits;
+pub use watchdog::{Freshness, StaleAction, Watchdog};
+pub use wrapping::{WrapPolicy, Wrapping, RECENTRE_TURNS};
 
 pub trait Float
 where
     Self: Copy
-        + std::cmp::PartialOrd
-        + std::ops::Add<Self, Output = Self>
-        + std::ops::Sub<Self, Output = Self>
-        + std::ops::Mul<Self, Output = Self>
-        + std::ops::Div<Self, Output = Self>
+        + core::cmp::PartialOrd
+        + core::ops::Add<Self, Output = Self>
+        + core::ops::Sub<Self, Output = Self>
+        + core::ops::Mul<Self, Output = Self>
+        + core::ops::Div<Self, Output = Self>
         + Sized,
 {
     fn negative() -> Self;
@@ -15,7 +82,43 @@ where
     fn one() -> Self;
     fn half() -> Self;
     fn zero() -> Self;
-    fn from_duration(dur: &Duration) -> Self;
+    // Everything else derives from the arithmetic bounds and the constants
+    // above, so a custom numeric type only has to supply those. The generic
+    // `from_f64`, `ln`, `sqrt` and `floor` work bit by bit and are slow;
+    // types with native versions should override them.
+    fn from_duration(dur: &Duration) -> Self {
+        Self::from_f64(dur.as_secs_f64())
+    }
+    fn from_f64(value: f64) -> Self {
+        math::generic_from_f64(value)
+    }
+    fn ln(self) -> Self {
+        math::generic_ln(self)
+    }
+    fn sqrt(self) -> Self {
+        math::generic_sqrt(self)
+    }
+    fn floor(self) -> Self {
+        math::generic_floor(self)
+    }
+    fn abs(self) -> Self {
+        if self < Self::zero() {
+            Self::zero() - self
+        } else {
+            self
+        }
+    }
+    fn infinity() -> Self {
+        Self::from_f64(f64::INFINITY)
+    }
+    // self * a + b, fused into a single rounding where the type supports it.
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+    // Zero times any finite value is zero; for infinities and NaN it is NaN.
+    fn is_finite(self) -> bool {
+        self * Self::zero() == Self::zero()
+    }
 }
 impl Float for f32 {
     fn negative() -> Self {
@@ -36,6 +139,41 @@ impl Float for f32 {
     fn from_duration(dur: &Duration) -> Self {
         dur.as_secs_f32()
     }
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+    fn floor(self) -> Self {
+        math::floor(self as f64) as f32
+    }
+    #[cfg(all(feature = "std", not(feature = "deterministic")))]
+    fn ln(self) -> Self {
+        f32::ln(self)
+    }
+    #[cfg(any(not(feature = "std"), feature = "deterministic"))]
+    fn ln(self) -> Self {
+        math::ln(self as f64) as f32
+    }
+    fn infinity() -> Self {
+        f32::INFINITY
+    }
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+    #[cfg(all(feature = "std", not(feature = "deterministic")))]
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+    #[cfg(all(feature = "fma", feature = "std", not(feature = "deterministic")))]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        f32::mul_add(self, a, b)
+    }
+    #[cfg(any(not(feature = "std"), feature = "deterministic"))]
+    fn sqrt(self) -> Self {
+        math::sqrt(self as f64) as f32
+    }
 }
 impl Float for f64 {
     fn negative() -> Self {
@@ -56,121 +194,646 @@ impl Float for f64 {
     fn from_duration(dur: &Duration) -> Self {
         dur.as_secs_f64()
     }
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+    fn floor(self) -> Self {
+        math::floor(self)
+    }
+    #[cfg(all(feature = "std", not(feature = "deterministic")))]
+    fn ln(self) -> Self {
+        f64::ln(self)
+    }
+    #[cfg(any(not(feature = "std"), feature = "deterministic"))]
+    fn ln(self) -> Self {
+        math::ln(self)
+    }
+    fn infinity() -> Self {
+        f64::INFINITY
+    }
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+    #[cfg(all(feature = "std", not(feature = "deterministic")))]
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+    #[cfg(all(feature = "fma", feature = "std", not(feature = "deterministic")))]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        f64::mul_add(self, a, b)
+    }
+    #[cfg(any(not(feature = "std"), feature = "deterministic"))]
+    fn sqrt(self) -> Self {
+        math::sqrt(self)
+    }
+}
+
+// Floats whose zero is known at compile time, for the const constructors
+// that put controllers in statics, where `Float::zero` cannot be called.
+pub trait ConstFloat: Float {
+    const ZERO: Self;
+}
+impl ConstFloat for f32 {
+    const ZERO: Self = 0.;
+}
+impl ConstFloat for f64 {
+    const ZERO: Self = 0.;
+}
+
+// The time step type is a trait parameter rather than a method parameter so
+// components stay usable as trait objects, e.g. `dyn ControllerComponent<f32>`.
+pub trait ControllerComponent<T: Float, D: Timestep<T> = Duration> {
+    fn init(&mut self);
+    fn update(&mut self, setpoint: T, measurement: T, sample_time: &D) -> T;
 }
 
-pub trait ControllerComponent<T: Float> {
+// The interface shared by the crate's single-loop controllers, so a loop can
+// swap between them, e.g. between on/off and PID control.
+pub trait ControlLoop<T: Float> {
     fn init(&mut self);
-    fn update(&mut self, setpoint: T, measurement: T, sample_time: &Duration) -> T;
+    fn output(&self) -> T;
+    fn update(&mut self, setpoint: T, measurement: T) -> T;
 }
 
+fn check_gain<T: Float>(gain: T) -> Result<(), ConfigError> {
+    if gain.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigError::NonFiniteGain)
+    }
+}
+
+// With the proportional term on the measurement, and the derivative on the
+// measurement as it is by default, only the integral acts on the error: the
+// I-PD structure, which responds to setpoint steps without a proportional
+// kick. The default of proportional on error gives PI-D.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProportionalMode {
+    Error,
+    Measurement,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Proportional<T: Float> {
     gain: T,
+    mode: ProportionalMode,
+    previous_input: T,
 }
 impl<T: Float> Proportional<T> {
     pub fn new(gain: T) -> Self {
-        Self { gain }
+        Self::with_zero(gain, T::zero())
+    }
+    // Shared by `new` and `new_const`; `zero` stands in for `T::zero()`,
+    // which cannot be called in const.
+    const fn with_zero(gain: T, zero: T) -> Self {
+        Self {
+            gain,
+            mode: ProportionalMode::Error,
+            previous_input: zero,
+        }
+    }
+    pub fn try_new(gain: T) -> Result<Self, ConfigError> {
+        check_gain(gain)?;
+        Ok(Self::new(gain))
+    }
+    pub fn init(&mut self) {
+        self.previous_input = T::zero();
+    }
+    pub fn set_mode(&mut self, mode: ProportionalMode) {
+        self.mode = mode;
+    }
+    fn step(&mut self, setpoint: T, measurement: T) -> T {
+        self.previous_input = match self.mode {
+            ProportionalMode::Error => setpoint - measurement,
+            ProportionalMode::Measurement => T::negative() * measurement,
+        };
+        self.gain * self.previous_input
     }
 }
-impl<T: Float> ControllerComponent<T> for Proportional<T> {
-    fn init(&mut self) {}
-    fn update(&mut self, setpoint: T, measurement: T, _: &Duration) -> T {
-        let error = setpoint - measurement;
-        self.gain * error
+impl<T: ConstFloat> Proportional<T> {
+    // `new` for const and static initializers.
+    pub const fn new_const(gain: T) -> Self {
+        Self::with_zero(gain, T::ZERO)
+    }
+}
+impl<T: Float, D: Timestep<T>> ControllerComponent<T, D> for Proportional<T> {
+    fn init(&mut self) {
+        Proportional::init(self);
+    }
+    fn update(&mut self, setpoint: T, measurement: T, _: &D) -> T {
+        self.step(setpoint, measurement)
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Integrator<T: Float> {
     value: T,
     gain: T,
     previous_error: T,
-    output_limit: Range<T>,
+    output_limit: OutputLimits<T>,
 }
 impl<T: Float> Integrator<T> {
-    pub fn new(gain: T, output_limit: Range<T>) -> Self {
+    pub fn new(gain: T, output_limit: impl Into<OutputLimits<T>>) -> Self {
+        Self::with_zero(gain, output_limit.into(), T::zero())
+    }
+    const fn with_zero(gain: T, output_limit: OutputLimits<T>, zero: T) -> Self {
         Self {
-            value: T::zero(),
+            value: zero,
             gain,
-            previous_error: T::zero(),
+            previous_error: zero,
             output_limit,
         }
     }
-    fn clamp_value(&mut self) {
-        if self.value > self.output_limit.end {
-            self.value = self.output_limit.end;
-        } else if self.value < self.output_limit.start {
-            self.value = self.output_limit.start;
+    pub fn try_new(gain: T, output_limit: impl Into<OutputLimits<T>>) -> Result<Self, ConfigError> {
+        check_gain(gain)?;
+        let output_limit = output_limit.into();
+        if output_limit.is_inverted() {
+            return Err(ConfigError::InvertedIntegralLimit);
         }
+        Ok(Self::new(gain, output_limit))
     }
-}
-impl<T: Float> ControllerComponent<T> for Integrator<T> {
-    fn init(&mut self) {
+    pub fn init(&mut self) {
         self.value = T::zero();
         self.previous_error = T::zero();
     }
-    fn update(&mut self, setpoint: T, measurement: T, sample_time: &Duration) -> T {
-        let error = setpoint - measurement;
-        let new_value =
-            T::half() * self.gain * T::from_duration(sample_time) * (error + self.previous_error);
-        self.value = self.value + new_value;
-        self.clamp_value();
+    fn clamp_value(&mut self) {
+        self.value = self.output_limit.clamp(self.value);
+    }
+    fn integrate(&mut self, error: T, dt: T) -> T {
+        self.value = (T::half() * self.gain * dt).mul_add(error + self.previous_error, self.value);
+        self.previous_error = error;
+        self.value
+    }
+    fn preload(&mut self, value: T, error: T) {
+        self.value = value;
         self.previous_error = error;
+        self.clamp_value();
+    }
+    fn track(&mut self, tracking_gain: T, excess: T, dt: T) {
+        self.value = (tracking_gain * dt).mul_add(excess, self.value);
+        self.clamp_value();
+    }
+    fn step(&mut self, setpoint: T, measurement: T, dt: T) -> T {
+        self.integrate(setpoint - measurement, dt);
+        self.clamp_value();
         self.value
     }
 }
+impl<T: ConstFloat> Integrator<T> {
+    // `new` for const and static initializers, which cannot use the `Into`
+    // conversion from ranges.
+    pub const fn new_const(gain: T, output_limit: OutputLimits<T>) -> Self {
+        Self::with_zero(gain, output_limit, T::ZERO)
+    }
+}
+impl<T: Float, D: Timestep<T>> ControllerComponent<T, D> for Integrator<T> {
+    fn init(&mut self) {
+        Integrator::init(self);
+    }
+    fn update(&mut self, setpoint: T, measurement: T, sample_time: &D) -> T {
+        self.step(setpoint, measurement, sample_time.seconds())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivativeMode {
+    Measurement,
+    Error,
+}
+
+pub const MAX_DERIVATIVE_WINDOW: usize = 16;
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Differentiator<T: Float> {
     value: T,
     gain: T,
     time_constant: T,
-    previous_measurement: T,
+    mode: DerivativeMode,
+    // Ring buffer of the last `window` inputs, oldest at `head`.
+    history: [T; MAX_DERIVATIVE_WINDOW],
+    window: usize,
+    head: usize,
 }
 impl<T: Float> Differentiator<T> {
     pub fn new(gain: T, time_constant: T) -> Self {
+        Self::with_zero(gain, time_constant, T::zero())
+    }
+    const fn with_zero(gain: T, time_constant: T, zero: T) -> Self {
         Self {
-            value: T::zero(),
+            value: zero,
             gain,
             time_constant,
-            previous_measurement: T::zero(),
+            mode: DerivativeMode::Measurement,
+            history: [zero; MAX_DERIVATIVE_WINDOW],
+            window: 1,
+            head: 0,
         }
     }
-}
-impl<T: Float> ControllerComponent<T> for Differentiator<T> {
-    fn init(&mut self) {
+    pub fn try_new(gain: T, time_constant: T) -> Result<Self, ConfigError> {
+        check_gain(gain)?;
+        if !time_constant.is_finite() {
+            return Err(ConfigError::NonFiniteTimeConstant);
+        }
+        if time_constant < T::zero() {
+            return Err(ConfigError::NegativeTimeConstant);
+        }
+        Ok(Self::new(gain, time_constant))
+    }
+    pub fn init(&mut self) {
         self.value = T::zero();
-        self.previous_measurement = T::zero();
+        self.fill(T::zero());
+    }
+    pub fn set_mode(&mut self, mode: DerivativeMode) {
+        self.mode = mode;
+    }
+    // Differences the input against the sample `window` steps back instead of
+    // the previous one, averaging the slope over the window.
+    pub fn set_window(&mut self, window: usize) -> Result<(), ConfigError> {
+        if window == 0 || window > MAX_DERIVATIVE_WINDOW {
+            return Err(ConfigError::InvalidDerivativeWindow);
+        }
+        let previous_input = self.previous_input();
+        self.window = window;
+        self.fill(previous_input);
+        Ok(())
+    }
+    fn previous_input(&self) -> T {
+        self.history[(self.head + self.window - 1) % self.window]
+    }
+    fn fill(&mut self, input: T) {
+        self.history = [input; MAX_DERIVATIVE_WINDOW];
+        self.head = 0;
     }
-    fn update(&mut self, _: T, measurement: T, sample_time: &Duration) -> T {
-        let measurement_error = measurement - self.previous_measurement;
+    // Stores the new input and returns the one `window` samples older.
+    fn push(&mut self, input: T) -> T {
+        let oldest = self.history[self.head];
+        self.history[self.head] = input;
+        self.head = (self.head + 1) % self.window;
+        oldest
+    }
+    fn input(&self, setpoint: T, measurement: T) -> T {
+        // Derivative on measurement differentiates -measurement, which matches the
+        // derivative of the error whenever the setpoint is constant.
+        match self.mode {
+            DerivativeMode::Measurement => T::negative() * measurement,
+            DerivativeMode::Error => setpoint - measurement,
+        }
+    }
+    // Advances the stored input without touching the output, so the next
+    // sample does not see the whole gap as a single step.
+    fn hold(&mut self, setpoint: T, measurement: T) -> T {
+        let input = self.input(setpoint, measurement);
+        self.push(input);
+        self.value
+    }
+    // Uses an externally estimated measurement velocity in place of the
+    // finite difference, bypassing the filter. The input is still recorded so
+    // switching back is bumpless. The rate of the input is -velocity in both
+    // modes; in error mode setpoint changes are ignored.
+    fn step_velocity(&mut self, setpoint: T, measurement: T, velocity: T) -> T {
+        let input = self.input(setpoint, measurement);
+        self.push(input);
+        self.value = T::negative() * self.gain * velocity;
+        self.value
+    }
+    fn step(&mut self, setpoint: T, measurement: T, dt: T) -> T {
+        let input = self.input(setpoint, measurement);
+        let oldest = self.push(input);
+        let input_difference = (input - oldest) / T::from_f64(self.window as f64);
 
-        let numerator = T::negative()
-            * (T::double() * self.gain * measurement_error
-                + (T::double() * self.time_constant - T::from_duration(sample_time)) * self.value);
+        // Without a filter the bilinear form degenerates into an undamped
+        // oscillation, so fall back to a plain backward difference.
+        if self.time_constant == T::zero() {
+            self.value = self.gain * input_difference / dt;
+            return self.value;
+        }
 
-        let denominator = T::double() * self.time_constant + T::from_duration(sample_time);
+        let numerator = (T::double() * self.time_constant - dt)
+            .mul_add(self.value, T::double() * self.gain * input_difference);
 
-        self.previous_measurement = measurement;
+        let denominator = T::double() * self.time_constant + dt;
 
         self.value = numerator / denominator;
 
         self.value
     }
 }
+impl<T: ConstFloat> Differentiator<T> {
+    // `new` for const and static initializers.
+    pub const fn new_const(gain: T, time_constant: T) -> Self {
+        Self::with_zero(gain, time_constant, T::ZERO)
+    }
+}
+impl<T: Float, D: Timestep<T>> ControllerComponent<T, D> for Differentiator<T> {
+    fn init(&mut self) {
+        Differentiator::init(self);
+    }
+    fn update(&mut self, setpoint: T, measurement: T, sample_time: &D) -> T {
+        self.step(setpoint, measurement, sample_time.seconds())
+    }
+}
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feedforward<T: Float> {
+    gain: T,
+    derivative_gain: T,
+    previous_setpoint: Option<T>,
+}
+impl<T: Float> Feedforward<T> {
+    pub const fn new(gain: T, derivative_gain: T) -> Self {
+        Self {
+            gain,
+            derivative_gain,
+            previous_setpoint: None,
+        }
+    }
+    pub fn try_new(gain: T, derivative_gain: T) -> Result<Self, ConfigError> {
+        check_gain(gain)?;
+        check_gain(derivative_gain)?;
+        Ok(Self::new(gain, derivative_gain))
+    }
+    pub fn init(&mut self) {
+        self.previous_setpoint = None;
+    }
+    fn step(&mut self, setpoint: T, dt: T) -> T {
+        let setpoint_rate = match self.previous_setpoint {
+            Some(previous_setpoint) => (setpoint - previous_setpoint) / dt,
+            None => T::zero(),
+        };
+        self.previous_setpoint = Some(setpoint);
+        self.gain * setpoint + self.derivative_gain * setpoint_rate
+    }
+}
+impl<T: Float, D: Timestep<T>> ControllerComponent<T, D> for Feedforward<T> {
+    fn init(&mut self) {
+        Feedforward::init(self);
+    }
+    fn update(&mut self, setpoint: T, _: T, sample_time: &D) -> T {
+        self.step(setpoint, sample_time.seconds())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntiWindup<T: Float> {
+    None,
+    Clamp,
+    Conditional,
+    BackCalculation { tracking_gain: T },
+}
+
+// The steady state a loop was linearized around: the terms work on the
+// deviation of the setpoint and measurement from `measurement`, and `output`
+// is added to the output, as is the bias.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OperatingPoint<T: Float> {
+    output: T,
+    measurement: T,
+}
+impl<T: Float> OperatingPoint<T> {
+    pub const fn new(output: T, measurement: T) -> Self {
+        Self {
+            output,
+            measurement,
+        }
+    }
+    pub fn output(&self) -> T {
+        self.output
+    }
+    pub fn measurement(&self) -> T {
+        self.measurement
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerAction {
+    Direct,
+    Reverse,
+}
+
+// In every mode but Auto the terms keep running on the current inputs, so
+// the derivative and filters stay current, and the output is set as follows:
+//
+// - Auto: by the terms.
+// - Manual: by `set_manual_output`; the integrator is preloaded so the terms
+//   would reproduce it, and returning to Auto is bumpless.
+// - Tracking: follows `set_tracking_output`, e.g. another controller's output
+//   while this one is switched out; preloaded and bumpless like Manual.
+// - Fault: the fail-safe output chosen by the `FaultAction`, preloaded like
+//   Manual. Only `acknowledge_fault` leaves it.
+// - Off: the off output, zero by default and not limited. The integrator is
+//   cleared, so returning to Auto is a cold start without a derivative kick.
+// - Hold: frozen, e.g. while a sensor is calibrated or an oven door is open.
+//   Updates are ignored and leave the integrator and output as they were; on
+//   release the time spent holding is not integrated and the derivative
+//   restarts from the next measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    Manual,
+    Auto,
+    Tracking,
+    Fault,
+    Hold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FaultAction<T: Float> {
+    // Keep the output the controller had when the fault was detected.
+    Hold,
+    // Drive the output to a fail-safe value, which is not limited.
+    Output(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultCause {
+    // Tripped with `trip`.
+    External,
+    MeasurementSlew,
+    NonFiniteInput,
+    MeasurementOutOfRange,
+    StaleMeasurement,
+}
+
+// What replaces a measurement outside the plausible range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Substitution {
+    // The last measurement inside the range; samples are dropped until there
+    // has been one.
+    LastGood,
+    // Trip into fault mode, which applies the fail-safe output.
+    FailSafe,
+}
+
+// What to do with an update whose setpoint, measurement, feedforward or time
+// step is NaN or infinite. None of them lets the value reach the state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinitePolicy {
+    // Drop the sample as if it never arrived.
+    Ignore,
+    // Drop the sample, and resume after the last bad one without a
+    // derivative step across the gap.
+    Hold,
+    // Trip into fault mode.
+    Fault,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlOutput<T: Float> {
+    pub p: T,
+    pub i: T,
+    pub d: T,
+    pub resonant: T,
+    pub feedforward: T,
+    pub unclamped: T,
+    pub output: T,
+    pub saturated: bool,
+    // The setpoint as passed in and as the controller used it, after the
+    // setpoint filters.
+    pub raw_setpoint: T,
+    pub setpoint: T,
+    // The raw setpoint was outside the setpoint limits and was clamped.
+    pub setpoint_clamped: bool,
+    // The measurement as the terms saw it, after the measurement filter.
+    pub measurement: T,
+    // The raw measurement changed faster than the configured slew limit.
+    pub measurement_slew_exceeded: bool,
+    // An input was not finite and the sample was rejected; the terms are
+    // zero and the output is unchanged.
+    pub non_finite_input: bool,
+    // The measurement was outside the plausible range and was substituted.
+    pub measurement_invalid: bool,
+    // The watchdog found the measurement stale.
+    pub measurement_stale: bool,
+}
+
+// A user-supplied shape for the error, such as a gap, square root or
+// piecewise gain. Non-capturing closures coerce to `fn`, which keeps the
+// controller Clone and usable in statics; shapes compare equal when they are
+// the same function.
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorShaping<T: Float>(fn(T) -> T);
+impl<T: Float> ErrorShaping<T> {
+    pub const fn new(shape: fn(T) -> T) -> Self {
+        Self(shape)
+    }
+    pub fn shape(&self, error: T) -> T {
+        (self.0)(error)
+    }
+}
+impl<T: Float> PartialEq for ErrorShaping<T> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::fn_addr_eq(self.0, other.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapPolicy {
+    Nominal,
+    Integrate,
+    Cap(u32),
+    SkipDerivative,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Controller<T: Float> {
-    output_limit: Range<T>,
+    output_limit: OutputLimits<T>,
     sample_time: Duration,
     p: Proportional<T>,
     i: Integrator<T>,
     d: Differentiator<T>,
+    resonant: Option<resonant::Resonant<T>>,
+    feedforward: Option<Feedforward<T>>,
+    disturbance_model: Option<filter::LeadLag<T>>,
+    setpoint_limit: OutputLimits<T>,
+    setpoint_filters: FilterChain<T>,
+    wrapping: Option<Wrapping<T>>,
+    // Last raw measurement and its unwrapped equivalent.
+    unwrapped_measurement: Option<(T, T)>,
+    decimator: filter::Decimator<T>,
+    measurement_slew: Option<filter::SlewLimiter<T>>,
+    measurement_filters: FilterChain<T>,
+    output_filters: FilterChain<T>,
+    estimator: Option<estimator::AlphaBeta<T>>,
+    estimated_derivative: bool,
+    deadband: Option<T>,
+    error_squared: Option<T>,
+    error_shaping: Option<ErrorShaping<T>>,
+    action: ControllerAction,
+    bias: T,
+    operating_point: OperatingPoint<T>,
+    mode: Mode,
+    off_output: T,
+    tracking_output: T,
+    // Set for the duration of `update_detailed_tracked`.
+    external_tracking: Option<T>,
+    // The resonant and feedforward terms of the latest update, which
+    // `reset_to` keeps out of the integrator.
+    previous_extra_terms: T,
+    // The report of the latest update, the time step it integrated over and
+    // the time since the previous update, for `Monitored`.
+    pub(crate) last_step: Option<(ControlOutput<T>, Duration, Duration)>,
+    fault_action: FaultAction<T>,
+    fault_cause: Option<FaultCause>,
+    slew_fault: bool,
+    non_finite_policy: NonFinitePolicy,
+    resuming: bool,
+    measurement_range: OutputLimits<T>,
+    substitution: Substitution,
+    max_invalid: Option<u32>,
+    invalid_count: u32,
+    last_good_measurement: Option<T>,
+    watchdog: Option<Watchdog<T>>,
+    setpoint_tracking: bool,
+    anti_windup: AntiWindup<T>,
+    blend_samples: u32,
+    gain_blend: Option<(tuning::Gains<T>, u32)>,
+    #[cfg(feature = "trace-hook")]
+    tracer: Option<trace::Tracer<T>>,
+    #[cfg(feature = "log-hook")]
+    logger: Option<log::Logger<T>>,
+    #[cfg(feature = "std")]
+    last_update: Option<Instant>,
+    last_tick: Option<u64>,
+    last_timestamp: Option<Duration>,
+    max_gap: Duration,
+    gap_policy: GapPolicy,
+    jitter: JitterStats<T>,
+    compensate_jitter: bool,
     out: T,
 }
+impl<T: ConstFloat> Controller<T> {
+    // `new` for const and static initializers, which cannot use the `Into`
+    // conversion from ranges.
+    pub const fn new_const(
+        output_limit: OutputLimits<T>,
+        sample_time: Duration,
+        p: Proportional<T>,
+        i: Integrator<T>,
+        d: Differentiator<T>,
+    ) -> Self {
+        Self::with_zero(output_limit, sample_time, p, i, d, T::ZERO)
+    }
+}
 impl<T: Float> Controller<T> {
     pub fn new(
-        output_limit: Range<T>,
+        output_limit: impl Into<OutputLimits<T>>,
         sample_time: Duration,
         p: Proportional<T>,
         i: Integrator<T>,
         d: Differentiator<T>,
+    ) -> Self {
+        Self::with_zero(output_limit.into(), sample_time, p, i, d, T::zero())
+    }
+    const fn with_zero(
+        output_limit: OutputLimits<T>,
+        sample_time: Duration,
+        p: Proportional<T>,
+        i: Integrator<T>,
+        d: Differentiator<T>,
+        zero: T,
     ) -> Self {
         Self {
             output_limit,
@@ -178,25 +841,1726 @@ impl<T: Float> Controller<T> {
             p,
             i,
             d,
-            out: T::zero(),
+            resonant: None,
+            feedforward: None,
+            disturbance_model: None,
+            setpoint_limit: OutputLimits::Unbounded,
+            setpoint_filters: FilterChain::new(),
+            wrapping: None,
+            unwrapped_measurement: None,
+            decimator: filter::Decimator::with_zero(zero),
+            measurement_slew: None,
+            measurement_filters: FilterChain::new(),
+            output_filters: FilterChain::new(),
+            estimator: None,
+            estimated_derivative: false,
+            deadband: None,
+            error_squared: None,
+            error_shaping: None,
+            action: ControllerAction::Direct,
+            bias: zero,
+            operating_point: OperatingPoint::new(zero, zero),
+            mode: Mode::Auto,
+            off_output: zero,
+            tracking_output: zero,
+            external_tracking: None,
+            previous_extra_terms: zero,
+            last_step: None,
+            fault_action: FaultAction::Hold,
+            fault_cause: None,
+            slew_fault: false,
+            non_finite_policy: NonFinitePolicy::Ignore,
+            resuming: false,
+            measurement_range: OutputLimits::Unbounded,
+            substitution: Substitution::LastGood,
+            max_invalid: None,
+            invalid_count: 0,
+            last_good_measurement: None,
+            watchdog: None,
+            setpoint_tracking: false,
+            anti_windup: AntiWindup::Clamp,
+            blend_samples: 0,
+            gain_blend: None,
+            #[cfg(feature = "trace-hook")]
+            tracer: None,
+            #[cfg(feature = "log-hook")]
+            logger: None,
+            #[cfg(feature = "std")]
+            last_update: None,
+            last_tick: None,
+            last_timestamp: None,
+            max_gap: sample_time.saturating_mul(10),
+            gap_policy: GapPolicy::Nominal,
+            jitter: JitterStats::with_zero(zero),
+            compensate_jitter: true,
+            out: zero,
+        }
+    }
+    pub fn try_new(
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        p: Proportional<T>,
+        i: Integrator<T>,
+        d: Differentiator<T>,
+    ) -> Result<Self, ConfigError> {
+        let output_limit = output_limit.into();
+        if output_limit.is_inverted() {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        if sample_time.is_zero() {
+            return Err(ConfigError::ZeroSampleTime);
+        }
+        Ok(Self::new(output_limit, sample_time, p, i, d))
+    }
+    pub fn builder() -> ControllerBuilder<T> {
+        ControllerBuilder::new()
+    }
+    // A resonant term on the error, summed with the PID terms and covered by
+    // the same anti-windup.
+    pub fn set_resonant(&mut self, resonant: Option<resonant::Resonant<T>>) {
+        self.resonant = resonant;
+    }
+    pub fn set_feedforward(&mut self, feedforward: Option<Feedforward<T>>) {
+        self.feedforward = feedforward;
+    }
+    // Setpoints outside these limits are clamped before anything else sees
+    // them, and flagged in `ControlOutput`.
+    pub fn set_setpoint_limits(
+        &mut self,
+        setpoint_limit: impl Into<OutputLimits<T>>,
+    ) -> Result<(), ConfigError> {
+        let setpoint_limit = setpoint_limit.into();
+        if setpoint_limit.is_inverted() {
+            return Err(ConfigError::InvertedSetpointLimit);
+        }
+        self.setpoint_limit = setpoint_limit;
+        Ok(())
+    }
+    // Filters the setpoint before it reaches feedforward and the terms; a
+    // `LowPass` here softens operator steps.
+    pub fn setpoint_filters_mut(&mut self) -> &mut FilterChain<T> {
+        &mut self.setpoint_filters
+    }
+    // Checks the raw measurement against a maximum rate of change ahead of
+    // the measurement filters, reporting violations in `ControlOutput`.
+    pub fn set_measurement_slew_limit(&mut self, limiter: Option<filter::SlewLimiter<T>>) {
+        self.measurement_slew = limiter;
+    }
+    // Filters the measurement before it reaches any of the terms.
+    pub fn measurement_filters_mut(&mut self) -> &mut FilterChain<T> {
+        &mut self.measurement_filters
+    }
+    // Replaces the measurement with the estimator's position estimate, after
+    // the measurement filter. With `estimated_derivative` the derivative term
+    // uses the estimated velocity directly instead of differencing.
+    pub fn set_estimator(
+        &mut self,
+        estimator: Option<estimator::AlphaBeta<T>>,
+        estimated_derivative: bool,
+    ) {
+        self.estimator = estimator;
+        self.estimated_derivative = estimated_derivative;
+    }
+    // Filters the sum of the terms before the output limits are applied, so
+    // the limits still hold and anti-windup sees the filtered output. Calling
+    // `reset_to(output)` on the chain after adding stages avoids a bump; in
+    // manual mode the chain is kept settled at the manual output.
+    pub fn output_filters_mut(&mut self) -> &mut FilterChain<T> {
+        &mut self.output_filters
+    }
+    pub fn set_deadband(&mut self, deadband: Option<T>) {
+        self.deadband = deadband;
+    }
+    // Error-squared control: the terms see error * |error| / span, so the
+    // effective gain grows with the error and equals the nominal gain when
+    // |error| = span. Small errors on noisy loops are mostly ignored while
+    // large ones get a firm response. The integral and derivative on error
+    // see the same shaped error as the proportional term.
+    pub fn set_error_squared(&mut self, span: Option<T>) -> Result<(), ConfigError> {
+        if span.is_some_and(|span| !(span.is_finite() && span > T::zero())) {
+            return Err(ConfigError::InvalidErrorSpan);
+        }
+        self.error_squared = span;
+        Ok(())
+    }
+    // Applied after the deadband and error-squared shaping, and treated the
+    // same way.
+    pub fn set_error_shaping(&mut self, shaping: Option<ErrorShaping<T>>) {
+        self.error_shaping = shaping;
+    }
+    pub fn set_action(&mut self, action: ControllerAction) {
+        self.action = action;
+    }
+    pub fn set_bias(&mut self, bias: T) {
+        self.bias = bias;
+    }
+    // Takes effect on the next update and, like the bias, is not bumpless.
+    pub fn set_operating_point(&mut self, operating_point: OperatingPoint<T>) {
+        self.operating_point = operating_point;
+    }
+    pub fn operating_point(&self) -> OperatingPoint<T> {
+        self.operating_point
+    }
+    // The constant part of the output: the bias plus the nominal output.
+    fn offset(&self) -> T {
+        self.bias + self.operating_point.output
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    // Ignored while faulted: a fault must be acknowledged first. Setting
+    // `Mode::Fault` trips the controller as `FaultCause::External`.
+    pub fn set_mode(&mut self, mode: Mode) {
+        if self.mode == Mode::Fault {
+            return;
+        }
+        if mode == Mode::Fault {
+            self.trip(FaultCause::External);
+            return;
+        }
+        if self.mode == Mode::Hold && mode != Mode::Hold {
+            self.release_hold();
+        }
+        self.enter_mode(mode);
+        if mode == Mode::Off {
+            self.out = self.off_output;
+        }
+    }
+    fn enter_mode(&mut self, mode: Mode) {
+        #[cfg(feature = "trace-hook")]
+        if mode != self.mode {
+            self.trace(&trace::TraceEvent::ModeChange {
+                from: self.mode,
+                to: mode,
+            });
+        }
+        #[cfg(feature = "log-hook")]
+        match (self.mode, mode, self.fault_cause) {
+            (Mode::Fault, Mode::Fault, _) => {}
+            (_, Mode::Fault, Some(cause)) => self.warn(log::Warning::Fault(cause)),
+            (Mode::Fault, _, _) => self.warn(log::Warning::FaultCleared),
+            _ => {}
+        }
+        self.mode = mode;
+    }
+    #[cfg(feature = "log-hook")]
+    pub fn set_logger(&mut self, logger: Option<log::Logger<T>>) {
+        self.logger = logger;
+    }
+    #[cfg(feature = "log-hook")]
+    fn warn(&mut self, warning: log::Warning<T>) {
+        if let Some(logger) = &mut self.logger {
+            logger.warn(warning);
+        }
+    }
+    // Reports the warnings raised by one update.
+    #[cfg(feature = "log-hook")]
+    fn log_update(&mut self, output: &ControlOutput<T>, previous_integral: T) {
+        if let Some(logger) = &mut self.logger {
+            logger.tick();
+        }
+        if output.non_finite_input {
+            self.warn(log::Warning::NonFiniteInput);
+        }
+        if output.saturated {
+            self.warn(log::Warning::Saturated {
+                unclamped: output.unclamped,
+                output: output.output,
+            });
+            let above = output.unclamped > output.output;
+            if (above && output.i > previous_integral) || (!above && output.i < previous_integral) {
+                self.warn(log::Warning::Windup { integral: output.i });
+            }
+        }
+    }
+    #[cfg(feature = "trace-hook")]
+    pub fn set_tracer(&mut self, tracer: Option<trace::Tracer<T>>) {
+        self.tracer = tracer;
+    }
+    #[cfg(feature = "trace-hook")]
+    fn trace(&self, event: &trace::TraceEvent<T>) {
+        if let Some(tracer) = &self.tracer {
+            tracer.trace(event);
+        }
+    }
+    // Drops the timestamps taken before the hold so the first update after it
+    // integrates over the nominal sample time, not the whole hold.
+    fn release_hold(&mut self) {
+        #[cfg(feature = "std")]
+        {
+            self.last_update = None;
+        }
+        self.last_tick = None;
+        self.last_timestamp = None;
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.init();
+        }
+        self.resuming = true;
+    }
+    pub fn set_fault_action(&mut self, fault_action: FaultAction<T>) {
+        self.fault_action = fault_action;
+    }
+    // Trips into fault mode on measurements that exceed the slew limit.
+    pub fn set_slew_fault(&mut self, slew_fault: bool) {
+        self.slew_fault = slew_fault;
+    }
+    pub fn set_non_finite_policy(&mut self, non_finite_policy: NonFinitePolicy) {
+        self.non_finite_policy = non_finite_policy;
+    }
+    // Measurements outside this range, such as a PT100 reading -300 °C, are
+    // replaced according to the substitution. With `max_invalid` the
+    // controller also trips after that many invalid samples in a row.
+    pub fn set_measurement_range(
+        &mut self,
+        measurement_range: impl Into<OutputLimits<T>>,
+        substitution: Substitution,
+        max_invalid: Option<u32>,
+    ) -> Result<(), ConfigError> {
+        let measurement_range = measurement_range.into();
+        if measurement_range.is_inverted() {
+            return Err(ConfigError::InvertedMeasurementRange);
+        }
+        self.measurement_range = measurement_range;
+        self.substitution = substitution;
+        self.max_invalid = max_invalid;
+        self.invalid_count = 0;
+        Ok(())
+    }
+    pub fn set_watchdog(&mut self, watchdog: Option<Watchdog<T>>) {
+        self.watchdog = watchdog;
+    }
+    // Advances the watchdog by time that passed without an update, from a
+    // timer that keeps running when the measurement stream stops.
+    pub fn tick_watchdog(&mut self, elapsed: Duration) {
+        if self.mode == Mode::Hold {
+            return;
+        }
+        let Some(watchdog) = &mut self.watchdog else {
+            return;
+        };
+        watchdog.tick(T::from_duration(&elapsed));
+        if watchdog.is_stale() && watchdog.action() == StaleAction::Fault {
+            self.trip(FaultCause::StaleMeasurement);
+        }
+    }
+    // Enters fault mode and applies the fault action, keeping the first cause
+    // if already faulted.
+    pub fn trip(&mut self, cause: FaultCause) {
+        if self.mode == Mode::Fault {
+            return;
+        }
+        self.fault_cause = Some(cause);
+        self.enter_mode(Mode::Fault);
+        if let FaultAction::Output(output) = self.fault_action {
+            self.out = output;
+        }
+    }
+    pub fn fault_cause(&self) -> Option<FaultCause> {
+        self.fault_cause
+    }
+    // Clears a fault into manual mode at the fail-safe output, from where the
+    // operator returns to auto bumplessly. Returns whether there was a fault.
+    pub fn acknowledge_fault(&mut self) -> bool {
+        if self.mode != Mode::Fault {
+            return false;
+        }
+        self.fault_cause = None;
+        self.enter_mode(Mode::Manual);
+        self.clamp_output();
+        true
+    }
+    pub fn set_off_output(&mut self, off_output: T) {
+        self.off_output = off_output;
+        if self.mode == Mode::Off {
+            self.out = off_output;
+        }
+    }
+    pub fn set_tracking_output(&mut self, tracking_output: T) {
+        self.tracking_output = tracking_output;
+    }
+    // PV tracking: outside auto mode the setpoint is replaced by the
+    // measurement and the setpoint filters are held there, so on return to
+    // auto the error starts at zero and filtered setpoints move off from the
+    // measurement. `ControlOutput::setpoint` reports the tracked value for the
+    // caller to adopt as its own setpoint.
+    pub fn set_setpoint_tracking(&mut self, setpoint_tracking: bool) {
+        self.setpoint_tracking = setpoint_tracking;
+    }
+    pub fn set_manual_output(&mut self, output: T) {
+        self.out = output;
+        self.clamp_output();
+    }
+    fn clamp_output(&mut self) {
+        self.out = self.output_limit.clamp(self.out);
+    }
+    // With blending, `set_kp`, `set_ki` and `set_kd` move the gains to their
+    // new values in equal steps over `samples` updates instead of at once,
+    // for loops where even a bumpless change of gains upsets the process.
+    pub fn set_gain_blending(&mut self, samples: u32) {
+        self.blend_samples = samples;
+        if samples == 0 {
+            if let Some((target, _)) = self.gain_blend.take() {
+                self.apply_gains(&target);
+            }
+        }
+    }
+    // The gains being blended towards, if a blend is in progress.
+    pub fn gain_target(&self) -> Option<tuning::Gains<T>> {
+        self.gain_blend.map(|(target, _)| target)
+    }
+    pub fn gains(&self) -> tuning::Gains<T> {
+        tuning::Gains::new(self.p.gain, self.i.gain, self.d.gain)
+    }
+    pub fn attach_sink<S: telemetry::TelemetrySink<T>>(
+        self,
+        sink: S,
+    ) -> telemetry::Monitored<T, S> {
+        telemetry::Monitored::new(self, sink)
+    }
+    pub fn set_kp(&mut self, kp: T) {
+        match self.blend_target() {
+            Some(target) => self.blend_to(tuning::Gains { kp, ..target }),
+            None => self.apply_kp(kp),
+        }
+    }
+    pub fn set_ki(&mut self, ki: T) {
+        match self.blend_target() {
+            Some(target) => self.blend_to(tuning::Gains { ki, ..target }),
+            None => self.apply_ki(ki),
+        }
+    }
+    pub fn set_kd(&mut self, kd: T) {
+        match self.blend_target() {
+            Some(target) => self.blend_to(tuning::Gains { kd, ..target }),
+            None => self.apply_kd(kd),
+        }
+    }
+    fn blend_target(&self) -> Option<tuning::Gains<T>> {
+        (self.blend_samples > 0).then(|| self.gain_target().unwrap_or(self.gains()))
+    }
+    fn blend_to(&mut self, target: tuning::Gains<T>) {
+        self.gain_blend = Some((target, self.blend_samples));
+    }
+    // Takes one step of a blend in progress.
+    fn blend_gains(&mut self) {
+        let Some((target, remaining)) = self.gain_blend else {
+            return;
+        };
+        let remaining_f = T::from_f64(f64::from(remaining));
+        let gains = self.gains();
+        self.apply_gains(&tuning::Gains::new(
+            gains.kp + (target.kp - gains.kp) / remaining_f,
+            gains.ki + (target.ki - gains.ki) / remaining_f,
+            gains.kd + (target.kd - gains.kd) / remaining_f,
+        ));
+        self.gain_blend = (remaining > 1).then_some((target, remaining - 1));
+    }
+    // Gain changes are absorbed by the integrator so the output does not jump
+    // at the moment of the change.
+    fn apply_kp(&mut self, kp: T) {
+        self.i.value = self.i.value - (kp - self.p.gain) * self.p.previous_input;
+        self.i.clamp_value();
+        self.p.gain = kp;
+    }
+    fn apply_ki(&mut self, ki: T) {
+        // The integrator accumulates gain-weighted error, so its state is
+        // already continuous across a change of ki.
+        self.i.gain = ki;
+    }
+    fn apply_kd(&mut self, kd: T) {
+        if self.d.gain != T::zero() {
+            let value = self.d.value * kd / self.d.gain;
+            self.i.value = self.i.value - (value - self.d.value);
+            self.i.clamp_value();
+            self.d.value = value;
+        }
+        self.d.gain = kd;
+    }
+    // Sets the derivative filter time constant from N for the current kp and
+    // kd. The time constant is kept, not N, when the gains change later.
+    pub fn set_derivative_filter(&mut self, n: T) -> Result<(), ConfigError> {
+        self.d.time_constant =
+            tuning::derivative_filter_time_constant(self.p.gain, self.d.gain, n)?;
+        Ok(())
+    }
+    pub fn derivative_filter(&self) -> Option<T> {
+        tuning::derivative_filter_coefficient(self.p.gain, self.d.gain, self.d.time_constant)
+    }
+    pub fn output_limits(&self) -> OutputLimits<T> {
+        self.output_limit
+    }
+    pub fn set_output_limits(
+        &mut self,
+        output_limit: impl Into<OutputLimits<T>>,
+    ) -> Result<(), ConfigError> {
+        let output_limit = output_limit.into();
+        if output_limit.is_inverted() {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        self.output_limit = output_limit;
+        self.clamp_output();
+        Ok(())
+    }
+    pub fn set_integral_limits(
+        &mut self,
+        integral_limit: impl Into<OutputLimits<T>>,
+    ) -> Result<(), ConfigError> {
+        let integral_limit = integral_limit.into();
+        if integral_limit.is_inverted() {
+            return Err(ConfigError::InvertedIntegralLimit);
+        }
+        self.i.output_limit = integral_limit;
+        self.i.clamp_value();
+        Ok(())
+    }
+    // Used by the supervisors that set the gains every update, which take
+    // over from any blend in progress.
+    fn set_gains(&mut self, gains: &tuning::Gains<T>) {
+        self.gain_blend = None;
+        self.apply_gains(gains);
+    }
+    fn apply_gains(&mut self, gains: &tuning::Gains<T>) {
+        self.apply_kp(gains.kp);
+        self.apply_ki(gains.ki);
+        self.apply_kd(gains.kd);
+    }
+    // Freezes the current gains, derivative filter, sample time, action and
+    // output limits into a difference equation, starting from the current
+    // output; bias and operating point carry over through that output.
+    // Anything else that changes the control law is refused: proportional or
+    // derivative action on the measurement, a derivative window, resonant,
+    // feedforward and disturbance terms, filters, wrapping, the estimator,
+    // deadband, error shaping and setpoint limits. The equation always uses
+    // velocity-form anti-windup and has no modes, fault handling or timing.
+    pub fn compile(&self) -> Result<DifferenceEquation<T>, ConfigError> {
+        let proportional_on_measurement =
+            self.p.mode == ProportionalMode::Measurement && self.p.gain != T::zero();
+        let derivative_unsupported = self.d.gain != T::zero()
+            && (self.d.mode == DerivativeMode::Measurement || self.d.window != 1);
+        if proportional_on_measurement
+            || derivative_unsupported
+            || self.resonant.is_some()
+            || self.feedforward.is_some()
+            || self.disturbance_model.is_some()
+            || !self.setpoint_filters.is_empty()
+            || !self.measurement_filters.is_empty()
+            || !self.output_filters.is_empty()
+            || self.wrapping.is_some()
+            || self.estimator.is_some()
+            || self.deadband.is_some()
+            || self.error_squared.is_some()
+            || self.error_shaping.is_some()
+            || self.setpoint_limit != OutputLimits::Unbounded
+        {
+            return Err(ConfigError::NotCompilable);
         }
+        let gains = match self.action {
+            ControllerAction::Direct => self.gains(),
+            ControllerAction::Reverse => {
+                let gains = self.gains();
+                tuning::Gains::new(
+                    T::negative() * gains.kp,
+                    T::negative() * gains.ki,
+                    T::negative() * gains.kd,
+                )
+            }
+        };
+        let mut equation = DifferenceEquation::new(
+            &gains,
+            self.d.time_constant,
+            self.sample_time,
+            self.output_limit,
+        );
+        equation.set_output(self.out);
+        Ok(equation)
+    }
+    pub fn output(&self) -> T {
+        self.out
+    }
+    pub fn integral(&self) -> T {
+        self.i.value
+    }
+    pub fn set_integral(&mut self, integral: T) {
+        self.i.value = integral;
+        self.i.clamp_value();
+    }
+    pub fn derivative(&self) -> T {
+        self.d.value
+    }
+    pub fn set_derivative(&mut self, derivative: T) {
+        self.d.value = derivative;
+    }
+    // The last input seen by the differentiator: the negated measurement or
+    // the error, depending on its mode.
+    pub fn derivative_input(&self) -> T {
+        self.d.previous_input()
+    }
+    pub fn set_derivative_input(&mut self, input: T) {
+        self.d.fill(input);
+    }
+    pub fn set_anti_windup(&mut self, anti_windup: AntiWindup<T>) {
+        self.anti_windup = anti_windup;
     }
     pub fn init(&mut self) {
         self.p.init();
         self.i.init();
         self.d.init();
-        self.out = T::zero();
+        if let Some(resonant) = &mut self.resonant {
+            resonant.init();
+        }
+        if let Some(feedforward) = &mut self.feedforward {
+            feedforward.init();
+        }
+        if let Some(model) = &mut self.disturbance_model {
+            Filter::init(model);
+        }
+        self.setpoint_filters.init();
+        self.unwrapped_measurement = None;
+        self.decimator.init();
+        if let Some(limiter) = &mut self.measurement_slew {
+            limiter.init();
+        }
+        self.measurement_filters.init();
+        self.output_filters.init();
+        self.previous_extra_terms = T::zero();
+        if let Some(estimator) = &mut self.estimator {
+            estimator.init();
+        }
+        #[cfg(feature = "std")]
+        {
+            self.last_update = None;
+        }
+        self.last_tick = None;
+        self.last_timestamp = None;
+        self.jitter.init();
+        self.resuming = false;
+        self.invalid_count = 0;
+        self.last_good_measurement = None;
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.init();
+        }
+        // Start from the nominal output, which is where a loop linearized
+        // around its operating point settles with no error.
+        self.out = self.operating_point.output;
+        self.clamp_output();
+        // A fault outlives a reset until it is acknowledged.
+        if let (Mode::Fault, FaultAction::Output(output)) = (self.mode, self.fault_action) {
+            self.out = output;
+        }
+    }
+    // Warm start: with the derivative cleared, the next update reproduces
+    // `output` as long as the error stays where it was.
+    pub fn reset_to(&mut self, output: T) {
+        self.d.value = T::zero();
+        self.i.value = output
+            - (self.p.gain * self.p.previous_input + self.previous_extra_terms + self.offset());
+        self.i.clamp_value();
+        self.out = output;
+        self.clamp_output();
+        self.output_filters.reset_to(self.out);
+    }
+    // Moves the controller onto an output it did not produce itself, given
+    // the terms of its last update, so the next update continues from there.
+    fn track(&mut self, output: T, terms: &ControlOutput<T>) {
+        self.i.value =
+            output - (terms.p + terms.d + terms.resonant + terms.feedforward + self.offset());
+        self.i.clamp_value();
+        self.out = output;
+        self.clamp_output();
+        self.output_filters.reset_to(self.out);
+    }
+    fn within_deadband(&self, error: T) -> bool {
+        match self.deadband {
+            Some(deadband) => error < deadband && error > T::negative() * deadband,
+            None => false,
+        }
     }
     pub fn update(&mut self, setpoint: T, measurement: T) -> T {
-        let p = self.p.update(setpoint, measurement, &self.sample_time);
-        let i = self.i.update(setpoint, measurement, &self.sample_time);
-        let d = self.d.update(setpoint, measurement, &self.sample_time);
-        self.out = p + i + d;
-        if self.out > self.output_limit.end {
-            self.out = self.output_limit.end;
-        } else if self.out < self.output_limit.start {
-            self.out = self.output_limit.start;
+        self.update_detailed(setpoint, measurement).output
+    }
+    // For periodic measurements: the measurement is unwrapped into a
+    // continuous signal, so filters and the derivative never see the jump at
+    // the wrap point, and the setpoint is moved to the equivalent the wrap
+    // policy picks. `ControlOutput` then reports unwrapped values.
+    //
+    // So that it keeps the precision to resolve a period, the unwrapped
+    // measurement is moved back onto the raw one after `RECENTRE_TURNS`
+    // periods in either direction. The setpoint and measurement paths then
+    // restart from there, as after a resume.
+    pub fn set_wrapping(&mut self, wrapping: Option<Wrapping<T>>) {
+        self.wrapping = wrapping;
+        self.unwrapped_measurement = None;
+    }
+    // Returns the unwrapped setpoint and measurement, and whether the
+    // unwrapped measurement was re-centred.
+    fn unwrap(&mut self, setpoint: T, measurement: T) -> (T, T, bool) {
+        let Some(wrapping) = self.wrapping else {
+            return (setpoint, measurement, false);
         };
-        self.out
+        let unwrapped = match self.unwrapped_measurement {
+            Some((raw, unwrapped)) => unwrapped + wrapping.shortest(measurement - raw),
+            None => measurement,
+        };
+        let limit = T::from_f64(RECENTRE_TURNS) * wrapping.period();
+        let recentre = (unwrapped - measurement).abs() >= limit;
+        let unwrapped = if recentre {
+            self.restart_measurement_path();
+            measurement
+        } else {
+            unwrapped
+        };
+        self.unwrapped_measurement = Some((measurement, unwrapped));
+        (
+            unwrapped + wrapping.error(setpoint - unwrapped),
+            unwrapped,
+            recentre,
+        )
+    }
+    fn restart_measurement_path(&mut self) {
+        if let Some(feedforward) = &mut self.feedforward {
+            feedforward.init();
+        }
+        self.setpoint_filters.init();
+        if let Some(limiter) = &mut self.measurement_slew {
+            limiter.init();
+        }
+        self.measurement_filters.init();
+        if let Some(estimator) = &mut self.estimator {
+            estimator.init();
+        }
+    }
+    // Queues an oversampled raw measurement for the next `update_decimated`.
+    pub fn push_measurement(&mut self, sample: T) {
+        self.decimator.push(sample);
+    }
+    // Updates with the mean of the measurements pushed since the last call,
+    // holding the output until the first one arrives.
+    pub fn update_decimated(&mut self, setpoint: T) -> T {
+        match self.decimator.take() {
+            Some(measurement) => self.update(setpoint, measurement),
+            None => self.out,
+        }
+    }
+    // Adds an externally computed feedforward term, such as a gain times the
+    // velocity of a `trajectory::MotionSetpoint`, to the configured one.
+    pub fn update_with_feedforward(&mut self, setpoint: T, measurement: T, feedforward: T) -> T {
+        let dt = T::from_duration(&self.sample_time);
+        self.step(
+            setpoint,
+            measurement,
+            feedforward,
+            dt,
+            self.sample_time,
+            false,
+        )
+        .output
+    }
+    // Feedforward from a measured disturbance, such as ambient temperature or
+    // feed flow, through the disturbance model: a `LeadLag`, with zero time
+    // constants for a static gain. The gain usually has the opposite sign to
+    // the disturbance's effect on the process, divided by the process gain.
+    pub fn set_disturbance_model(&mut self, model: Option<filter::LeadLag<T>>) {
+        self.disturbance_model = model;
+    }
+    pub fn update_with_disturbance(&mut self, setpoint: T, measurement: T, disturbance: T) -> T {
+        let dt = T::from_duration(&self.sample_time);
+        let feedforward = match &mut self.disturbance_model {
+            Some(model) => Filter::update(model, disturbance, dt),
+            None => T::zero(),
+        };
+        self.step(
+            setpoint,
+            measurement,
+            feedforward,
+            dt,
+            self.sample_time,
+            false,
+        )
+        .output
+    }
+    // Updates from redundant sensors voted down to one measurement. With no
+    // good channel the output is held, as for an implausible measurement.
+    pub fn update_voted<const N: usize>(
+        &mut self,
+        setpoint: T,
+        voter: &mut voting::Voter<T, N>,
+        measurements: &[T; N],
+    ) -> ControlOutput<T> {
+        match voter.vote(measurements).value {
+            Some(measurement) => self.update_detailed(setpoint, measurement),
+            None => {
+                self.resuming = true;
+                let measurement = self.last_good_measurement.unwrap_or(T::zero());
+                let output = ControlOutput {
+                    measurement_invalid: true,
+                    ..self.rejected(setpoint, measurement)
+                };
+                self.last_step = Some((output, self.sample_time, self.sample_time));
+                #[cfg(feature = "trace-hook")]
+                self.trace(&trace::TraceEvent::Update {
+                    mode: self.mode,
+                    output,
+                });
+                output
+            }
+        }
+    }
+    // Updates in tracking mode with the output actually applied to the
+    // actuator, e.g. by another controller or a hand station, so the internal
+    // state follows it and switching back to auto is bumpless.
+    pub fn update_tracking(&mut self, setpoint: T, measurement: T, tracked_output: T) -> T {
+        self.set_mode(Mode::Tracking);
+        self.tracking_output = tracked_output;
+        self.update(setpoint, measurement)
+    }
+    // One update with the output following `tracked_output` as in tracking
+    // mode, but without leaving the current mode, for an outer loop whose
+    // inner loop is not following it. Off, faulted and held controllers are
+    // left to their mode.
+    pub(crate) fn update_detailed_tracked(
+        &mut self,
+        setpoint: T,
+        measurement: T,
+        tracked_output: T,
+    ) -> ControlOutput<T> {
+        self.external_tracking = Some(tracked_output);
+        let output = self.update_detailed(setpoint, measurement);
+        self.external_tracking = None;
+        output
+    }
+    pub fn update_from<S: setpoint::SetpointSource<T>>(
+        &mut self,
+        source: &mut S,
+        measurement: T,
+    ) -> T {
+        let setpoint = source.next_setpoint(&self.sample_time);
+        self.update(setpoint, measurement)
+    }
+    // A step without a duration, such as a bare float, counts as the nominal
+    // sample time in telemetry. A step that is not positive and finite holds
+    // the output and leaves the state alone, like a zero elapsed time in
+    // `update_elapsed`.
+    pub fn update_with_dt<D: Timestep<T>>(&mut self, setpoint: T, measurement: T, dt: D) -> T {
+        let seconds = dt.seconds();
+        if seconds <= T::zero() || !seconds.is_finite() {
+            return self.out;
+        }
+        let elapsed = dt.duration().unwrap_or(self.sample_time);
+        self.step(setpoint, measurement, T::zero(), seconds, elapsed, false)
+            .output
+    }
+    // The integrator and differentiator store their state in continuous-time
+    // units (accumulated gain-weighted error and the filtered derivative), so
+    // they carry over to the new rate unchanged. The gap threshold keeps its
+    // ratio to the sample time, and the jitter statistics restart since they
+    // are relative to the old nominal interval.
+    pub fn set_sample_time(&mut self, sample_time: Duration) -> Result<(), ConfigError> {
+        if sample_time.is_zero() {
+            return Err(ConfigError::ZeroSampleTime);
+        }
+        self.max_gap = Duration::try_from_secs_f64(
+            self.max_gap.as_secs_f64() * sample_time.as_secs_f64() / self.sample_time.as_secs_f64(),
+        )
+        .map_err(|_| ConfigError::MaxGapOverflow)?;
+        self.sample_time = sample_time;
+        self.jitter.init();
+        Ok(())
+    }
+    // Timestamped updates arriving more than `max_gap` after the previous one
+    // are handled according to the gap policy.
+    pub fn set_max_gap(&mut self, max_gap: Duration) {
+        self.max_gap = max_gap;
+    }
+    pub fn set_gap_policy(&mut self, gap_policy: GapPolicy) {
+        self.gap_policy = gap_policy;
+    }
+    #[cfg(feature = "std")]
+    pub fn update_at(&mut self, setpoint: T, measurement: T, now: Instant) -> T {
+        let elapsed = self
+            .last_update
+            .replace(now)
+            .map(|last_update| now.saturating_duration_since(last_update));
+        self.update_elapsed(setpoint, measurement, elapsed)
+    }
+    pub fn update_with_clock<C: Clock>(&mut self, setpoint: T, measurement: T, clock: &C) -> T {
+        let now = clock.now();
+        let elapsed = self
+            .last_tick
+            .replace(now)
+            .map(|last_tick| clock.elapsed(last_tick, now));
+        self.update_elapsed(setpoint, measurement, elapsed)
+    }
+    pub fn update_at_timestamp<S: Timestamp>(&mut self, setpoint: T, measurement: T, now: &S) -> T {
+        let now = now.since_epoch();
+        let elapsed = self
+            .last_timestamp
+            .replace(now)
+            .map(|last_timestamp| now.saturating_sub(last_timestamp));
+        self.update_elapsed(setpoint, measurement, elapsed)
+    }
+    pub fn jitter(&self) -> &JitterStats<T> {
+        &self.jitter
+    }
+    // When disabled, timestamped updates only record jitter and integrate
+    // over the nominal sample time.
+    pub fn set_jitter_compensation(&mut self, compensate_jitter: bool) {
+        self.compensate_jitter = compensate_jitter;
+    }
+    fn update_elapsed(&mut self, setpoint: T, measurement: T, elapsed: Option<Duration>) -> T {
+        if elapsed.is_some_and(|elapsed| elapsed.is_zero()) {
+            return self.out;
+        }
+        if let Some(elapsed) = elapsed {
+            self.jitter
+                .record(T::from_duration(&elapsed) - T::from_duration(&self.sample_time));
+        }
+        let elapsed = elapsed.unwrap_or(self.sample_time);
+        let (dt, skip_derivative) = if elapsed <= self.max_gap {
+            let dt = if self.compensate_jitter {
+                elapsed
+            } else {
+                self.sample_time
+            };
+            (dt, false)
+        } else {
+            match self.gap_policy {
+                GapPolicy::Nominal => (self.sample_time, false),
+                GapPolicy::Integrate => (elapsed, false),
+                // A cap below one sample time would integrate over nothing.
+                GapPolicy::Cap(factor) => (elapsed.min(self.sample_time * factor.max(1)), false),
+                GapPolicy::SkipDerivative => (elapsed, true),
+            }
+        };
+        let output = self
+            .step(
+                setpoint,
+                measurement,
+                T::zero(),
+                T::from_duration(&dt),
+                dt,
+                skip_derivative,
+            )
+            .output;
+        // Telemetry sees the time that actually passed, gaps included.
+        if let Some((_, _, interval)) = &mut self.last_step {
+            *interval = elapsed;
+        }
+        output
+    }
+    pub fn update_detailed(&mut self, setpoint: T, measurement: T) -> ControlOutput<T> {
+        self.step(
+            setpoint,
+            measurement,
+            T::zero(),
+            T::from_duration(&self.sample_time),
+            self.sample_time,
+            false,
+        )
+    }
+    // The report for a sample that did not reach the terms.
+    fn rejected(&self, setpoint: T, measurement: T) -> ControlOutput<T> {
+        ControlOutput {
+            p: T::zero(),
+            i: self.i.value,
+            d: T::zero(),
+            resonant: T::zero(),
+            feedforward: T::zero(),
+            unclamped: self.out,
+            output: self.out,
+            saturated: false,
+            raw_setpoint: setpoint,
+            setpoint,
+            setpoint_clamped: false,
+            measurement,
+            measurement_slew_exceeded: false,
+            non_finite_input: false,
+            measurement_invalid: false,
+            measurement_stale: false,
+        }
+    }
+    fn step(
+        &mut self,
+        setpoint: T,
+        measurement: T,
+        feedforward: T,
+        dt: T,
+        elapsed: Duration,
+        skip_derivative: bool,
+    ) -> ControlOutput<T> {
+        #[cfg(feature = "log-hook")]
+        let previous_integral = self.i.value;
+        let output = self.step_terms(setpoint, measurement, feedforward, dt, skip_derivative);
+        #[cfg(feature = "log-hook")]
+        self.log_update(&output, previous_integral);
+        #[cfg(feature = "trace-hook")]
+        self.trace(&trace::TraceEvent::Update {
+            mode: self.mode,
+            output,
+        });
+        self.last_step = Some((output, elapsed, elapsed));
+        output
+    }
+    fn step_terms(
+        &mut self,
+        setpoint: T,
+        measurement: T,
+        feedforward: T,
+        dt: T,
+        skip_derivative: bool,
+    ) -> ControlOutput<T> {
+        if self.mode == Mode::Hold {
+            return self.rejected(setpoint, measurement);
+        }
+        let finite = setpoint.is_finite()
+            && measurement.is_finite()
+            && feedforward.is_finite()
+            && dt.is_finite();
+        if !finite {
+            match self.non_finite_policy {
+                NonFinitePolicy::Ignore => {}
+                NonFinitePolicy::Hold => self.resuming = true,
+                NonFinitePolicy::Fault => self.trip(FaultCause::NonFiniteInput),
+            }
+            return ControlOutput {
+                non_finite_input: true,
+                ..self.rejected(setpoint, measurement)
+            };
+        }
+        let stale = match &mut self.watchdog {
+            Some(watchdog) => watchdog.feed(measurement, dt).then_some(watchdog.action()),
+            None => None,
+        };
+        match stale {
+            Some(StaleAction::Hold) => self.resuming = true,
+            Some(StaleAction::Fault) => self.trip(FaultCause::StaleMeasurement),
+            None => {}
+        }
+        if stale.is_some() {
+            return ControlOutput {
+                measurement_stale: true,
+                ..self.rejected(setpoint, measurement)
+            };
+        }
+        let measurement_invalid = self.measurement_range.is_above(measurement)
+            || self.measurement_range.is_below(measurement);
+        let measurement = if measurement_invalid {
+            self.invalid_count = self.invalid_count.saturating_add(1);
+            if self
+                .max_invalid
+                .is_some_and(|max| self.invalid_count >= max)
+                || self.substitution == Substitution::FailSafe
+            {
+                self.trip(FaultCause::MeasurementOutOfRange);
+            }
+            match (self.substitution, self.last_good_measurement) {
+                (Substitution::LastGood, Some(last_good)) => last_good,
+                _ => {
+                    return ControlOutput {
+                        measurement_invalid: true,
+                        ..self.rejected(setpoint, measurement)
+                    };
+                }
+            }
+        } else {
+            self.invalid_count = 0;
+            self.last_good_measurement = Some(measurement);
+            measurement
+        };
+        let skip_derivative = skip_derivative || core::mem::take(&mut self.resuming);
+        self.blend_gains();
+        let raw_setpoint = setpoint;
+        let setpoint = self.setpoint_limit.clamp(setpoint);
+        let setpoint_clamped = setpoint != raw_setpoint;
+        let (setpoint, measurement, recentred) = self.unwrap(setpoint, measurement);
+        let skip_derivative = skip_derivative || recentred;
+        let setpoint = if self.mode != Mode::Auto && self.setpoint_tracking {
+            self.setpoint_filters.reset_to(measurement);
+            measurement
+        } else {
+            setpoint
+        };
+        let setpoint = self.setpoint_filters.update(setpoint, dt);
+        let filtered_setpoint = setpoint;
+        let f = match &mut self.feedforward {
+            Some(feedforward) => feedforward.step(setpoint, dt),
+            None => T::zero(),
+        } + feedforward;
+        let (measurement, measurement_slew_exceeded) = match &mut self.measurement_slew {
+            Some(limiter) => limiter.update(measurement, dt),
+            None => (measurement, false),
+        };
+        if measurement_slew_exceeded && self.slew_fault {
+            self.trip(FaultCause::MeasurementSlew);
+        }
+        let measurement = self.measurement_filters.update(measurement, dt);
+        let (measurement, velocity) = match &mut self.estimator {
+            Some(estimator) => {
+                let estimate = estimator.update(measurement, dt);
+                let velocity = Some(estimate.velocity).filter(|_| self.estimated_derivative);
+                (estimate.position, velocity)
+            }
+            None => (measurement, None),
+        };
+        let filtered_measurement = measurement;
+        let (setpoint, measurement) = (
+            setpoint - self.operating_point.measurement,
+            measurement - self.operating_point.measurement,
+        );
+        // Reverse action negates both inputs so the error and the derivative on
+        // measurement both change sign.
+        let (setpoint, measurement, velocity) = match self.action {
+            ControllerAction::Direct => (setpoint, measurement, velocity),
+            ControllerAction::Reverse => (
+                T::negative() * setpoint,
+                T::negative() * measurement,
+                velocity.map(|velocity| T::negative() * velocity),
+            ),
+        };
+        // Errors inside the deadband are presented to the terms as a setpoint equal
+        // to the measurement.
+        let setpoint = if self.within_deadband(setpoint - measurement) {
+            measurement
+        } else {
+            setpoint
+        };
+        // Likewise a shaped error is presented as a shifted setpoint.
+        let setpoint = match self.error_squared {
+            Some(span) => {
+                let error = setpoint - measurement;
+                measurement + error * error.abs() / span
+            }
+            None => setpoint,
+        };
+        let setpoint = match self.error_shaping {
+            Some(shaping) => measurement + shaping.shape(setpoint - measurement),
+            None => setpoint,
+        };
+        let p = self.p.step(setpoint, measurement);
+        let d = if skip_derivative {
+            self.d.hold(setpoint, measurement)
+        } else if let Some(velocity) = velocity {
+            self.d.step_velocity(setpoint, measurement, velocity)
+        } else {
+            self.d.step(setpoint, measurement, dt)
+        };
+        let r = match &mut self.resonant {
+            Some(resonant) => resonant.step(setpoint - measurement),
+            None => T::zero(),
+        };
+        let tracked = self
+            .external_tracking
+            .filter(|_| matches!(self.mode, Mode::Auto | Mode::Manual | Mode::Tracking));
+        if self.mode != Mode::Auto || tracked.is_some() {
+            match (self.mode, tracked) {
+                (_, Some(tracked)) => {
+                    self.out = tracked;
+                    self.clamp_output();
+                }
+                (Mode::Off, None) => self.out = self.off_output,
+                (Mode::Tracking, None) => {
+                    self.out = self.tracking_output;
+                    self.clamp_output();
+                }
+                _ => {}
+            }
+            // Keep the integrator tracking the output so switching back to
+            // auto does not bump the output.
+            let integral = if self.mode == Mode::Off {
+                T::zero()
+            } else {
+                self.out - (p + d + r + f + self.offset())
+            };
+            self.i.preload(integral, setpoint - measurement);
+            self.output_filters.reset_to(self.out);
+            self.previous_extra_terms = r + f;
+            return ControlOutput {
+                p,
+                i: self.i.value,
+                d,
+                resonant: r,
+                feedforward: f,
+                unclamped: self.out,
+                output: self.out,
+                saturated: false,
+                raw_setpoint,
+                setpoint: filtered_setpoint,
+                setpoint_clamped,
+                measurement: filtered_measurement,
+                measurement_slew_exceeded,
+                non_finite_input: false,
+                measurement_invalid,
+                measurement_stale: false,
+            };
+        }
+        let i = match self.anti_windup {
+            AntiWindup::None => self.i.integrate(setpoint - measurement, dt),
+            AntiWindup::Conditional => {
+                let previous = self.i.value;
+                let i = self.i.step(setpoint, measurement, dt);
+                let unsaturated = p + i + d + r + f + self.offset();
+                if (self.output_limit.is_above(unsaturated) && i > previous)
+                    || (self.output_limit.is_below(unsaturated) && i < previous)
+                {
+                    self.i.value = previous;
+                }
+                self.i.value
+            }
+            AntiWindup::Clamp | AntiWindup::BackCalculation { .. } => {
+                self.i.step(setpoint, measurement, dt)
+            }
+        };
+        let unsaturated = p + i + d + r + f + self.offset();
+        let unsaturated = self.output_filters.update(unsaturated, dt);
+        self.out = unsaturated;
+        self.clamp_output();
+        if let AntiWindup::BackCalculation { tracking_gain } = self.anti_windup {
+            self.i.track(tracking_gain, self.out - unsaturated, dt);
+        }
+        self.previous_extra_terms = r + f;
+        ControlOutput {
+            p,
+            i,
+            d,
+            resonant: r,
+            feedforward: f,
+            unclamped: unsaturated,
+            output: self.out,
+            saturated: self.out != unsaturated,
+            raw_setpoint,
+            setpoint: filtered_setpoint,
+            setpoint_clamped,
+            measurement: filtered_measurement,
+            measurement_slew_exceeded,
+            non_finite_input: false,
+            measurement_invalid,
+            measurement_stale: false,
+        }
+    }
+}
+impl<T: Float> ControlLoop<T> for Controller<T> {
+    fn init(&mut self) {
+        Controller::init(self);
+    }
+    fn output(&self) -> T {
+        Controller::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        Controller::update(self, setpoint, measurement)
+    }
+}
+impl<T: Float + fmt::Display> fmt::Display for Controller<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "kp={} ki={} kd={} limits={} sample_time={:?} mode={:?} integral={} derivative={} output={}",
+            self.p.gain,
+            self.i.gain,
+            self.d.gain,
+            self.output_limit,
+            self.sample_time,
+            self.mode,
+            self.i.value,
+            self.d.value,
+            self.out,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTROLLER: Controller<f32> = Controller::new_const(
+        OutputLimits::Inclusive(-10.0, 10.0),
+        Duration::from_millis(100),
+        Proportional::new_const(2.0),
+        Integrator::new_const(0.0, OutputLimits::Unbounded),
+        Differentiator::new_const(0.0, 0.0),
+    );
+
+    #[test]
+    fn const_constructors_build_a_static_controller() {
+        let mut controller = CONTROLLER.clone();
+        assert_eq!(controller.update(1.0, 0.0), 2.0);
+    }
+
+    #[test]
+    fn components_work_as_trait_objects() {
+        let mut p = Proportional::new(2.0);
+        let mut i = Integrator::new(1.0, OutputLimits::Unbounded);
+        let mut components: [&mut dyn ControllerComponent<f32>; 2] = [&mut p, &mut i];
+        let dt = Duration::from_millis(500);
+        let output: f32 = components
+            .iter_mut()
+            .map(|component| component.update(1.0, 0.0, &dt))
+            .sum();
+        assert_eq!(output, 2.25);
+        for component in &mut components {
+            component.init();
+        }
+        let output: f32 = components
+            .iter_mut()
+            .map(|component| component.update(1.0, 0.5, &dt))
+            .sum();
+        assert_eq!(output, 1.125);
+    }
+
+    fn error_derivative_controller() -> Controller<f64> {
+        let mut d = Differentiator::new(0.2, 0.05);
+        d.set_mode(DerivativeMode::Error);
+        Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(1.5),
+            Integrator::new(2.0, OutputLimits::Unbounded),
+            d,
+        )
+    }
+
+    #[test]
+    fn compiled_controller_matches_the_original() {
+        for action in [ControllerAction::Direct, ControllerAction::Reverse] {
+            let mut controller = error_derivative_controller();
+            controller.set_action(action);
+            controller.set_bias(0.5);
+            controller.update(1.0, 1.0);
+            let mut equation = controller.compile().unwrap();
+            for k in 0..50 {
+                let measurement = (k as f64 * 0.3).sin();
+                let expected = controller.update(1.0, measurement);
+                let actual = equation.update(1.0, measurement);
+                assert!((expected - actual).abs() < 1e-9, "{action:?} step {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn wrapped_controller_handles_huge_setpoints() {
+        let mut controller = error_derivative_controller();
+        controller.set_wrapping(Some(Wrapping::degrees()));
+        let report = controller.update_detailed(1e20, 10.0);
+        assert!((-170.0..190.0).contains(&report.setpoint));
+        assert!(report.output.is_finite());
+    }
+
+    #[test]
+    fn unwrapped_measurement_is_recentred() {
+        let mut controller = error_derivative_controller();
+        controller.set_wrapping(Some(Wrapping::degrees()));
+        let mut raw = 0.0;
+        let mut furthest: f64 = 0.0;
+        for _ in 0..140_000 {
+            raw = (raw + 170.0) % 360.0;
+            let report = controller.update_detailed(raw, raw);
+            furthest = furthest.max((report.measurement - raw).abs());
+            assert_eq!(report.setpoint, report.measurement);
+        }
+        assert!(furthest <= RECENTRE_TURNS * 360.0);
+        assert!(controller.output().abs() < 1e-6);
+    }
+
+    #[test]
+    fn compile_refuses_what_it_cannot_represent() {
+        let controller = Controller::<f64>::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(1.5),
+            Integrator::new(2.0, OutputLimits::Unbounded),
+            Differentiator::new(0.2, 0.05),
+        );
+        assert_eq!(controller.compile(), Err(ConfigError::NotCompilable));
+
+        let mut controller = error_derivative_controller();
+        assert!(controller.compile().is_ok());
+        controller.set_deadband(Some(0.1));
+        assert_eq!(controller.compile(), Err(ConfigError::NotCompilable));
+    }
+
+    #[test]
+    fn zero_time_steps_hold_the_output() {
+        let mut controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(1.0),
+            Integrator::new(1.0, OutputLimits::Unbounded),
+            Differentiator::new(0.5, 0.0),
+        );
+        controller.set_feedforward(Some(Feedforward::new(1.0, 0.2)));
+        let output = controller.update(1.0, 0.0);
+        assert_eq!(controller.update_with_dt(2.0, 0.5, Duration::ZERO), output);
+        assert_eq!(controller.update_with_dt(2.0, 0.5, 0.0), output);
+        assert_eq!(controller.update_with_dt(2.0, 0.5, -1.0), output);
+        assert!(controller.update(2.0, 0.5).is_finite());
+
+        // A zero cap still integrates over one sample time.
+        controller.set_max_gap(Duration::from_millis(10));
+        controller.set_gap_policy(GapPolicy::Cap(0));
+        for millis in [0, 10, 100] {
+            let output = controller.update_at_timestamp(2.0, 0.5, &Duration::from_millis(millis));
+            assert!(output.is_finite());
+        }
+    }
+
+    #[test]
+    fn inverted_limits_are_rejected_by_the_setters() {
+        let mut controller = error_derivative_controller();
+        assert_eq!(
+            controller.set_output_limits(1.0..=-1.0),
+            Err(ConfigError::InvertedOutputLimit)
+        );
+        assert_eq!(
+            controller.set_integral_limits(1.0..=-1.0),
+            Err(ConfigError::InvertedIntegralLimit)
+        );
+        assert_eq!(controller.output_limits(), OutputLimits::Unbounded);
+        controller.set_output_limits(-1.0..=1.0).unwrap();
+        controller.set_integral_limits(-0.5..=0.5).unwrap();
+        assert_eq!(controller.update(10.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn reset_to_is_bumpless_with_feedforward_and_output_filters() {
+        let mut controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(1.0),
+            Integrator::new(1.0, OutputLimits::Unbounded),
+            Differentiator::new(0.5, 0.01),
+        );
+        controller.set_feedforward(Some(Feedforward::new(0.5, 0.0)));
+        controller
+            .output_filters_mut()
+            .push(filter::LowPass::new(0.1))
+            .unwrap();
+        for _ in 0..20 {
+            controller.update(2.0, 2.0);
+        }
+        controller.reset_to(3.0);
+        assert_eq!(controller.output(), 3.0);
+        assert!((controller.update(2.0, 2.0) - 3.0).abs() < 1e-12);
+    }
+
+    fn saturating_controller(anti_windup: AntiWindup<f64>) -> Controller<f64> {
+        let mut controller = Controller::new(
+            OutputLimits::Inclusive(-1.0, 1.0),
+            Duration::from_millis(10),
+            Proportional::new(1.0),
+            Integrator::new(1.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        controller.set_anti_windup(anti_windup);
+        controller
+    }
+
+    #[test]
+    fn back_calculation_unwinds_a_saturated_integrator() {
+        let mut clamped = saturating_controller(AntiWindup::Clamp);
+        let mut tracked = saturating_controller(AntiWindup::BackCalculation {
+            tracking_gain: 10.0,
+        });
+        for _ in 0..200 {
+            clamped.update(0.5, 0.0);
+            tracked.update(0.5, 0.0);
+        }
+        assert_eq!(clamped.output(), 1.0);
+        assert_eq!(tracked.output(), 1.0);
+        // The tracked integrator settles where ki e = kt (p + i - limit), at
+        // i = 0.55, while the clamped one has integrated the whole error.
+        assert!((clamped.integral() - 1.0).abs() < 0.01);
+        assert!((tracked.integral() - 0.55).abs() < 0.01);
+        // Once the error is gone the tracked output leaves the limit at once.
+        assert_eq!(clamped.update(0.5, 0.5), 1.0);
+        assert!((tracked.update(0.5, 0.5) - 0.55).abs() < 0.01);
+    }
+
+    #[test]
+    fn conditional_integration_stops_at_the_limit() {
+        let mut unlimited = saturating_controller(AntiWindup::None);
+        let mut conditional = saturating_controller(AntiWindup::Conditional);
+        for _ in 0..200 {
+            unlimited.update(0.5, 0.0);
+            conditional.update(0.5, 0.0);
+        }
+        assert!((unlimited.integral() - 1.0).abs() < 0.01);
+        // Integration stops once p + i reaches the limit.
+        assert!((conditional.integral() - 0.5).abs() < 0.01);
+        assert!((conditional.output() - 1.0).abs() < 0.01);
+
+        // Integration that pulls the output back off the limit is allowed.
+        conditional.set_integral(2.0);
+        conditional.update(0.0, 0.5);
+        conditional.update(0.0, 0.5);
+        assert!(conditional.integral() < 2.0);
+        assert_eq!(conditional.output(), 1.0);
+    }
+
+    #[test]
+    fn returning_from_manual_is_bumpless() {
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        controller.set_mode(Mode::Manual);
+        controller.set_manual_output(0.7);
+        assert_eq!(controller.update(1.0, 0.2), 0.7);
+        assert_eq!(controller.update(1.0, 0.2), 0.7);
+        controller.set_manual_output(5.0);
+        assert_eq!(controller.output(), 1.0);
+        controller.set_manual_output(0.7);
+        controller.update(1.0, 0.2);
+        controller.set_mode(Mode::Auto);
+        // Only one step of integration separates the first auto output from
+        // the manual one.
+        let output = controller.update(1.0, 0.2);
+        assert!((output - 0.708).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gain_changes_do_not_bump_the_output() {
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        for _ in 0..10 {
+            controller.update(0.2, 0.0);
+        }
+        let output = controller.output();
+        controller.set_kp(3.0);
+        controller.set_ki(2.0);
+        assert_eq!(controller.gains(), tuning::Gains::new(3.0, 2.0, 0.0));
+        // The next output differs by one step of the new integral gain.
+        let next = controller.update(0.2, 0.0);
+        assert!((next - output - 0.004).abs() < 1e-12);
+    }
+
+    #[test]
+    fn modes_set_the_output_and_the_integrator() {
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        for _ in 0..10 {
+            controller.update(0.5, 0.0);
+        }
+        controller.set_off_output(-2.0);
+        controller.set_mode(Mode::Off);
+        assert_eq!(controller.output(), -2.0);
+        assert_eq!(controller.update(0.5, 0.0), -2.0);
+        assert_eq!(controller.integral(), 0.0);
+        // Off to auto is a cold start from the proportional term.
+        controller.set_mode(Mode::Auto);
+        assert!((controller.update(0.5, 0.0) - 0.505).abs() < 1e-12);
+
+        controller.set_tracking_output(0.25);
+        controller.set_mode(Mode::Tracking);
+        assert_eq!(controller.update(0.5, 0.0), 0.25);
+        controller.set_tracking_output(3.0);
+        assert_eq!(controller.update(0.5, 0.0), 1.0);
+        controller.set_tracking_output(0.25);
+        controller.update(0.5, 0.0);
+        controller.set_mode(Mode::Auto);
+        assert!((controller.update(0.5, 0.0) - 0.255).abs() < 1e-12);
+
+        controller.set_mode(Mode::Fault);
+        assert_eq!(controller.mode(), Mode::Fault);
+        assert_eq!(controller.fault_cause(), Some(FaultCause::External));
+        controller.set_mode(Mode::Auto);
+        assert_eq!(controller.mode(), Mode::Fault);
+    }
+
+    #[test]
+    fn tracking_follows_the_applied_output() {
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        for tracked in [0.1, 0.3, -0.4] {
+            assert_eq!(controller.update_tracking(0.5, 0.2, tracked), tracked);
+            assert_eq!(controller.mode(), Mode::Tracking);
+        }
+        assert_eq!(controller.update_tracking(0.5, 0.2, -4.0), -1.0);
+        controller.update_tracking(0.5, 0.2, -0.4);
+        controller.set_mode(Mode::Auto);
+        assert!((controller.update(0.5, 0.2) + 0.397).abs() < 1e-12);
+    }
+
+    #[test]
+    fn faults_apply_the_fail_safe_output_until_acknowledged() {
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        controller.set_fault_action(FaultAction::Output(-5.0));
+        controller.update(0.5, 0.0);
+        controller.trip(FaultCause::External);
+        controller.trip(FaultCause::MeasurementSlew);
+        assert_eq!(controller.fault_cause(), Some(FaultCause::External));
+        // The fail-safe output is not limited.
+        assert_eq!(controller.update(0.5, 0.0), -5.0);
+        controller.init();
+        assert_eq!(controller.output(), -5.0);
+
+        // Acknowledging leaves manual mode at the limited fail-safe output.
+        assert!(controller.acknowledge_fault());
+        assert!(!controller.acknowledge_fault());
+        assert_eq!(controller.mode(), Mode::Manual);
+        assert_eq!(controller.fault_cause(), None);
+        assert_eq!(controller.update(0.5, 0.0), -1.0);
+
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        let output = controller.update(0.5, 0.0);
+        controller.trip(FaultCause::External);
+        assert_eq!(controller.update(2.0, 0.0), output);
+    }
+
+    fn derivative_controller(policy: NonFinitePolicy) -> Controller<f64> {
+        let mut controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(1.0),
+            Integrator::new(1.0, OutputLimits::Unbounded),
+            Differentiator::new(0.5, 0.0),
+        );
+        controller.set_non_finite_policy(policy);
+        controller
+    }
+
+    #[test]
+    fn non_finite_inputs_never_reach_the_state() {
+        for policy in [
+            NonFinitePolicy::Ignore,
+            NonFinitePolicy::Hold,
+            NonFinitePolicy::Fault,
+        ] {
+            let mut controller = derivative_controller(policy);
+            controller.update(1.0, 0.0);
+            let (output, integral) = (controller.output(), controller.integral());
+            for (setpoint, measurement) in [(f64::NAN, 0.0), (1.0, f64::INFINITY)] {
+                let report = controller.update_detailed(setpoint, measurement);
+                assert!(report.non_finite_input, "{policy:?}");
+                assert_eq!(report.output, output, "{policy:?}");
+                assert_eq!(controller.integral(), integral, "{policy:?}");
+            }
+            let expected_mode = match policy {
+                NonFinitePolicy::Fault => Mode::Fault,
+                _ => Mode::Auto,
+            };
+            assert_eq!(controller.mode(), expected_mode);
+        }
+    }
+
+    #[test]
+    fn hold_policy_resumes_without_a_derivative_step() {
+        let mut ignoring = derivative_controller(NonFinitePolicy::Ignore);
+        let mut holding = derivative_controller(NonFinitePolicy::Hold);
+        for controller in [&mut ignoring, &mut holding] {
+            controller.update(1.0, 0.0);
+            controller.update(1.0, 0.0);
+            controller.update(1.0, f64::NAN);
+        }
+        assert_eq!(ignoring.update_detailed(1.0, 0.5).d, -25.0);
+        assert_eq!(holding.update_detailed(1.0, 0.5).d, 0.0);
+        assert!((holding.update_detailed(1.0, 0.6).d + 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn implausible_measurements_are_substituted() {
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        controller
+            .set_measurement_range(-1.0..=1.0, Substitution::LastGood, Some(3))
+            .unwrap();
+        // Nothing to substitute before the first good measurement.
+        let report = controller.update_detailed(0.5, 2.0);
+        assert!(report.measurement_invalid);
+        assert_eq!(report.output, 0.0);
+
+        controller.update(0.5, 0.25);
+        for _ in 0..2 {
+            let report = controller.update_detailed(0.5, -3.0);
+            assert!(report.measurement_invalid);
+            assert_eq!(report.measurement, 0.25);
+            assert_eq!(controller.mode(), Mode::Auto);
+        }
+        controller.update(0.5, -3.0);
+        assert_eq!(controller.mode(), Mode::Fault);
+        assert_eq!(
+            controller.fault_cause(),
+            Some(FaultCause::MeasurementOutOfRange)
+        );
+
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        assert_eq!(
+            controller.set_measurement_range(1.0..=-1.0, Substitution::FailSafe, None),
+            Err(ConfigError::InvertedMeasurementRange)
+        );
+        controller
+            .set_measurement_range(-1.0..=1.0, Substitution::FailSafe, None)
+            .unwrap();
+        controller.update(0.5, 0.25);
+        controller.update(0.5, 1.5);
+        assert_eq!(controller.mode(), Mode::Fault);
+    }
+
+    #[test]
+    fn hold_freezes_the_controller_and_resumes_cleanly() {
+        let mut controller = derivative_controller(NonFinitePolicy::Ignore);
+        controller.update_at_timestamp(1.0, 0.0, &Duration::from_millis(0));
+        controller.update_at_timestamp(1.0, 0.0, &Duration::from_millis(10));
+        let (output, integral) = (controller.output(), controller.integral());
+        controller.set_watchdog(Some(Watchdog::new(
+            Duration::from_millis(50),
+            watchdog::Freshness::Update,
+            StaleAction::Fault,
+        )));
+        controller.set_mode(Mode::Hold);
+        for millis in [20, 30, 5000] {
+            let now = Duration::from_millis(millis);
+            assert_eq!(controller.update_at_timestamp(3.0, 0.5, &now), output);
+        }
+        assert_eq!(controller.integral(), integral);
+        controller.tick_watchdog(Duration::from_secs(10));
+        assert_eq!(controller.mode(), Mode::Hold);
+
+        // The first update after the hold integrates one sample time and
+        // takes no derivative step.
+        controller.set_mode(Mode::Auto);
+        controller.update_at_timestamp(1.0, 0.5, &Duration::from_millis(5010));
+        let (report, dt, _) = controller.last_step.unwrap();
+        assert_eq!(dt, Duration::from_millis(10));
+        assert_eq!(report.d, 0.0);
+        assert!((report.i - integral - 0.0075).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gain_changes_blend_over_the_configured_samples() {
+        let mut controller = saturating_controller(AntiWindup::Clamp);
+        controller.set_gain_blending(4);
+        controller.set_kp(3.0);
+        controller.set_kd(0.2);
+        assert_eq!(controller.gains(), tuning::Gains::new(1.0, 1.0, 0.0));
+        assert_eq!(
+            controller.gain_target(),
+            Some(tuning::Gains::new(3.0, 1.0, 0.2))
+        );
+        for (kp, kd) in [(1.5, 0.05), (2.0, 0.1), (2.5, 0.15)] {
+            controller.update(0.0, 0.0);
+            let gains = controller.gains();
+            assert!((gains.kp - kp).abs() < 1e-12 && (gains.kd - kd).abs() < 1e-12);
+        }
+        controller.update(0.0, 0.0);
+        assert_eq!(controller.gains(), tuning::Gains::new(3.0, 1.0, 0.2));
+        assert_eq!(controller.gain_target(), None);
+
+        // Turning blending off applies a blend in progress at once.
+        controller.set_ki(3.0);
+        controller.update(0.0, 0.0);
+        assert_eq!(
+            controller.gain_target(),
+            Some(tuning::Gains::new(3.0, 3.0, 0.2))
+        );
+        controller.set_gain_blending(0);
+        assert_eq!(controller.gains(), tuning::Gains::new(3.0, 3.0, 0.2));
+        assert_eq!(controller.gain_target(), None);
+    }
+
+    #[test]
+    fn sample_time_changes_that_overflow_the_max_gap_are_rejected() {
+        let mut controller = error_derivative_controller();
+        controller.set_max_gap(Duration::MAX);
+        assert_eq!(
+            controller.set_sample_time(Duration::from_secs(1)),
+            Err(ConfigError::MaxGapOverflow)
+        );
+        assert_eq!(controller.sample_time, Duration::from_millis(10));
+        controller.set_max_gap(Duration::from_millis(50));
+        controller
+            .set_sample_time(Duration::from_millis(20))
+            .unwrap();
+        assert_eq!(controller.max_gap, Duration::from_millis(100));
     }
 }
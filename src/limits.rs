@@ -0,0 +1,128 @@
+use core::{
+    fmt,
+    ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeToInclusive},
+};
+
+use crate::Float;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputLimits<T: Float> {
+    Unbounded,
+    Lower(T),
+    Upper(T),
+    Inclusive(T, T),
+}
+impl<T: Float> OutputLimits<T> {
+    pub fn lower(&self) -> Option<T> {
+        match *self {
+            OutputLimits::Lower(lower) | OutputLimits::Inclusive(lower, _) => Some(lower),
+            OutputLimits::Unbounded | OutputLimits::Upper(_) => None,
+        }
+    }
+    pub fn upper(&self) -> Option<T> {
+        match *self {
+            OutputLimits::Upper(upper) | OutputLimits::Inclusive(_, upper) => Some(upper),
+            OutputLimits::Unbounded | OutputLimits::Lower(_) => None,
+        }
+    }
+    pub fn is_inverted(&self) -> bool {
+        matches!(*self, OutputLimits::Inclusive(lower, upper) if upper < lower)
+    }
+    pub fn is_above(&self, value: T) -> bool {
+        self.upper().is_some_and(|upper| value > upper)
+    }
+    pub fn is_below(&self, value: T) -> bool {
+        self.lower().is_some_and(|lower| value < lower)
+    }
+    pub fn clamp(&self, value: T) -> T {
+        match (self.lower(), self.upper()) {
+            (_, Some(upper)) if value > upper => upper,
+            (Some(lower), _) if value < lower => lower,
+            _ => value,
+        }
+    }
+}
+impl<T: Float + fmt::Display> fmt::Display for OutputLimits<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputLimits::Unbounded => write!(f, "(-inf, inf)"),
+            OutputLimits::Lower(lower) => write!(f, "[{}, inf)", lower),
+            OutputLimits::Upper(upper) => write!(f, "(-inf, {}]", upper),
+            OutputLimits::Inclusive(lower, upper) => write!(f, "[{}, {}]", lower, upper),
+        }
+    }
+}
+impl<T: Float> From<Range<T>> for OutputLimits<T> {
+    fn from(range: Range<T>) -> Self {
+        OutputLimits::Inclusive(range.start, range.end)
+    }
+}
+impl<T: Float> From<RangeInclusive<T>> for OutputLimits<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let (lower, upper) = range.into_inner();
+        OutputLimits::Inclusive(lower, upper)
+    }
+}
+impl<T: Float> From<RangeFrom<T>> for OutputLimits<T> {
+    fn from(range: RangeFrom<T>) -> Self {
+        OutputLimits::Lower(range.start)
+    }
+}
+impl<T: Float> From<RangeToInclusive<T>> for OutputLimits<T> {
+    fn from(range: RangeToInclusive<T>) -> Self {
+        OutputLimits::Upper(range.end)
+    }
+}
+impl<T: Float> From<RangeFull> for OutputLimits<T> {
+    fn from(_: RangeFull) -> Self {
+        OutputLimits::Unbounded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_respects_each_bound() {
+        assert_eq!(OutputLimits::Unbounded.clamp(1e9), 1e9);
+        assert_eq!(OutputLimits::Lower(-1.0).clamp(-5.0), -1.0);
+        assert_eq!(OutputLimits::Lower(-1.0).clamp(5.0), 5.0);
+        assert_eq!(OutputLimits::Upper(1.0).clamp(5.0), 1.0);
+        assert_eq!(OutputLimits::Upper(1.0).clamp(-5.0), -5.0);
+        let band = OutputLimits::Inclusive(-1.0, 2.0);
+        assert_eq!(band.clamp(-3.0), -1.0);
+        assert_eq!(band.clamp(0.5), 0.5);
+        assert_eq!(band.clamp(3.0), 2.0);
+        assert!(band.is_above(2.5) && !band.is_above(2.0));
+        assert!(band.is_below(-1.5) && !band.is_below(-1.0));
+        assert!(!band.is_inverted());
+        assert!(OutputLimits::Inclusive(2.0, -1.0).is_inverted());
+    }
+
+    #[test]
+    fn ranges_convert_to_limits() {
+        assert_eq!(
+            OutputLimits::from(-1.0..2.0),
+            OutputLimits::Inclusive(-1.0, 2.0)
+        );
+        assert_eq!(
+            OutputLimits::from(-1.0..=2.0),
+            OutputLimits::Inclusive(-1.0, 2.0)
+        );
+        assert_eq!(OutputLimits::from(-1.0..), OutputLimits::Lower(-1.0));
+        assert_eq!(OutputLimits::from(..=2.0), OutputLimits::Upper(2.0));
+        assert_eq!(OutputLimits::<f64>::from(..), OutputLimits::Unbounded);
+        assert_eq!(OutputLimits::from(-1.0..).upper(), None);
+        assert_eq!(OutputLimits::from(..=2.0).lower(), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn limits_display_as_intervals() {
+        assert_eq!(OutputLimits::<f64>::Unbounded.to_string(), "(-inf, inf)");
+        assert_eq!(OutputLimits::Lower(1.5).to_string(), "[1.5, inf)");
+        assert_eq!(OutputLimits::Upper(1.5).to_string(), "(-inf, 1.5]");
+        assert_eq!(OutputLimits::Inclusive(-1.0, 1.5).to_string(), "[-1, 1.5]");
+    }
+}
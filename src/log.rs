@@ -0,0 +1,128 @@
+use crate::{FaultCause, Float};
+
+// Warnings a controller reports with the `log-hook` feature. The crate does
+// not depend on any logging library; the warnings go to a `Logger` callback
+// that writes them to the application log, e.g. with `log::warn!`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Warning<T: Float> {
+    // The output was clamped to its limits.
+    Saturated { unclamped: T, output: T },
+    // The integral kept growing while the output was saturated.
+    Windup { integral: T },
+    // An update was rejected for a NaN or infinite input.
+    NonFiniteInput,
+    Fault(FaultCause),
+    FaultCleared,
+}
+
+// Each kind of per-update warning is reported at most once every
+// `min_interval` updates, so a loop that stays saturated does not flood the
+// log. Fault transitions are always reported.
+#[derive(Debug, Clone, Copy)]
+pub struct Logger<T: Float> {
+    log: fn(&Warning<T>),
+    min_interval: u32,
+    // Updates left before each kind may be reported again, indexed by
+    // `Logger::kind`.
+    quiet: [u32; 3],
+}
+impl<T: Float> Logger<T> {
+    pub const fn new(log: fn(&Warning<T>), min_interval: u32) -> Self {
+        Self {
+            log,
+            min_interval,
+            quiet: [0; 3],
+        }
+    }
+    pub(crate) fn tick(&mut self) {
+        for quiet in &mut self.quiet {
+            *quiet = quiet.saturating_sub(1);
+        }
+    }
+    pub(crate) fn warn(&mut self, warning: Warning<T>) {
+        if let Some(kind) = Self::kind(&warning) {
+            if self.quiet[kind] > 0 {
+                return;
+            }
+            self.quiet[kind] = self.min_interval;
+        }
+        (self.log)(&warning);
+    }
+    fn kind(warning: &Warning<T>) -> Option<usize> {
+        match warning {
+            Warning::Saturated { .. } => Some(0),
+            Warning::Windup { .. } => Some(1),
+            Warning::NonFiniteInput => Some(2),
+            Warning::Fault(_) | Warning::FaultCleared => None,
+        }
+    }
+}
+impl<T: Float> PartialEq for Logger<T> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::fn_addr_eq(self.log, other.log)
+            && self.min_interval == other.min_interval
+            && self.quiet == other.quiet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{Controller, Differentiator, Integrator, OutputLimits, Proportional};
+
+    static RATE_LIMITED: [AtomicU32; 3] = [const { AtomicU32::new(0) }; 3];
+    fn count_by_kind(warning: &Warning<f64>) {
+        let kind = match warning {
+            Warning::Saturated { .. } => 0,
+            Warning::Fault(_) | Warning::FaultCleared => 1,
+            _ => 2,
+        };
+        RATE_LIMITED[kind].fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn repeated_warnings_are_rate_limited() {
+        let mut logger = Logger::new(count_by_kind, 3);
+        for _ in 0..7 {
+            logger.tick();
+            logger.warn(Warning::Saturated {
+                unclamped: 2.0,
+                output: 1.0,
+            });
+            logger.warn(Warning::FaultCleared);
+        }
+        assert_eq!(RATE_LIMITED[0].load(Ordering::Relaxed), 3);
+        assert_eq!(RATE_LIMITED[1].load(Ordering::Relaxed), 7);
+        assert_eq!(RATE_LIMITED[2].load(Ordering::Relaxed), 0);
+    }
+
+    static SATURATED: AtomicU32 = AtomicU32::new(0);
+    fn count_saturated(warning: &Warning<f64>) {
+        if let Warning::Saturated { unclamped, output } = warning {
+            assert!(unclamped > output);
+            SATURATED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn controller_reports_saturation() {
+        let mut controller = Controller::new(
+            OutputLimits::Inclusive(-1.0, 1.0),
+            Duration::from_millis(10),
+            Proportional::new(5.0),
+            Integrator::new(0.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        controller.set_logger(Some(Logger::new(count_saturated, 5)));
+        for _ in 0..10 {
+            controller.update(1.0, 0.0);
+        }
+        controller.update(0.1, 0.0);
+        assert_eq!(SATURATED.load(Ordering::Relaxed), 2);
+    }
+}
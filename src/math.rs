@@ -0,0 +1,385 @@
+// Software versions of the float functions that `core` does not provide,
+// used in place of the std ones when building without `std`.
+//
+// They use only basic IEEE 754 operations in a fixed order, so unlike the
+// platform libm they give bit-identical results on every target with IEEE
+// arithmetic (x86-64 SSE2, AArch64, ARMv7 with VFP, wasm). The
+// `deterministic` feature uses them even with `std`, so a std build on a
+// desktop and a no_std build on the target agree bit for bit.
+
+use core::cmp::Ordering;
+
+use crate::Float;
+
+#[cfg(any(not(feature = "std"), feature = "deterministic"))]
+const SUBNORMAL_SCALE: f64 = 18014398509481984.0; // 2^54
+
+// sqrt and ln are within a couple of ulps over the whole f64 range.
+#[cfg(any(not(feature = "std"), feature = "deterministic"))]
+pub(crate) fn sqrt(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 || x == f64::INFINITY {
+        return x;
+    }
+    if x < f64::MIN_POSITIVE {
+        // sqrt(2^54) = 2^27
+        return sqrt(x * SUBNORMAL_SCALE) / 134217728.0;
+    }
+    // Halving the biased exponent gives a guess within a few percent, which
+    // Newton's method then refines quadratically.
+    let mut y = f64::from_bits((x.to_bits() >> 1) + (1023 << 51));
+    for _ in 0..6 {
+        y = 0.5 * (y + x / y);
+    }
+    y
+}
+
+#[cfg(any(not(feature = "std"), feature = "deterministic"))]
+pub(crate) fn ln(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if x == f64::INFINITY {
+        return x;
+    }
+    if x < f64::MIN_POSITIVE {
+        return ln(x * SUBNORMAL_SCALE) - 54.0 * core::f64::consts::LN_2;
+    }
+    // Split x = m * 2^e with m in [sqrt(1/2), sqrt(2)), then expand
+    // ln(m) = 2 * atanh(s) with s = (m - 1) / (m + 1), |s| < 0.172.
+    let bits = x.to_bits();
+    let mut exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mut mantissa = f64::from_bits((bits & 0x000f_ffff_ffff_ffff) | (1023 << 52));
+    if mantissa > core::f64::consts::SQRT_2 {
+        mantissa *= 0.5;
+        exponent += 1;
+    }
+    let s = (mantissa - 1.0) / (mantissa + 1.0);
+    let s2 = s * s;
+    let mut term = s;
+    let mut sum = 0.0;
+    let mut k = 1.0;
+    while k < 40.0 {
+        sum += term / k;
+        term *= s2;
+        k += 2.0;
+    }
+    2.0 * sum + exponent as f64 * core::f64::consts::LN_2
+}
+
+// Exact, so it is used with and without std. Doubles of 2^52 and above are
+// already whole, and casting them to i64 could saturate.
+pub(crate) fn floor(x: f64) -> f64 {
+    if x.is_nan() || x.abs() >= 4503599627370496.0 {
+        return x;
+    }
+    let whole = x as i64 as f64;
+    if whole > x {
+        whole - 1.0
+    } else {
+        whole
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "deterministic")))]
+pub(crate) fn sin_cos(x: f64) -> (f64, f64) {
+    x.sin_cos()
+}
+#[cfg(any(not(feature = "std"), feature = "deterministic"))]
+pub(crate) fn sin_cos(x: f64) -> (f64, f64) {
+    use core::f64::consts::{PI, TAU};
+    if !x.is_finite() {
+        return (f64::NAN, f64::NAN);
+    }
+    // Reduce to [-pi, pi], where the Taylor series below is below 1e-21 by
+    // the 34th power; cancellation between terms limits the result to about
+    // 1e-15 absolute error.
+    let turns = x / TAU;
+    let mut whole = turns as i64 as f64;
+    if whole > turns {
+        whole -= 1.0;
+    }
+    let mut x = x - whole * TAU;
+    if x > PI {
+        x -= TAU;
+    }
+    let x2 = x * x;
+    let mut sin = 0.0;
+    let mut cos = 0.0;
+    let mut sin_term = x;
+    let mut cos_term = 1.0;
+    let mut n = 0.0;
+    while n < 34.0 {
+        sin += sin_term;
+        cos += cos_term;
+        sin_term *= -x2 / ((n + 2.0) * (n + 3.0));
+        cos_term *= -x2 / ((n + 1.0) * (n + 2.0));
+        n += 2.0;
+    }
+    (sin, cos)
+}
+
+// Generic versions behind the `Float` defaults, for types that only supply
+// the basic constants. They use nothing but the arithmetic bounds, so they
+// are exact to the precision of the type but slow.
+
+fn nan<T: Float>() -> T {
+    T::zero() / T::zero()
+}
+
+// Rebuilds the value one bit of its binary expansion at a time, so every
+// f64 converts exactly where the type can represent it.
+pub(crate) fn generic_from_f64<T: Float>(value: f64) -> T {
+    if value.is_nan() {
+        return nan();
+    }
+    let negative = value < 0.0;
+    let magnitude = if negative { -value } else { value };
+    let result = if magnitude == f64::INFINITY {
+        T::one() / T::zero()
+    } else if magnitude == 0.0 {
+        T::zero()
+    } else {
+        // The largest power of two not above the magnitude, in both types.
+        let mut power = 1.0;
+        let mut scale = T::one();
+        while power * 2.0 <= magnitude && power * 2.0 != f64::INFINITY {
+            power *= 2.0;
+            scale = scale * T::double();
+        }
+        while power > magnitude {
+            power *= 0.5;
+            scale = scale * T::half();
+        }
+        let mut remainder = magnitude;
+        let mut result = T::zero();
+        while remainder > 0.0 && power > 0.0 {
+            if remainder >= power {
+                remainder -= power;
+                result = result + scale;
+            }
+            power *= 0.5;
+            scale = scale * T::half();
+        }
+        result
+    };
+    if negative {
+        T::negative() * result
+    } else {
+        result
+    }
+}
+
+// Reduces x to m * 2^k with m in [1, 2) and expands
+// ln(m) = 2 * atanh(s) with s = (m - 1) / (m + 1) < 1/3.
+pub(crate) fn generic_ln<T: Float>(x: T) -> T {
+    match x.partial_cmp(&T::zero()) {
+        None | Some(Ordering::Less) => return nan(),
+        Some(Ordering::Equal) => return T::negative() / T::zero(),
+        Some(Ordering::Greater) if !x.is_finite() => return x,
+        Some(Ordering::Greater) => {}
+    }
+    let mut mantissa = x;
+    let mut exponent = T::zero();
+    while mantissa >= T::double() {
+        mantissa = mantissa * T::half();
+        exponent = exponent + T::one();
+    }
+    while mantissa < T::one() {
+        mantissa = mantissa * T::double();
+        exponent = exponent - T::one();
+    }
+    let s = (mantissa - T::one()) / (mantissa + T::one());
+    let s2 = s * s;
+    let mut term = s;
+    let mut k = T::one();
+    let mut sum = T::zero();
+    for _ in 0..40 {
+        sum = sum + term / k;
+        term = term * s2;
+        k = k + T::double();
+    }
+    T::double() * sum + exponent * T::from_f64(core::f64::consts::LN_2)
+}
+
+// Reduces x to m * 4^k with m in [1, 4), where Newton's method from
+// (1 + m) / 2 converges from above, and scales the root back by 2^k.
+pub(crate) fn generic_sqrt<T: Float>(x: T) -> T {
+    match x.partial_cmp(&T::zero()) {
+        None | Some(Ordering::Less) => return nan(),
+        Some(Ordering::Equal) => return x,
+        Some(Ordering::Greater) if !x.is_finite() => return x,
+        Some(Ordering::Greater) => {}
+    }
+    let four = T::double() * T::double();
+    let quarter = T::half() * T::half();
+    let mut mantissa = x;
+    let mut scale = T::one();
+    while mantissa >= four {
+        mantissa = mantissa * quarter;
+        scale = scale * T::double();
+    }
+    while mantissa < T::one() {
+        mantissa = mantissa * four;
+        scale = scale * T::half();
+    }
+    let mut root = T::half() * (T::one() + mantissa);
+    for _ in 0..64 {
+        let next = T::half() * (root + mantissa / root);
+        if next >= root {
+            break;
+        }
+        root = next;
+    }
+    root * scale
+}
+
+// Builds the whole part of |x| from descending powers of two, which stays
+// exact however many fraction bits the type has.
+pub(crate) fn generic_floor<T: Float>(x: T) -> T {
+    if !x.is_finite() {
+        return x;
+    }
+    let magnitude = x.abs();
+    let mut power = T::one();
+    while power + power <= magnitude {
+        power = power + power;
+    }
+    let mut whole = T::zero();
+    while power >= T::one() {
+        if whole + power <= magnitude {
+            whole = whole + power;
+        }
+        power = power * T::half();
+    }
+    if x >= T::zero() {
+        whole
+    } else if whole < magnitude {
+        T::zero() - whole - T::one()
+    } else {
+        T::zero() - whole
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        ops::{Add, Div, Mul, Sub},
+        time::Duration,
+    };
+
+    use super::*;
+
+    // A numeric type implementing only the methods `Float` originally
+    // required, so every other item comes from the defaults.
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    struct Basic(f64);
+    impl Add for Basic {
+        type Output = Self;
+        fn add(self, other: Self) -> Self {
+            Self(self.0 + other.0)
+        }
+    }
+    impl Sub for Basic {
+        type Output = Self;
+        fn sub(self, other: Self) -> Self {
+            Self(self.0 - other.0)
+        }
+    }
+    impl Mul for Basic {
+        type Output = Self;
+        fn mul(self, other: Self) -> Self {
+            Self(self.0 * other.0)
+        }
+    }
+    impl Div for Basic {
+        type Output = Self;
+        fn div(self, other: Self) -> Self {
+            Self(self.0 / other.0)
+        }
+    }
+    impl Float for Basic {
+        fn negative() -> Self {
+            Self(-1.0)
+        }
+        fn double() -> Self {
+            Self(2.0)
+        }
+        fn one() -> Self {
+            Self(1.0)
+        }
+        fn half() -> Self {
+            Self(0.5)
+        }
+        fn zero() -> Self {
+            Self(0.0)
+        }
+        fn from_duration(dur: &Duration) -> Self {
+            Self(dur.as_secs_f64())
+        }
+    }
+
+    #[test]
+    fn from_f64_is_exact() {
+        for value in [0.0, 1.0, -1.0, 0.1, -3.75, 1e-300, 5e-324, 1e300, f64::MAX] {
+            assert_eq!(Basic::from_f64(value), Basic(value));
+        }
+        assert_eq!(Basic::from_f64(f64::INFINITY), Basic(f64::INFINITY));
+        assert_eq!(Basic::from_f64(f64::NEG_INFINITY), Basic(f64::NEG_INFINITY));
+        assert!(Basic::from_f64(f64::NAN).0.is_nan());
+    }
+
+    #[test]
+    fn ln_matches_std() {
+        for value in [1e-300, 0.001, 0.5, 1.0, 1.5, 2.0, 10.0, 12345.678, 1e300] {
+            let expected = value.ln();
+            let error = (Basic(value).ln().0 - expected).abs();
+            assert!(error <= 1e-14 * expected.abs().max(1.0), "ln({value})");
+        }
+        assert_eq!(Basic(0.0).ln(), Basic(f64::NEG_INFINITY));
+        assert!(Basic(-1.0).ln().0.is_nan());
+        assert_eq!(Basic(f64::INFINITY).ln(), Basic(f64::INFINITY));
+    }
+
+    #[test]
+    fn sqrt_matches_std() {
+        for value in [1e-300, 0.001, 0.5, 1.0, 2.0, 3.99, 4.0, 10.0, 1e300] {
+            let expected = value.sqrt();
+            let error = (Basic(value).sqrt().0 - expected).abs();
+            assert!(error <= 1e-15 * expected, "sqrt({value})");
+        }
+        assert_eq!(Basic(0.0).sqrt(), Basic(0.0));
+        assert!(Basic(-1.0).sqrt().0.is_nan());
+    }
+
+    #[test]
+    fn floor_matches_std() {
+        for value in [
+            0.0,
+            0.5,
+            -0.5,
+            1.0,
+            -1.0,
+            2.75,
+            -2.75,
+            1e15 + 0.5,
+            -1e15 - 0.5,
+            1e20,
+            -1e300,
+        ] {
+            assert_eq!(
+                Basic(value).floor().0,
+                value.floor(),
+                "generic floor({value})"
+            );
+            assert_eq!(floor(value), value.floor(), "floor({value})");
+        }
+        assert!(floor(f64::NAN).is_nan());
+        assert_eq!(floor(f64::NEG_INFINITY), f64::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{bank::ControllerBank, ConfigError, Float, OutputLimits, Timestep};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecouplingPoint {
+    // Each loop sees a mix of all the errors.
+    Error,
+    // Each actuator receives a mix of all the loop outputs.
+    Output,
+}
+
+// N interacting loops, such as temperature zones with thermal crosstalk: a
+// bank of PID loops with a static N x N decoupling matrix applied to the
+// error vector or to the output vector. The matrix is usually the inverse of
+// the plant's steady-state gain matrix, scaled so its diagonal is one.
+// Decoupled outputs are clamped again to the per-actuator limits, which
+// default to unbounded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MimoController<T: Float, const N: usize> {
+    bank: ControllerBank<T, N>,
+    decoupling: [[T; N]; N],
+    point: DecouplingPoint,
+    output_limits: [OutputLimits<T>; N],
+    out: [T; N],
+}
+impl<T: Float, const N: usize> MimoController<T, N> {
+    pub fn new(
+        bank: ControllerBank<T, N>,
+        decoupling: [[T; N]; N],
+        point: DecouplingPoint,
+    ) -> Self {
+        Self {
+            bank,
+            decoupling,
+            point,
+            output_limits: [OutputLimits::Unbounded; N],
+            out: [T::zero(); N],
+        }
+    }
+    // No decoupling: the loops run independently.
+    pub fn identity() -> [[T; N]; N] {
+        core::array::from_fn(|row| {
+            core::array::from_fn(|column| if row == column { T::one() } else { T::zero() })
+        })
+    }
+    pub fn bank(&self) -> &ControllerBank<T, N> {
+        &self.bank
+    }
+    pub fn bank_mut(&mut self) -> &mut ControllerBank<T, N> {
+        &mut self.bank
+    }
+    pub fn set_decoupling(&mut self, decoupling: [[T; N]; N], point: DecouplingPoint) {
+        self.decoupling = decoupling;
+        self.point = point;
+    }
+    pub fn set_output_limits(
+        &mut self,
+        actuator: usize,
+        output_limit: impl Into<OutputLimits<T>>,
+    ) -> Result<(), ConfigError> {
+        let output_limit = output_limit.into();
+        if output_limit.is_inverted() {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        self.output_limits[actuator] = output_limit;
+        self.out[actuator] = output_limit.clamp(self.out[actuator]);
+        Ok(())
+    }
+    pub fn init(&mut self) {
+        self.bank.init();
+        self.out = [T::zero(); N];
+    }
+    pub fn output(&self) -> &[T; N] {
+        &self.out
+    }
+    pub fn update(&mut self, setpoints: &[T; N], measurements: &[T; N]) -> &[T; N] {
+        let setpoints = self.decouple_errors(setpoints, measurements);
+        let outputs = *self.bank.update(&setpoints, measurements);
+        self.decouple_outputs(&outputs)
+    }
+    pub fn update_with_dt<D: Timestep<T>>(
+        &mut self,
+        setpoints: &[T; N],
+        measurements: &[T; N],
+        dt: D,
+    ) -> &[T; N] {
+        let setpoints = self.decouple_errors(setpoints, measurements);
+        let outputs = *self.bank.update_with_dt(&setpoints, measurements, dt);
+        self.decouple_outputs(&outputs)
+    }
+    fn multiply(&self, vector: &[T; N]) -> [T; N] {
+        core::array::from_fn(|row| {
+            self.decoupling[row]
+                .iter()
+                .zip(vector)
+                .fold(T::zero(), |sum, (&gain, &value)| gain.mul_add(value, sum))
+        })
+    }
+    // Decoupled errors reach the bank as setpoints offset from the
+    // measurements, which leaves its derivative on measurement unaffected.
+    fn decouple_errors(&self, setpoints: &[T; N], measurements: &[T; N]) -> [T; N] {
+        if self.point != DecouplingPoint::Error {
+            return *setpoints;
+        }
+        let errors: [T; N] = core::array::from_fn(|lane| setpoints[lane] - measurements[lane]);
+        let errors = self.multiply(&errors);
+        core::array::from_fn(|lane| measurements[lane] + errors[lane])
+    }
+    fn decouple_outputs(&mut self, outputs: &[T; N]) -> &[T; N] {
+        let outputs = match self.point {
+            DecouplingPoint::Error => *outputs,
+            DecouplingPoint::Output => self.multiply(outputs),
+        };
+        self.out = core::array::from_fn(|lane| self.output_limits[lane].clamp(outputs[lane]));
+        &self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::tuning::Gains;
+
+    const PLANT: [[f64; 2]; 2] = [[1.0, 0.5], [0.5, 1.0]];
+    const INVERSE: [[f64; 2]; 2] = [[4.0 / 3.0, -2.0 / 3.0], [-2.0 / 3.0, 4.0 / 3.0]];
+
+    fn mimo(
+        gains: &Gains<f64>,
+        decoupling: [[f64; 2]; 2],
+        point: DecouplingPoint,
+    ) -> MimoController<f64, 2> {
+        let mut bank = ControllerBank::new(Duration::from_millis(100));
+        for lane in 0..2 {
+            bank.set_lane(lane, gains, ..).unwrap();
+        }
+        MimoController::new(bank, decoupling, point)
+    }
+
+    fn plant(input: &[f64; 2]) -> [f64; 2] {
+        core::array::from_fn(|row| PLANT[row][0] * input[0] + PLANT[row][1] * input[1])
+    }
+
+    #[test]
+    fn both_points_invert_the_static_gain() {
+        let proportional = Gains::new(1.0, 0.0, 0.0);
+        for point in [DecouplingPoint::Error, DecouplingPoint::Output] {
+            let mut mimo = mimo(&proportional, INVERSE, point);
+            let output = plant(mimo.update(&[1.0, 0.0], &[0.0, 0.0]));
+            assert!((output[0] - 1.0).abs() < 1e-12);
+            assert!(output[1].abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn decoupled_setpoint_change_leaves_the_other_loop_alone() {
+        let integral = Gains::new(0.0, 5.0, 0.0);
+        let run = |decoupling| {
+            let mut mimo = mimo(&integral, decoupling, DecouplingPoint::Output);
+            let mut measurements = [0.0; 2];
+            let mut worst: f64 = 0.0;
+            for _ in 0..100 {
+                measurements = plant(mimo.update(&[1.0, 0.0], &measurements));
+                worst = worst.max(measurements[1].abs());
+            }
+            assert!((measurements[0] - 1.0).abs() < 1e-6);
+            worst
+        };
+        assert!(run(INVERSE) < 1e-12);
+        assert!(run(MimoController::identity()) > 0.1);
+    }
+
+    #[test]
+    fn actuator_limits_apply_after_decoupling() {
+        let mut mimo = mimo(&Gains::new(1.0, 0.0, 0.0), INVERSE, DecouplingPoint::Output);
+        assert_eq!(
+            mimo.set_output_limits(0, 1.0..=0.0),
+            Err(ConfigError::InvertedOutputLimit)
+        );
+        mimo.set_output_limits(1, -0.5..=0.5).unwrap();
+        assert_eq!(mimo.update(&[1.0, 0.0], &[0.0, 0.0])[1], -0.5);
+        mimo.init();
+        assert_eq!(mimo.output(), &[0.0; 2]);
+    }
+}
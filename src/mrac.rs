@@ -0,0 +1,237 @@
+use core::time::Duration;
+
+use crate::{
+    filter::{Filter, LowPass},
+    ConfigError, ControlLoop, Float, OutputLimits,
+};
+
+// Model-reference adaptive control with the MIT rule, for a plant with
+// positive, roughly first-order response. The control law u = theta1 r -
+// theta2 y adapts its two gains so the measurement follows a first-order
+// reference model with unit gain and the given time constant.
+//
+// The update is normalized by the size of the regressors so the adaptation
+// rate does not depend on signal levels, and further guarded: each gain
+// changes no faster than the rate limit and stays within the gain limits, and
+// adaptation pauses while the output is saturated, where the plant no longer
+// responds to the gains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mrac<T: Float> {
+    sample_time: Duration,
+    output_limit: OutputLimits<T>,
+    model: LowPass<T>,
+    setpoint_filter: LowPass<T>,
+    measurement_filter: LowPass<T>,
+    adaptation_rate: T,
+    max_gain_rate: Option<T>,
+    gain_limit: OutputLimits<T>,
+    initial_gains: [T; 2],
+    gains: [T; 2],
+    saturated: bool,
+    out: T,
+}
+impl<T: Float> Mrac<T> {
+    pub fn new(
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        model_time_constant: T,
+        adaptation_rate: T,
+        initial_gains: [T; 2],
+    ) -> Result<Self, ConfigError> {
+        let output_limit = output_limit.into();
+        if output_limit.is_inverted() {
+            return Err(ConfigError::InvertedOutputLimit);
+        }
+        if sample_time.is_zero() {
+            return Err(ConfigError::ZeroSampleTime);
+        }
+        if !(adaptation_rate.is_finite() && adaptation_rate >= T::zero()) {
+            return Err(ConfigError::NonFiniteGain);
+        }
+        let model = LowPass::try_new(model_time_constant)?;
+        Ok(Self {
+            sample_time,
+            output_limit,
+            model: model.clone(),
+            setpoint_filter: model.clone(),
+            measurement_filter: model,
+            adaptation_rate,
+            max_gain_rate: None,
+            gain_limit: OutputLimits::Unbounded,
+            initial_gains,
+            gains: initial_gains,
+            saturated: false,
+            out: T::zero(),
+        })
+    }
+    // The fastest either gain may change, per second.
+    pub fn set_max_gain_rate(&mut self, max_gain_rate: Option<T>) {
+        self.max_gain_rate = max_gain_rate;
+    }
+    pub fn set_gain_limits(
+        &mut self,
+        gain_limit: impl Into<OutputLimits<T>>,
+    ) -> Result<(), ConfigError> {
+        let gain_limit = gain_limit.into();
+        if gain_limit.is_inverted() {
+            return Err(ConfigError::InvertedGainLimit);
+        }
+        self.gain_limit = gain_limit;
+        self.gains = self.gains.map(|gain| gain_limit.clamp(gain));
+        Ok(())
+    }
+    pub fn set_adaptation_rate(&mut self, adaptation_rate: T) {
+        self.adaptation_rate = adaptation_rate;
+    }
+    // The feedforward and feedback gains, theta1 and theta2.
+    pub fn gains(&self) -> [T; 2] {
+        self.gains
+    }
+    pub fn model_output(&self) -> Option<T> {
+        self.model.value()
+    }
+    // Restarts the model and the gains from their initial values.
+    pub fn init(&mut self) {
+        self.model.init();
+        self.setpoint_filter.init();
+        self.measurement_filter.init();
+        self.gains = self.initial_gains.map(|gain| self.gain_limit.clamp(gain));
+        self.saturated = false;
+        self.out = T::zero();
+    }
+    pub fn output(&self) -> T {
+        self.out
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        let dt = T::from_duration(&self.sample_time);
+        let model = self.model.update(setpoint, dt);
+        let error = measurement - model;
+        // The sensitivities of the error to the gains, up to the unknown plant
+        // gain, are the regressors filtered by the model.
+        let setpoint_sensitivity = self.setpoint_filter.update(setpoint, dt);
+        let measurement_sensitivity = self.measurement_filter.update(measurement, dt);
+        if !self.saturated {
+            let norm = T::one()
+                + setpoint_sensitivity * setpoint_sensitivity
+                + measurement_sensitivity * measurement_sensitivity;
+            let step = self.adaptation_rate * error / norm * dt;
+            let changes = [
+                T::zero() - step * setpoint_sensitivity,
+                step * measurement_sensitivity,
+            ];
+            for (gain, change) in self.gains.iter_mut().zip(changes) {
+                let change = match self.max_gain_rate {
+                    Some(rate) => {
+                        OutputLimits::Inclusive(T::zero() - rate * dt, rate * dt).clamp(change)
+                    }
+                    None => change,
+                };
+                *gain = self.gain_limit.clamp(*gain + change);
+            }
+        }
+        let unclamped = self.gains[0] * setpoint - self.gains[1] * measurement;
+        self.out = self.output_limit.clamp(unclamped);
+        self.saturated = self.out != unclamped;
+        self.out
+    }
+}
+impl<T: Float> ControlLoop<T> for Mrac<T> {
+    fn init(&mut self) {
+        Mrac::init(self);
+    }
+    fn output(&self) -> T {
+        Mrac::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        Mrac::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // y' = (2 u - y) / 1 s; the reference model of 0.5 s is matched by gains
+    // of 1 and 0.5. Returns the worst model error in the first and last
+    // 20 s under a square-wave setpoint.
+    fn adapt(mrac: &mut Mrac<f64>, seconds: usize) -> (f64, f64) {
+        let (mut measurement, mut first, mut last) = (0.0, 0.0f64, 0.0f64);
+        let steps = seconds * 100;
+        for step in 0..steps {
+            let setpoint = if (step / 1000) % 2 == 0 { 1.0 } else { -1.0 };
+            let output = mrac.update(setpoint, measurement);
+            let error = (measurement - mrac.model_output().unwrap()).abs();
+            if step < 2000 {
+                first = first.max(error);
+            } else if step >= steps - 2000 {
+                last = last.max(error);
+            }
+            measurement += 0.01 * (2.0 * output - measurement);
+        }
+        (first, last)
+    }
+
+    fn mrac() -> Mrac<f64> {
+        Mrac::new(.., Duration::from_millis(10), 0.5, 5.0, [0.2, 0.0]).unwrap()
+    }
+
+    #[test]
+    fn gains_adapt_to_the_reference_model() {
+        let mut mrac = mrac();
+        let (first, last) = adapt(&mut mrac, 400);
+        assert!(last < 0.1 * first);
+        let [feedforward, feedback] = mrac.gains();
+        assert!((feedforward - 1.0).abs() < 0.05);
+        assert!((feedback - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn adaptation_is_guarded() {
+        let mut limited = mrac();
+        limited.set_max_gain_rate(Some(0.1));
+        adapt(&mut limited, 1);
+        assert!(limited.gains()[0] - 0.2 <= 0.1 + 1e-12);
+        assert!(limited.gains()[1].abs() <= 0.1 + 1e-12);
+
+        let mut clamped = mrac();
+        clamped.set_gain_limits(0.0..=0.6).unwrap();
+        adapt(&mut clamped, 100);
+        assert!(clamped
+            .gains()
+            .iter()
+            .all(|gain| (0.0..=0.6).contains(gain)));
+        clamped.init();
+        assert_eq!(clamped.gains(), [0.2, 0.0]);
+
+        // Saturated from the first update, so only that one adapts.
+        let mut saturated =
+            Mrac::new(-0.1..=0.1, Duration::from_millis(10), 0.5, 5.0, [1.0, 0.0]).unwrap();
+        saturated.update(1.0, 0.0);
+        let gains = saturated.gains();
+        for _ in 0..100 {
+            assert_eq!(saturated.update(1.0, 0.0), 0.1);
+        }
+        assert_eq!(saturated.gains(), gains);
+    }
+
+    #[test]
+    fn invalid_configuration_is_rejected() {
+        let sample_time = Duration::from_millis(10);
+        assert_eq!(
+            Mrac::new(1.0..=-1.0, sample_time, 0.5, 1.0, [0.0; 2]),
+            Err(ConfigError::InvertedOutputLimit)
+        );
+        assert_eq!(
+            Mrac::new(.., Duration::ZERO, 0.5, 1.0, [0.0; 2]),
+            Err(ConfigError::ZeroSampleTime)
+        );
+        assert_eq!(
+            Mrac::new(.., sample_time, 0.5, -1.0, [0.0; 2]),
+            Err(ConfigError::NonFiniteGain)
+        );
+        assert_eq!(
+            mrac().set_gain_limits(1.0..=0.0),
+            Err(ConfigError::InvertedGainLimit)
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use core::time::Duration;
+
+use crate::{ConfigError, ControlLoop, ControllerAction, Float, OutputLimits};
+
+// On/off control with hysteresis, as in a thermostat. A direct-acting
+// controller switches on once the measurement falls half the hysteresis
+// below the setpoint and off once it rises half above it; reverse action,
+// e.g. for cooling, swaps the two. In between the output holds its state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BangBang<T: Float> {
+    hysteresis: T,
+    on_output: T,
+    off_output: T,
+    action: ControllerAction,
+    on: bool,
+}
+impl<T: Float> BangBang<T> {
+    // Switches between one and zero.
+    pub fn new(hysteresis: T) -> Self {
+        Self {
+            hysteresis,
+            on_output: T::one(),
+            off_output: T::zero(),
+            action: ControllerAction::Direct,
+            on: false,
+        }
+    }
+    pub fn try_new(hysteresis: T) -> Result<Self, ConfigError> {
+        if !(hysteresis.is_finite() && hysteresis >= T::zero()) {
+            return Err(ConfigError::InvalidHysteresis);
+        }
+        Ok(Self::new(hysteresis))
+    }
+    pub fn set_outputs(&mut self, on_output: T, off_output: T) {
+        self.on_output = on_output;
+        self.off_output = off_output;
+    }
+    pub fn set_hysteresis(&mut self, hysteresis: T) {
+        self.hysteresis = hysteresis;
+    }
+    pub fn set_action(&mut self, action: ControllerAction) {
+        self.action = action;
+    }
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+    pub fn init(&mut self) {
+        self.on = false;
+    }
+    pub fn output(&self) -> T {
+        if self.on {
+            self.on_output
+        } else {
+            self.off_output
+        }
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        let error = match self.action {
+            ControllerAction::Direct => setpoint - measurement,
+            ControllerAction::Reverse => measurement - setpoint,
+        };
+        let half_band = T::half() * self.hysteresis;
+        if error > half_band {
+            self.on = true;
+        } else if error < T::zero() - half_band {
+            self.on = false;
+        }
+        self.output()
+    }
+}
+impl<T: Float> ControlLoop<T> for BangBang<T> {
+    fn init(&mut self) {
+        BangBang::init(self);
+    }
+    fn output(&self) -> T {
+        BangBang::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        BangBang::update(self, setpoint, measurement)
+    }
+}
+
+// Drives a relay or SSR from a continuous controller output: each window the
+// output is on for the fraction of the window that the controller output
+// takes up of its limits. The fraction is latched at the start of the
+// window. On and off times shorter than the minimums are not switched but
+// carried over into the next window, so the average still matches the
+// controller output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeProportioning<T: Float> {
+    lower: T,
+    upper: T,
+    window: T,
+    min_on: T,
+    min_off: T,
+    elapsed: T,
+    on_time: T,
+    carry: T,
+    started: bool,
+}
+impl<T: Float> TimeProportioning<T> {
+    // Takes the output limits of the controller that drives it, which must
+    // be bounded on both sides.
+    pub fn try_new(
+        output_limit: impl Into<OutputLimits<T>>,
+        window: Duration,
+    ) -> Result<Self, ConfigError> {
+        let OutputLimits::Inclusive(lower, upper) = output_limit.into() else {
+            return Err(ConfigError::InvalidTimeProportioning);
+        };
+        if upper <= lower || window.is_zero() {
+            return Err(ConfigError::InvalidTimeProportioning);
+        }
+        Ok(Self {
+            lower,
+            upper,
+            window: T::from_duration(&window),
+            min_on: T::zero(),
+            min_off: T::zero(),
+            elapsed: T::zero(),
+            on_time: T::zero(),
+            carry: T::zero(),
+            started: false,
+        })
+    }
+    pub fn set_min_times(
+        &mut self,
+        min_on: Duration,
+        min_off: Duration,
+    ) -> Result<(), ConfigError> {
+        let (min_on, min_off) = (T::from_duration(&min_on), T::from_duration(&min_off));
+        if min_on + min_off > self.window {
+            return Err(ConfigError::InvalidTimeProportioning);
+        }
+        self.min_on = min_on;
+        self.min_off = min_off;
+        Ok(())
+    }
+    pub fn init(&mut self) {
+        self.elapsed = T::zero();
+        self.on_time = T::zero();
+        self.carry = T::zero();
+        self.started = false;
+    }
+    // Whether the output is on for the coming `dt`.
+    pub fn update(&mut self, output: T, dt: &Duration) -> bool {
+        if !self.started || self.elapsed >= self.window {
+            self.start_window(output);
+        }
+        let on = self.elapsed < self.on_time;
+        self.elapsed = self.elapsed + T::from_duration(dt);
+        on
+    }
+    fn start_window(&mut self, output: T) {
+        let fraction = (output - self.lower) / (self.upper - self.lower);
+        let fraction = OutputLimits::Inclusive(T::zero(), T::one()).clamp(fraction);
+        let requested = fraction * self.window + self.carry;
+        self.on_time = if requested < self.min_on {
+            T::zero()
+        } else if self.window - requested < self.min_off {
+            self.window
+        } else {
+            requested
+        };
+        // The carry is bounded so a long saturated stretch is not paid back
+        // over many windows afterwards.
+        self.carry = OutputLimits::Inclusive(T::zero() - self.window, self.window)
+            .clamp(requested - self.on_time);
+        self.elapsed = if self.started && self.elapsed >= self.window {
+            self.elapsed - self.window
+        } else {
+            T::zero()
+        };
+        self.started = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hysteresis_holds_the_state_inside_the_band() {
+        let mut heater = BangBang::try_new(1.0).unwrap();
+        let states: [bool; 5] = [19.6, 19.4, 20.4, 20.6, 19.6].map(|measurement| {
+            heater.update(20.0, measurement);
+            heater.is_on()
+        });
+        assert_eq!(states, [false, true, true, false, false]);
+
+        let mut cooler = BangBang::new(1.0);
+        cooler.set_action(ControllerAction::Reverse);
+        cooler.set_outputs(-5.0, 0.0);
+        assert_eq!(cooler.update(20.0, 20.6), -5.0);
+        assert_eq!(cooler.update(20.0, 19.6), -5.0);
+        assert_eq!(cooler.update(20.0, 19.4), 0.0);
+    }
+
+    #[test]
+    fn hysteresis_must_be_finite_and_non_negative() {
+        for hysteresis in [-1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                BangBang::try_new(hysteresis),
+                Err(ConfigError::InvalidHysteresis)
+            );
+        }
+    }
+
+    // Counts the on steps of 125 ms over `windows` one-second windows.
+    fn on_steps(relay: &mut TimeProportioning<f64>, output: f64, windows: usize) -> usize {
+        let dt = Duration::from_millis(125);
+        (0..8 * windows)
+            .filter(|_| relay.update(output, &dt))
+            .count()
+    }
+
+    #[test]
+    fn duty_cycle_follows_the_output() {
+        let mut relay = TimeProportioning::try_new(0.0..=100.0, Duration::from_secs(1)).unwrap();
+        let dt = Duration::from_millis(125);
+        let pattern: [bool; 8] = core::array::from_fn(|_| relay.update(25.0, &dt));
+        assert_eq!(
+            pattern,
+            [true, true, false, false, false, false, false, false]
+        );
+        assert_eq!(on_steps(&mut relay, 150.0, 2), 16);
+        assert_eq!(on_steps(&mut relay, -10.0, 2), 0);
+    }
+
+    #[test]
+    fn short_pulses_are_carried_over() {
+        let mut relay = TimeProportioning::try_new(0.0..=100.0, Duration::from_secs(1)).unwrap();
+        relay
+            .set_min_times(Duration::from_millis(300), Duration::ZERO)
+            .unwrap();
+        // 125 ms per window is below the minimum, so it is switched as one
+        // 375 ms pulse every third window.
+        let dt = Duration::from_millis(125);
+        let pattern: [bool; 24] = core::array::from_fn(|_| relay.update(12.5, &dt));
+        assert_eq!(pattern.iter().filter(|&&on| on).count(), 3);
+        assert!(pattern[16..19].iter().all(|&on| on));
+        assert_eq!(on_steps(&mut relay, 12.5, 24), 24);
+    }
+
+    #[test]
+    fn invalid_configuration_is_rejected() {
+        let error = Err(ConfigError::InvalidTimeProportioning);
+        assert_eq!(
+            TimeProportioning::<f64>::try_new(0.0.., Duration::from_secs(1)),
+            error
+        );
+        assert_eq!(
+            TimeProportioning::try_new(0.0..=100.0, Duration::ZERO),
+            error
+        );
+        assert_eq!(
+            TimeProportioning::try_new(1.0..=1.0, Duration::from_secs(1)),
+            error
+        );
+        let mut relay =
+            TimeProportioning::<f64>::try_new(0.0..=1.0, Duration::from_secs(1)).unwrap();
+        assert_eq!(
+            relay.set_min_times(Duration::from_millis(600), Duration::from_millis(600)),
+            Err(ConfigError::InvalidTimeProportioning)
+        );
+    }
+}
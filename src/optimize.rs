@@ -0,0 +1,173 @@
+use crate::{
+    simulation::{Cost, Plant, Simulation},
+    tuning::Gains,
+    Float,
+};
+
+type Point<T> = [T; 3];
+
+fn to_point<T: Float>(gains: &Gains<T>) -> Point<T> {
+    [gains.kp, gains.ki, gains.kd]
+}
+
+// Negative gains are never useful here, so the search space is folded onto
+// the non-negative octant.
+fn to_gains<T: Float>(point: &Point<T>) -> Gains<T> {
+    let non_negative = |value: T| if value < T::zero() { T::zero() } else { value };
+    Gains::new(
+        non_negative(point[0]),
+        non_negative(point[1]),
+        non_negative(point[2]),
+    )
+}
+
+// A NaN cost, e.g. from a simulation that went unstable, compares false
+// against everything and would never be replaced; rank it as the worst.
+pub(crate) fn nan_to_worst<T: Float>(cost: T) -> T {
+    if cost.partial_cmp(&T::zero()).is_none() {
+        T::infinity()
+    } else {
+        cost
+    }
+}
+
+fn towards<T: Float>(from: &Point<T>, to: &Point<T>, factor: T) -> Point<T> {
+    [
+        from[0] + factor * (to[0] - from[0]),
+        from[1] + factor * (to[1] - from[1]),
+        from[2] + factor * (to[2] - from[2]),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NelderMead<T: Float> {
+    initial_step: T,
+    tolerance: T,
+    max_iterations: usize,
+}
+impl<T: Float> NelderMead<T> {
+    pub fn new(initial_step: T, tolerance: T, max_iterations: usize) -> Self {
+        Self {
+            initial_step,
+            tolerance,
+            max_iterations,
+        }
+    }
+    pub fn minimize<F: FnMut(&Gains<T>) -> T>(&self, initial: Gains<T>, mut cost: F) -> Gains<T> {
+        let mut cost = |gains: &Gains<T>| nan_to_worst(cost(gains));
+        let origin = to_point(&initial);
+        let mut simplex = [origin; 4];
+        for (axis, vertex) in simplex.iter_mut().skip(1).enumerate() {
+            vertex[axis] = vertex[axis] + self.initial_step;
+        }
+        let mut costs = simplex.map(|vertex| cost(&to_gains(&vertex)));
+
+        for _ in 0..self.max_iterations {
+            // Order vertices from best to worst.
+            for i in 1..simplex.len() {
+                let mut j = i;
+                while j > 0 && costs[j] < costs[j - 1] {
+                    simplex.swap(j, j - 1);
+                    costs.swap(j, j - 1);
+                    j -= 1;
+                }
+            }
+            if costs[3] - costs[0] < self.tolerance {
+                break;
+            }
+
+            let third = T::one() / T::from_f64(3.0);
+            let centroid = [0, 1, 2]
+                .map(|axis| (simplex[0][axis] + simplex[1][axis] + simplex[2][axis]) * third);
+
+            let reflected = towards(&centroid, &simplex[3], T::negative());
+            let reflected_cost = cost(&to_gains(&reflected));
+            if reflected_cost < costs[0] {
+                let expanded = towards(&centroid, &simplex[3], T::negative() * T::double());
+                let expanded_cost = cost(&to_gains(&expanded));
+                if expanded_cost < reflected_cost {
+                    simplex[3] = expanded;
+                    costs[3] = expanded_cost;
+                } else {
+                    simplex[3] = reflected;
+                    costs[3] = reflected_cost;
+                }
+                continue;
+            }
+            if reflected_cost < costs[2] {
+                simplex[3] = reflected;
+                costs[3] = reflected_cost;
+                continue;
+            }
+
+            let (contracted, threshold) = if reflected_cost < costs[3] {
+                (towards(&centroid, &reflected, T::half()), reflected_cost)
+            } else {
+                (towards(&centroid, &simplex[3], T::half()), costs[3])
+            };
+            let contracted_cost = cost(&to_gains(&contracted));
+            if contracted_cost < threshold {
+                simplex[3] = contracted;
+                costs[3] = contracted_cost;
+                continue;
+            }
+
+            let best = simplex[0];
+            for i in 1..simplex.len() {
+                simplex[i] = towards(&best, &simplex[i], T::half());
+                costs[i] = cost(&to_gains(&simplex[i]));
+            }
+        }
+
+        let mut best = 0;
+        for i in 1..costs.len() {
+            if costs[i] < costs[best] {
+                best = i;
+            }
+        }
+        to_gains(&simplex[best])
+    }
+}
+
+pub fn optimize<T: Float, P: Plant<T>>(
+    simulation: &Simulation<T>,
+    plant: &mut P,
+    initial: Gains<T>,
+    cost: &Cost<T>,
+    nelder_mead: &NelderMead<T>,
+) -> Gains<T> {
+    nelder_mead.minimize(initial, |gains| simulation.run(plant, gains, cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quadratic(gains: &Gains<f64>) -> f64 {
+        (gains.kp - 1.0).powi(2) + (gains.ki - 2.0).powi(2) + (gains.kd - 0.5).powi(2)
+    }
+
+    #[test]
+    fn finds_minimum_of_quadratic() {
+        let gains = NelderMead::new(0.5, 1e-12, 500).minimize(Gains::new(3.0, 3.0, 3.0), quadratic);
+        assert!((gains.kp - 1.0).abs() < 1e-3);
+        assert!((gains.ki - 2.0).abs() < 1e-3);
+        assert!((gains.kd - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn moves_away_from_nan_costs() {
+        // A pocket of unstable gains around the starting point.
+        let cost = |gains: &Gains<f64>| {
+            if gains.kp > 2.9 && gains.kp + gains.ki + gains.kd < 9.5 {
+                f64::NAN
+            } else {
+                quadratic(gains)
+            }
+        };
+        let gains = NelderMead::new(1.0, 1e-12, 500).minimize(Gains::new(3.0, 3.0, 3.0), cost);
+        assert!(!cost(&gains).is_nan());
+        assert!((gains.kp - 1.0).abs() < 1e-3);
+        assert!((gains.ki - 2.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{ConfigError, ControlOutput, Controller, Float, OutputLimits};
+
+// Ratio control: the setpoint of the controlled flow is a ratio of a second,
+// uncontrolled ("wild") flow plus a bias, recomputed from the wild flow on
+// every update so it moves in step with the controller. The ratio is kept
+// within its limits, and the controller's own setpoint limits still apply to
+// the resulting setpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatioController<T: Float> {
+    controller: Controller<T>,
+    ratio: T,
+    bias: T,
+    ratio_limit: OutputLimits<T>,
+}
+impl<T: Float> RatioController<T> {
+    pub fn new(controller: Controller<T>, ratio: T) -> Self {
+        Self {
+            controller,
+            ratio,
+            bias: T::zero(),
+            ratio_limit: OutputLimits::Unbounded,
+        }
+    }
+    pub fn controller(&self) -> &Controller<T> {
+        &self.controller
+    }
+    pub fn controller_mut(&mut self) -> &mut Controller<T> {
+        &mut self.controller
+    }
+    pub fn ratio(&self) -> T {
+        self.ratio
+    }
+    pub fn set_ratio(&mut self, ratio: T) {
+        self.ratio = self.ratio_limit.clamp(ratio);
+    }
+    pub fn set_ratio_limits(
+        &mut self,
+        ratio_limit: impl Into<OutputLimits<T>>,
+    ) -> Result<(), ConfigError> {
+        let ratio_limit = ratio_limit.into();
+        if ratio_limit.is_inverted() {
+            return Err(ConfigError::InvertedRatioLimit);
+        }
+        self.ratio_limit = ratio_limit;
+        self.ratio = self.ratio_limit.clamp(self.ratio);
+        Ok(())
+    }
+    pub fn bias(&self) -> T {
+        self.bias
+    }
+    pub fn set_bias(&mut self, bias: T) {
+        self.bias = bias;
+    }
+    pub fn setpoint(&self, wild_flow: T) -> T {
+        self.ratio * wild_flow + self.bias
+    }
+    // The ratio actually achieved, net of the bias; `None` while the wild
+    // flow is zero.
+    pub fn actual_ratio(&self, wild_flow: T, measurement: T) -> Option<T> {
+        if wild_flow == T::zero() {
+            return None;
+        }
+        Some((measurement - self.bias) / wild_flow)
+    }
+    pub fn init(&mut self) {
+        self.controller.init();
+    }
+    pub fn update(&mut self, wild_flow: T, measurement: T) -> T {
+        self.update_detailed(wild_flow, measurement).output
+    }
+    pub fn update_detailed(&mut self, wild_flow: T, measurement: T) -> ControlOutput<T> {
+        let setpoint = self.setpoint(wild_flow);
+        self.controller.update_detailed(setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::{Differentiator, Integrator, Proportional};
+
+    fn ratio_controller() -> RatioController<f64> {
+        let controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(100),
+            Proportional::new(1.0),
+            Integrator::new(0.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        RatioController::new(controller, 2.0)
+    }
+
+    #[test]
+    fn setpoint_follows_the_wild_flow() {
+        let mut ratio = ratio_controller();
+        ratio.set_bias(0.5);
+        let report = ratio.update_detailed(3.0, 4.0);
+        assert_eq!(report.setpoint, 6.5);
+        assert_eq!(report.output, 2.5);
+        assert_eq!(ratio.update(1.0, 2.0), 0.5);
+        assert_eq!(ratio.actual_ratio(3.0, 6.5), Some(2.0));
+        assert_eq!(ratio.actual_ratio(0.0, 6.5), None);
+    }
+
+    #[test]
+    fn ratio_and_setpoint_limits_apply() {
+        let mut ratio = ratio_controller();
+        assert_eq!(
+            ratio.set_ratio_limits(3.0..=1.0),
+            Err(ConfigError::InvertedRatioLimit)
+        );
+        ratio.set_ratio_limits(0.5..=1.5).unwrap();
+        assert_eq!(ratio.ratio(), 1.5);
+        ratio.set_ratio(0.1);
+        assert_eq!(ratio.ratio(), 0.5);
+
+        ratio.set_ratio(1.5);
+        ratio.controller_mut().set_setpoint_limits(..=4.0).unwrap();
+        let report = ratio.update_detailed(10.0, 0.0);
+        assert_eq!(report.raw_setpoint, 15.0);
+        assert_eq!(report.setpoint, 4.0);
+        assert!(report.setpoint_clamped);
+    }
+}
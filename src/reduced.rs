@@ -0,0 +1,318 @@
+use core::time::Duration;
+
+use crate::{
+    ConfigError, ControlLoop, Differentiator, Float, Integrator, OutputLimits, Proportional,
+    Timestep,
+};
+
+// Controllers with a fixed subset of the three terms, for loops that do not
+// need the rest of `Controller`: no filters, modes or timestamp handling, and
+// only the state their terms use.
+
+fn check_limits<T: Float>(output_limit: OutputLimits<T>) -> Result<(), ConfigError> {
+    if output_limit.is_inverted() {
+        return Err(ConfigError::InvertedOutputLimit);
+    }
+    Ok(())
+}
+
+// Proportional-only, with a bias (manual reset) to remove the steady-state
+// offset at the normal operating point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PController<T: Float> {
+    output_limit: OutputLimits<T>,
+    p: Proportional<T>,
+    bias: T,
+    out: T,
+}
+impl<T: Float> PController<T> {
+    pub fn new(output_limit: impl Into<OutputLimits<T>>, p: Proportional<T>) -> Self {
+        Self {
+            output_limit: output_limit.into(),
+            p,
+            bias: T::zero(),
+            out: T::zero(),
+        }
+    }
+    pub fn try_new(
+        output_limit: impl Into<OutputLimits<T>>,
+        p: Proportional<T>,
+    ) -> Result<Self, ConfigError> {
+        let output_limit = output_limit.into();
+        check_limits(output_limit)?;
+        Ok(Self::new(output_limit, p))
+    }
+    pub fn set_bias(&mut self, bias: T) {
+        self.bias = bias;
+    }
+    pub fn init(&mut self) {
+        self.out = T::zero();
+    }
+    pub fn output(&self) -> T {
+        self.out
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        self.out = self
+            .output_limit
+            .clamp(self.p.step(setpoint, measurement) + self.bias);
+        self.out
+    }
+}
+
+impl<T: Float> ControlLoop<T> for PController<T> {
+    fn init(&mut self) {
+        PController::init(self);
+    }
+    fn output(&self) -> T {
+        PController::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        PController::update(self, setpoint, measurement)
+    }
+}
+
+// Proportional-integral, with the integrator clamped to its own limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiController<T: Float> {
+    output_limit: OutputLimits<T>,
+    sample_time: Duration,
+    p: Proportional<T>,
+    i: Integrator<T>,
+    out: T,
+}
+impl<T: Float> PiController<T> {
+    pub fn new(
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        p: Proportional<T>,
+        i: Integrator<T>,
+    ) -> Self {
+        Self {
+            output_limit: output_limit.into(),
+            sample_time,
+            p,
+            i,
+            out: T::zero(),
+        }
+    }
+    pub fn try_new(
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        p: Proportional<T>,
+        i: Integrator<T>,
+    ) -> Result<Self, ConfigError> {
+        let output_limit = output_limit.into();
+        check_limits(output_limit)?;
+        if sample_time.is_zero() {
+            return Err(ConfigError::ZeroSampleTime);
+        }
+        Ok(Self::new(output_limit, sample_time, p, i))
+    }
+    pub fn init(&mut self) {
+        self.i.init();
+        self.out = T::zero();
+    }
+    pub fn output(&self) -> T {
+        self.out
+    }
+    pub fn integral(&self) -> T {
+        self.i.value
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        let dt = T::from_duration(&self.sample_time);
+        self.step(setpoint, measurement, dt)
+    }
+    pub fn update_with_dt<D: Timestep<T>>(&mut self, setpoint: T, measurement: T, dt: D) -> T {
+        self.step(setpoint, measurement, dt.seconds())
+    }
+    fn step(&mut self, setpoint: T, measurement: T, dt: T) -> T {
+        let p = self.p.step(setpoint, measurement);
+        let i = self.i.step(setpoint, measurement, dt);
+        self.out = self.output_limit.clamp(p + i);
+        self.out
+    }
+}
+
+impl<T: Float> ControlLoop<T> for PiController<T> {
+    fn init(&mut self) {
+        PiController::init(self);
+    }
+    fn output(&self) -> T {
+        PiController::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        PiController::update(self, setpoint, measurement)
+    }
+}
+
+// Proportional-derivative, with the derivative's own mode and filter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdController<T: Float> {
+    output_limit: OutputLimits<T>,
+    sample_time: Duration,
+    p: Proportional<T>,
+    d: Differentiator<T>,
+    bias: T,
+    out: T,
+}
+impl<T: Float> PdController<T> {
+    pub fn new(
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        p: Proportional<T>,
+        d: Differentiator<T>,
+    ) -> Self {
+        Self {
+            output_limit: output_limit.into(),
+            sample_time,
+            p,
+            d,
+            bias: T::zero(),
+            out: T::zero(),
+        }
+    }
+    pub fn try_new(
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        p: Proportional<T>,
+        d: Differentiator<T>,
+    ) -> Result<Self, ConfigError> {
+        let output_limit = output_limit.into();
+        check_limits(output_limit)?;
+        if sample_time.is_zero() {
+            return Err(ConfigError::ZeroSampleTime);
+        }
+        Ok(Self::new(output_limit, sample_time, p, d))
+    }
+    pub fn set_bias(&mut self, bias: T) {
+        self.bias = bias;
+    }
+    pub fn init(&mut self) {
+        self.d.init();
+        self.out = T::zero();
+    }
+    pub fn output(&self) -> T {
+        self.out
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        let dt = T::from_duration(&self.sample_time);
+        self.step(setpoint, measurement, dt)
+    }
+    pub fn update_with_dt<D: Timestep<T>>(&mut self, setpoint: T, measurement: T, dt: D) -> T {
+        self.step(setpoint, measurement, dt.seconds())
+    }
+    fn step(&mut self, setpoint: T, measurement: T, dt: T) -> T {
+        let p = self.p.step(setpoint, measurement);
+        let d = self.d.step(setpoint, measurement, dt);
+        self.out = self.output_limit.clamp(p + d + self.bias);
+        self.out
+    }
+}
+impl<T: Float> ControlLoop<T> for PdController<T> {
+    fn init(&mut self) {
+        PdController::init(self);
+    }
+    fn output(&self) -> T {
+        PdController::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        PdController::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Controller;
+
+    fn full(kp: f64, ki: f64, d: Differentiator<f64>) -> Controller<f64> {
+        Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(kp),
+            Integrator::new(ki, OutputLimits::Unbounded),
+            d,
+        )
+    }
+
+    fn measurement(step: usize) -> f64 {
+        (step as f64 * 0.1).sin()
+    }
+
+    #[test]
+    fn reduced_controllers_match_the_full_controller() {
+        let sample_time = Duration::from_millis(10);
+        let mut p = PController::new(.., Proportional::new(2.0));
+        let mut pi = PiController::new(
+            ..,
+            sample_time,
+            Proportional::new(2.0),
+            Integrator::new(3.0, OutputLimits::Unbounded),
+        );
+        let mut pd = PdController::new(
+            ..,
+            sample_time,
+            Proportional::new(2.0),
+            Differentiator::new(0.5, 0.02),
+        );
+        let mut full_p = full(2.0, 0.0, Differentiator::new(0.0, 0.0));
+        let mut full_pi = full(2.0, 3.0, Differentiator::new(0.0, 0.0));
+        let mut full_pd = full(2.0, 0.0, Differentiator::new(0.5, 0.02));
+        for step in 0..50 {
+            let measurement = measurement(step);
+            assert_eq!(p.update(1.0, measurement), full_p.update(1.0, measurement));
+            assert!((pi.update(1.0, measurement) - full_pi.update(1.0, measurement)).abs() < 1e-12);
+            assert!((pd.update(1.0, measurement) - full_pd.update(1.0, measurement)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn bias_and_limits_apply() {
+        let mut p = PController::try_new(-1.0..=1.0, Proportional::new(2.0)).unwrap();
+        p.set_bias(0.5);
+        assert_eq!(p.update(0.1, 0.0), 0.7);
+        assert_eq!(p.update(1.0, 0.0), 1.0);
+        p.init();
+        assert_eq!(p.output(), 0.0);
+
+        let mut pi = PiController::new(
+            ..,
+            Duration::from_millis(100),
+            Proportional::new(0.0),
+            Integrator::new(1.0, -0.5..=0.5),
+        );
+        for _ in 0..100 {
+            pi.update(1.0, 0.0);
+        }
+        assert_eq!(pi.integral(), 0.5);
+        pi.init();
+        assert_eq!(pi.integral(), 0.0);
+    }
+
+    #[test]
+    fn invalid_configuration_is_rejected() {
+        let sample_time = Duration::from_millis(10);
+        assert_eq!(
+            PController::try_new(1.0..=-1.0, Proportional::new(1.0)),
+            Err(ConfigError::InvertedOutputLimit)
+        );
+        assert_eq!(
+            PiController::try_new(
+                ..,
+                Duration::ZERO,
+                Proportional::new(1.0),
+                Integrator::new(1.0, OutputLimits::Unbounded)
+            ),
+            Err(ConfigError::ZeroSampleTime)
+        );
+        assert_eq!(
+            PdController::try_new(
+                1.0..=-1.0,
+                sample_time,
+                Proportional::new(1.0),
+                Differentiator::new(1.0, 0.0)
+            ),
+            Err(ConfigError::InvertedOutputLimit)
+        );
+    }
+}
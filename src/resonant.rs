@@ -0,0 +1,109 @@
+use core::time::Duration;
+
+use crate::{
+    filter::{Biquad, Filter},
+    ConfigError, ControllerComponent, Float, Timestep,
+};
+
+// Resonant control for periodic disturbances such as cogging torque or mains
+// ripple: one band-pass section on the error per harmonic of the disturbance
+// frequency, each with a high gain at its frequency and little effect
+// elsewhere, which drives the error at those frequencies towards zero the way
+// an integrator does at DC. Higher Q gives a higher, narrower peak; harmonics
+// at or above the Nyquist frequency are rejected, as are more than
+// `MAX_HARMONICS` harmonics.
+//
+// The sections are designed for a fixed sample time and ignore the dt passed
+// to `update`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resonant<T: Float> {
+    sections: [Biquad<T>; MAX_HARMONICS],
+    harmonics: usize,
+}
+
+pub const MAX_HARMONICS: usize = 4;
+
+impl<T: Float> Resonant<T> {
+    pub fn new(
+        gain: f64,
+        frequency: f64,
+        q: f64,
+        harmonics: usize,
+        sample_time: Duration,
+    ) -> Result<Self, ConfigError> {
+        if harmonics > MAX_HARMONICS {
+            return Err(ConfigError::TooManyHarmonics);
+        }
+        let mut sections = core::array::from_fn(|_| Biquad::new([T::zero(); 3], [T::zero(); 2]));
+        for (index, section) in sections[..harmonics].iter_mut().enumerate() {
+            let harmonic = (index + 1) as f64;
+            *section = Biquad::resonant(gain, harmonic * frequency, q, sample_time)?;
+        }
+        Ok(Self {
+            sections,
+            harmonics,
+        })
+    }
+    pub fn init(&mut self) {
+        for section in &mut self.sections {
+            Filter::init(section);
+        }
+    }
+    pub(crate) fn step(&mut self, error: T) -> T {
+        let sections = &mut self.sections[..self.harmonics];
+        sections.iter_mut().fold(T::zero(), |sum, section| {
+            sum + section.update(error, T::zero())
+        })
+    }
+}
+impl<T: Float, D: Timestep<T>> ControllerComponent<T, D> for Resonant<T> {
+    fn init(&mut self) {
+        Resonant::init(self);
+    }
+    fn update(&mut self, setpoint: T, measurement: T, _: &D) -> T {
+        self.step(setpoint - measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Amplitude of the term's response to a unit sine error at `frequency`,
+    // once the transient has died out.
+    fn gain_at(resonant: &mut Resonant<f64>, frequency: f64, sample_time: f64) -> f64 {
+        let mut peak: f64 = 0.0;
+        for k in 0..20_000 {
+            let error = (core::f64::consts::TAU * frequency * k as f64 * sample_time).sin();
+            let output = resonant.step(error);
+            if k >= 15_000 {
+                peak = peak.max(output.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn peaks_at_each_harmonic() {
+        let sample_time = Duration::from_millis(1);
+        let mut resonant = Resonant::<f64>::new(1.0, 50.0, 5.0, 2, sample_time).unwrap();
+        assert!((gain_at(&mut resonant, 50.0, 0.001) - 1.0).abs() < 0.05);
+        resonant.init();
+        assert!((gain_at(&mut resonant, 100.0, 0.001) - 1.0).abs() < 0.05);
+        resonant.init();
+        assert!(gain_at(&mut resonant, 200.0, 0.001) < 0.3);
+    }
+
+    #[test]
+    fn rejects_unsupported_harmonics() {
+        let sample_time = Duration::from_millis(1);
+        assert_eq!(
+            Resonant::<f64>::new(1.0, 50.0, 5.0, MAX_HARMONICS + 1, sample_time),
+            Err(ConfigError::TooManyHarmonics)
+        );
+        assert_eq!(
+            Resonant::<f64>::new(1.0, 300.0, 5.0, 2, sample_time),
+            Err(ConfigError::InvalidFilterFrequency)
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use alloc::vec::Vec;
+
+use crate::{tuning::Gains, ConfigError, ControlLoop, ControlOutput, Controller, Float};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScheduleRow<T: Float> {
+    pub breakpoint: T,
+    pub gains: Gains<T>,
+}
+impl<T: Float> ScheduleRow<T> {
+    pub fn new(breakpoint: T, kp: T, ki: T, kd: T) -> Self {
+        Self {
+            breakpoint,
+            gains: Gains::new(kp, ki, kd),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleKey {
+    Measurement,
+    Setpoint,
+    // A separate input, such as airspeed for flight control, set with
+    // `set_scheduling_variable` whenever it is sampled.
+    External,
+}
+
+// Gains as a piecewise-linear function of an operating-point variable, held
+// constant beyond the first and last breakpoints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GainSchedule<T: Float> {
+    rows: Vec<ScheduleRow<T>>,
+}
+impl<T: Float> GainSchedule<T> {
+    // Breakpoints must be finite and strictly increasing.
+    pub fn try_new(rows: Vec<ScheduleRow<T>>) -> Result<Self, ConfigError> {
+        let increasing = rows
+            .windows(2)
+            .all(|pair| pair[0].breakpoint < pair[1].breakpoint);
+        let finite = rows.iter().all(|row| row.breakpoint.is_finite());
+        if rows.is_empty() || !increasing || !finite {
+            return Err(ConfigError::InvalidSchedule);
+        }
+        Ok(Self { rows })
+    }
+    pub fn rows(&self) -> &[ScheduleRow<T>] {
+        &self.rows
+    }
+    pub fn gains(&self, at: T) -> Gains<T> {
+        let first = self.rows[0];
+        if at <= first.breakpoint {
+            return first.gains;
+        }
+        for pair in self.rows.windows(2) {
+            let (low, high) = (pair[0], pair[1]);
+            if at <= high.breakpoint {
+                let weight = (at - low.breakpoint) / (high.breakpoint - low.breakpoint);
+                let blend = |low: T, high: T| low + weight * (high - low);
+                return Gains::new(
+                    blend(low.gains.kp, high.gains.kp),
+                    blend(low.gains.ki, high.gains.ki),
+                    blend(low.gains.kd, high.gains.kd),
+                );
+            }
+        }
+        self.rows[self.rows.len() - 1].gains
+    }
+}
+
+// Applies the scheduled gains before every update. Gain changes go through
+// the controller's bumpless setters, so moving along the schedule does not
+// kick the output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledController<T: Float> {
+    controller: Controller<T>,
+    schedule: GainSchedule<T>,
+    key: ScheduleKey,
+    variable: T,
+}
+impl<T: Float> ScheduledController<T> {
+    pub fn new(controller: Controller<T>, schedule: GainSchedule<T>, key: ScheduleKey) -> Self {
+        Self {
+            controller,
+            schedule,
+            key,
+            variable: T::zero(),
+        }
+    }
+    pub fn controller(&self) -> &Controller<T> {
+        &self.controller
+    }
+    pub fn controller_mut(&mut self) -> &mut Controller<T> {
+        &mut self.controller
+    }
+    pub fn schedule(&self) -> &GainSchedule<T> {
+        &self.schedule
+    }
+    pub fn set_schedule(&mut self, schedule: GainSchedule<T>) {
+        self.schedule = schedule;
+    }
+    pub fn set_key(&mut self, key: ScheduleKey) {
+        self.key = key;
+    }
+    pub fn scheduling_variable(&self) -> T {
+        self.variable
+    }
+    // Holds until the next call, so the variable can be sampled at a
+    // different rate than the loop runs.
+    pub fn set_scheduling_variable(&mut self, variable: T) {
+        self.variable = variable;
+    }
+    pub fn init(&mut self) {
+        self.controller.init();
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        self.update_detailed(setpoint, measurement).output
+    }
+    // Schedules on `variable` for this update whatever the key, and keeps it
+    // for later updates with `ScheduleKey::External`.
+    pub fn update_with_scheduling_variable(
+        &mut self,
+        setpoint: T,
+        measurement: T,
+        variable: T,
+    ) -> T {
+        self.variable = variable;
+        self.step(setpoint, measurement, variable).output
+    }
+    pub fn update_detailed(&mut self, setpoint: T, measurement: T) -> ControlOutput<T> {
+        let at = match self.key {
+            ScheduleKey::Measurement => measurement,
+            ScheduleKey::Setpoint => setpoint,
+            ScheduleKey::External => self.variable,
+        };
+        self.step(setpoint, measurement, at)
+    }
+    fn step(&mut self, setpoint: T, measurement: T, at: T) -> ControlOutput<T> {
+        self.controller.set_gains(&self.schedule.gains(at));
+        self.controller.update_detailed(setpoint, measurement)
+    }
+}
+impl<T: Float> ControlLoop<T> for ScheduledController<T> {
+    fn init(&mut self) {
+        ScheduledController::init(self);
+    }
+    fn output(&self) -> T {
+        self.controller.output()
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        ScheduledController::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use core::time::Duration;
+
+    use super::*;
+    use crate::{Differentiator, Integrator, OutputLimits, Proportional};
+
+    fn schedule(ki: f64) -> GainSchedule<f64> {
+        GainSchedule::try_new(vec![
+            ScheduleRow::new(0.0, 1.0, ki, 0.0),
+            ScheduleRow::new(10.0, 3.0, ki, 0.0),
+            ScheduleRow::new(20.0, 3.0, ki, 1.0),
+        ])
+        .unwrap()
+    }
+
+    fn scheduled(ki: f64, key: ScheduleKey) -> ScheduledController<f64> {
+        let controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(100),
+            Proportional::new(0.0),
+            Integrator::new(0.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        ScheduledController::new(controller, schedule(ki), key)
+    }
+
+    #[test]
+    fn gains_are_interpolated_and_held_at_the_ends() {
+        let schedule = schedule(0.0);
+        assert_eq!(schedule.gains(-5.0), Gains::new(1.0, 0.0, 0.0));
+        assert_eq!(schedule.gains(5.0), Gains::new(2.0, 0.0, 0.0));
+        assert_eq!(schedule.gains(15.0), Gains::new(3.0, 0.0, 0.5));
+        assert_eq!(schedule.gains(25.0), Gains::new(3.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn breakpoints_must_be_finite_and_increasing() {
+        let row = |breakpoint| ScheduleRow::new(breakpoint, 1.0, 0.0, 0.0);
+        for rows in [
+            vec![],
+            vec![row(1.0), row(1.0)],
+            vec![row(2.0), row(1.0)],
+            vec![row(0.0), row(f64::INFINITY)],
+            vec![row(f64::NAN)],
+        ] {
+            assert_eq!(
+                GainSchedule::try_new(rows),
+                Err(ConfigError::InvalidSchedule)
+            );
+        }
+    }
+
+    #[test]
+    fn key_selects_the_scheduling_variable() {
+        let update = |key| scheduled(0.0, key).update(10.0, 5.0);
+        assert_eq!(update(ScheduleKey::Measurement), 10.0);
+        assert_eq!(update(ScheduleKey::Setpoint), 15.0);
+        assert_eq!(update(ScheduleKey::External), 5.0);
+
+        let mut scheduled = scheduled(0.0, ScheduleKey::Measurement);
+        assert_eq!(
+            scheduled.update_with_scheduling_variable(10.0, 5.0, 10.0),
+            15.0
+        );
+        assert_eq!(scheduled.scheduling_variable(), 10.0);
+    }
+
+    #[test]
+    fn moving_along_the_schedule_is_bumpless() {
+        let mut scheduled = scheduled(1.0, ScheduleKey::External);
+        let first = scheduled.update(1.0, 0.0);
+        let second = scheduled.update(1.0, 0.0);
+        scheduled.set_scheduling_variable(10.0);
+        let third = scheduled.update(1.0, 0.0);
+        // Tripling kp moves the output by the integral step alone.
+        assert!((third - second - (second - first)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{ControlOutput, Controller, Float};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelectorOutput<T: Float, const N: usize> {
+    pub selected: usize,
+    pub output: T,
+    pub controllers: [ControlOutput<T>; N],
+}
+
+// Override control: N controllers act on one actuator and the lowest or
+// highest output wins, as when a pressure controller overrides a flow loop
+// near its constraint. The controllers that lose track the selected output,
+// so none of them winds up while it is not in charge and each can take over
+// without a bump. Ties go to the lowest index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector<T: Float, const N: usize> {
+    controllers: [Controller<T>; N],
+    selection: Selection,
+    selected: usize,
+}
+impl<T: Float, const N: usize> Selector<T, N> {
+    pub fn new(controllers: [Controller<T>; N], selection: Selection) -> Self {
+        const { assert!(N > 0, "a selector needs at least one controller") };
+        Self {
+            controllers,
+            selection,
+            selected: 0,
+        }
+    }
+    pub fn controllers(&self) -> &[Controller<T>; N] {
+        &self.controllers
+    }
+    pub fn controllers_mut(&mut self) -> &mut [Controller<T>; N] {
+        &mut self.controllers
+    }
+    pub fn set_selection(&mut self, selection: Selection) {
+        self.selection = selection;
+    }
+    // The controller selected on the last update.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+    pub fn output(&self) -> T {
+        self.controllers[self.selected].output()
+    }
+    pub fn init(&mut self) {
+        for controller in &mut self.controllers {
+            controller.init();
+        }
+        self.selected = 0;
+    }
+    pub fn update(&mut self, setpoints: &[T; N], measurements: &[T; N]) -> T {
+        self.update_detailed(setpoints, measurements).output
+    }
+    pub fn update_detailed(
+        &mut self,
+        setpoints: &[T; N],
+        measurements: &[T; N],
+    ) -> SelectorOutput<T, N> {
+        let controllers: [ControlOutput<T>; N] = core::array::from_fn(|index| {
+            self.controllers[index].update_detailed(setpoints[index], measurements[index])
+        });
+        let mut selected = 0;
+        for (index, candidate) in controllers.iter().enumerate().skip(1) {
+            let wins = match self.selection {
+                Selection::Min => candidate.output < controllers[selected].output,
+                Selection::Max => candidate.output > controllers[selected].output,
+            };
+            if wins {
+                selected = index;
+            }
+        }
+        let output = controllers[selected].output;
+        for (index, controller) in self.controllers.iter_mut().enumerate() {
+            if index != selected {
+                controller.track(output, &controllers[index]);
+            }
+        }
+        self.selected = selected;
+        SelectorOutput {
+            selected,
+            output,
+            controllers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::{Differentiator, Integrator, OutputLimits, Proportional};
+
+    fn pi_controller(kp: f64) -> Controller<f64> {
+        Controller::new(
+            OutputLimits::Inclusive(0.0, 100.0),
+            Duration::from_millis(100),
+            Proportional::new(kp),
+            Integrator::new(1.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn lowest_output_wins_and_the_others_track_it() {
+        let mut selector = Selector::new([pi_controller(1.0), pi_controller(2.0)], Selection::Min);
+        let report = selector.update_detailed(&[10.0, 10.0], &[5.0, 8.0]);
+        assert_eq!(report.selected, 1);
+        assert_eq!(selector.controllers()[0].output(), report.output);
+
+        // The tracking controller takes over without a bump.
+        let report = selector.update_detailed(&[10.0, 10.0], &[5.0, 0.0]);
+        assert_eq!(report.selected, 0);
+        assert!(report.output > 4.0 && report.output < 6.0);
+    }
+
+    #[test]
+    fn ties_go_to_the_lowest_index() {
+        let mut selector = Selector::new([pi_controller(1.0), pi_controller(1.0)], Selection::Max);
+        assert_eq!(
+            selector.update_detailed(&[1.0, 1.0], &[0.0, 0.0]).selected,
+            0
+        );
+    }
+}
@@ -0,0 +1,259 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{ConfigError, Float};
+
+pub trait SetpointSource<T: Float> {
+    fn next_setpoint(&mut self, sample_time: &Duration) -> T;
+}
+impl<T: Float> SetpointSource<T> for T {
+    fn next_setpoint(&mut self, _: &Duration) -> T {
+        *self
+    }
+}
+
+// Walks the setpoint toward a target at no more than `rate` units per second.
+// Until it is given a starting point with `reset_to` the ramp starts at the
+// first target, so it is usually reset to the measurement when the loop is
+// switched on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetpointRamp<T: Float> {
+    rate: T,
+    target: T,
+    current: Option<T>,
+}
+impl<T: Float> SetpointRamp<T> {
+    pub const fn new(rate: T, target: T) -> Self {
+        Self {
+            rate,
+            target,
+            current: None,
+        }
+    }
+    pub fn try_new(rate: T, target: T) -> Result<Self, ConfigError> {
+        if rate <= T::zero() || !rate.is_finite() {
+            return Err(ConfigError::InvalidRampRate);
+        }
+        Ok(Self::new(rate, target))
+    }
+    pub fn set_rate(&mut self, rate: T) {
+        self.rate = rate;
+    }
+    pub fn set_target(&mut self, target: T) {
+        self.target = target;
+    }
+    pub fn target(&self) -> T {
+        self.target
+    }
+    pub fn current(&self) -> Option<T> {
+        self.current
+    }
+    pub fn reset_to(&mut self, setpoint: T) {
+        self.current = Some(setpoint);
+    }
+    pub fn is_settled(&self) -> bool {
+        self.current == Some(self.target)
+    }
+}
+impl<T: Float> SetpointSource<T> for SetpointRamp<T> {
+    fn next_setpoint(&mut self, sample_time: &Duration) -> T {
+        let current = self.current.unwrap_or(self.target);
+        let step = self.rate * T::from_duration(sample_time);
+        let next = if self.target > current + step {
+            current + step
+        } else if self.target < current - step {
+            current - step
+        } else {
+            self.target
+        };
+        self.current = Some(next);
+        next
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfileSegment<T: Float> {
+    pub target: T,
+    pub ramp_time: Duration,
+    pub hold_time: Duration,
+}
+impl<T: Float> ProfileSegment<T> {
+    pub fn new(target: T, ramp_time: Duration, hold_time: Duration) -> Self {
+        Self {
+            target,
+            ramp_time,
+            hold_time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackState {
+    Idle,
+    Running,
+    Paused,
+    Finished,
+    Aborted,
+}
+
+// A schedule of segments, each ramping linearly from the previous target (or
+// the starting setpoint) to its own over `ramp_time` and then holding it for
+// `hold_time`. Time only advances while running; when paused or aborted the
+// setpoint stays where it was, and once finished it holds the last target.
+// Before it is started the profile holds the setpoint it was created with.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile<T: Float> {
+    segments: Vec<ProfileSegment<T>>,
+    state: PlaybackState,
+    index: usize,
+    elapsed: Duration,
+    from: T,
+    setpoint: T,
+}
+#[cfg(feature = "alloc")]
+impl<T: Float> Profile<T> {
+    pub fn new(segments: Vec<ProfileSegment<T>>, setpoint: T) -> Self {
+        Self {
+            segments,
+            state: PlaybackState::Idle,
+            index: 0,
+            elapsed: Duration::ZERO,
+            from: setpoint,
+            setpoint,
+        }
+    }
+    pub fn segments(&self) -> &[ProfileSegment<T>] {
+        &self.segments
+    }
+    pub fn state(&self) -> PlaybackState {
+        self.state
+    }
+    // Index of the segment being played.
+    pub fn segment(&self) -> usize {
+        self.index
+    }
+    pub fn setpoint(&self) -> T {
+        self.setpoint
+    }
+    // Starts from the beginning, ramping from `setpoint`, usually the current
+    // measurement.
+    pub fn start(&mut self, setpoint: T) {
+        self.index = 0;
+        self.elapsed = Duration::ZERO;
+        self.from = setpoint;
+        self.setpoint = setpoint;
+        self.state = if self.segments.is_empty() {
+            PlaybackState::Finished
+        } else {
+            PlaybackState::Running
+        };
+    }
+    pub fn pause(&mut self) {
+        if self.state == PlaybackState::Running {
+            self.state = PlaybackState::Paused;
+        }
+    }
+    pub fn resume(&mut self) {
+        if self.state == PlaybackState::Paused {
+            self.state = PlaybackState::Running;
+        }
+    }
+    pub fn abort(&mut self) {
+        if matches!(self.state, PlaybackState::Running | PlaybackState::Paused) {
+            self.state = PlaybackState::Aborted;
+        }
+    }
+    fn advance(&mut self, sample_time: &Duration) {
+        self.elapsed += *sample_time;
+        while let Some(segment) = self.segments.get(self.index) {
+            let length = segment.ramp_time + segment.hold_time;
+            if self.elapsed < length {
+                self.setpoint = if self.elapsed < segment.ramp_time {
+                    let fraction =
+                        T::from_duration(&self.elapsed) / T::from_duration(&segment.ramp_time);
+                    self.from + (segment.target - self.from) * fraction
+                } else {
+                    segment.target
+                };
+                return;
+            }
+            self.elapsed -= length;
+            self.from = segment.target;
+            self.setpoint = segment.target;
+            self.index += 1;
+        }
+        self.state = PlaybackState::Finished;
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T: Float> SetpointSource<T> for Profile<T> {
+    fn next_setpoint(&mut self, sample_time: &Duration) -> T {
+        if self.state == PlaybackState::Running {
+            self.advance(sample_time);
+        }
+        self.setpoint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn ramp_limits_the_rate() {
+        let mut ramp = SetpointRamp::new(2.0, 1.0);
+        ramp.reset_to(0.0);
+        let steps: [f64; 6] = core::array::from_fn(|_| ramp.next_setpoint(&DT));
+        for (step, expected) in steps.iter().zip([0.2, 0.4, 0.6, 0.8, 1.0, 1.0]) {
+            assert!((step - expected).abs() < 1e-12);
+        }
+        assert!(ramp.is_settled());
+    }
+
+    #[test]
+    fn ramp_rate_must_be_positive_and_finite() {
+        for rate in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            assert_eq!(
+                SetpointRamp::try_new(rate, 1.0),
+                Err(ConfigError::InvalidRampRate)
+            );
+        }
+        assert_eq!(
+            SetpointRamp::try_new(2.0, 1.0),
+            Ok(SetpointRamp::new(2.0, 1.0))
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn idle_profile_holds_its_initial_setpoint() {
+        let segments = alloc::vec![ProfileSegment::new(10.0, DT * 2, DT)];
+        let mut profile = Profile::new(segments, 4.0);
+        assert_eq!(profile.next_setpoint(&DT), 4.0);
+        assert_eq!(profile.state(), PlaybackState::Idle);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn profile_ramps_holds_and_finishes() {
+        let segments = alloc::vec![
+            ProfileSegment::new(10.0, DT * 2, DT),
+            ProfileSegment::new(0.0, Duration::ZERO, DT),
+        ];
+        let mut profile = Profile::new(segments, 0.0);
+        profile.start(2.0);
+        assert_eq!(profile.next_setpoint(&DT), 6.0);
+        profile.pause();
+        assert_eq!(profile.next_setpoint(&DT), 6.0);
+        profile.resume();
+        assert_eq!(profile.next_setpoint(&DT), 10.0);
+        assert_eq!(profile.next_setpoint(&DT), 0.0);
+        assert_eq!(profile.segment(), 1);
+        assert_eq!(profile.next_setpoint(&DT), 0.0);
+        assert_eq!(profile.state(), PlaybackState::Finished);
+    }
+}
@@ -0,0 +1,162 @@
+use core::time::Duration;
+
+use crate::{tuning::Gains, Float, OutputLimits};
+
+pub trait Plant<T: Float> {
+    fn init(&mut self);
+    fn update(&mut self, input: T, sample_time: &Duration) -> T;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    Iae,
+    Ise,
+    Itae,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cost<T: Float> {
+    criterion: Criterion,
+    effort_weight: T,
+    max_overshoot: Option<T>,
+    overshoot_penalty: T,
+}
+impl<T: Float> Cost<T> {
+    pub fn new(criterion: Criterion, effort_weight: T) -> Self {
+        Self {
+            criterion,
+            effort_weight,
+            max_overshoot: None,
+            overshoot_penalty: T::zero(),
+        }
+    }
+    pub fn set_max_overshoot(&mut self, max_overshoot: Option<T>, penalty: T) {
+        self.max_overshoot = max_overshoot;
+        self.overshoot_penalty = penalty;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation<T: Float> {
+    setpoint: T,
+    sample_time: Duration,
+    steps: usize,
+    output_limit: OutputLimits<T>,
+    derivative_time_constant: T,
+}
+impl<T: Float> Simulation<T> {
+    pub fn new(
+        setpoint: T,
+        sample_time: Duration,
+        steps: usize,
+        output_limit: impl Into<OutputLimits<T>>,
+        derivative_time_constant: T,
+    ) -> Self {
+        Self {
+            setpoint,
+            sample_time,
+            steps,
+            output_limit: output_limit.into(),
+            derivative_time_constant,
+        }
+    }
+    // Runs a closed-loop setpoint step from rest and returns the accumulated
+    // error criterion plus the weighted squared control effort, penalizing any
+    // overshoot beyond the allowed maximum.
+    pub fn run<P: Plant<T>>(&self, plant: &mut P, gains: &Gains<T>, cost: &Cost<T>) -> T {
+        plant.init();
+        let mut controller = gains.into_controller(
+            self.output_limit,
+            self.sample_time,
+            self.derivative_time_constant,
+        );
+        let dt = T::from_duration(&self.sample_time);
+        let mut measurement = T::zero();
+        let mut time = T::zero();
+        let mut total = T::zero();
+        let mut overshoot = T::zero();
+        for _ in 0..self.steps {
+            let output = controller.update(self.setpoint, measurement);
+            measurement = plant.update(output, &self.sample_time);
+            time = time + dt;
+            let error = self.setpoint - measurement;
+            let excess = if self.setpoint < T::zero() {
+                error
+            } else {
+                T::zero() - error
+            };
+            if excess > overshoot {
+                overshoot = excess;
+            }
+            let error_cost = match cost.criterion {
+                Criterion::Iae => error.abs(),
+                Criterion::Ise => error * error,
+                Criterion::Itae => time * error.abs(),
+            };
+            total = total + (error_cost + cost.effort_weight * output * output) * dt;
+        }
+        match cost.max_overshoot {
+            Some(max_overshoot) if overshoot > max_overshoot => {
+                total + cost.overshoot_penalty * (overshoot - max_overshoot)
+            }
+            _ => total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // y' = (u - y) / 0.5, integrated with backward Euler.
+    struct Lag(f64);
+    impl Plant<f64> for Lag {
+        fn init(&mut self) {
+            self.0 = 0.0;
+        }
+        fn update(&mut self, input: f64, sample_time: &Duration) -> f64 {
+            let dt = sample_time.as_secs_f64();
+            self.0 += dt / (0.5 + dt) * (input - self.0);
+            self.0
+        }
+    }
+
+    fn simulation() -> Simulation<f64> {
+        Simulation::new(
+            1.0,
+            Duration::from_millis(10),
+            500,
+            OutputLimits::Unbounded,
+            0.0,
+        )
+    }
+
+    #[test]
+    fn open_loop_cost_is_the_integrated_error() {
+        let idle = Gains::new(0.0, 0.0, 0.0);
+        let iae = simulation().run(&mut Lag(0.0), &idle, &Cost::new(Criterion::Iae, 0.0));
+        assert!((iae - 5.0).abs() < 1e-9);
+        let itae = simulation().run(&mut Lag(0.0), &idle, &Cost::new(Criterion::Itae, 0.0));
+        // The sum of t dt over 500 steps of 10 ms.
+        assert!((itae - 0.0001 * 500.0 * 501.0 / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn better_control_costs_less_and_overshoot_is_penalized() {
+        let cost = Cost::new(Criterion::Ise, 0.0);
+        let weak = simulation().run(&mut Lag(0.0), &Gains::new(0.5, 0.5, 0.0), &cost);
+        let good = simulation().run(&mut Lag(0.0), &Gains::new(2.0, 4.0, 0.0), &cost);
+        assert!(good < weak);
+
+        let aggressive = Gains::new(1.0, 20.0, 0.0);
+        let unpenalized = simulation().run(&mut Lag(0.0), &aggressive, &cost);
+        let mut limited = Cost::new(Criterion::Ise, 0.0);
+        limited.set_max_overshoot(Some(0.05), 100.0);
+        let penalized = simulation().run(&mut Lag(0.0), &aggressive, &limited);
+        assert!(penalized > unpenalized + 1.0);
+
+        let mut effort = Cost::new(Criterion::Ise, 1.0);
+        effort.set_max_overshoot(None, 0.0);
+        assert!(simulation().run(&mut Lag(0.0), &aggressive, &effort) > unpenalized);
+    }
+}
@@ -0,0 +1,170 @@
+use alloc::collections::VecDeque;
+
+use crate::{
+    filter::{Filter, LowPass},
+    tuning::Fopdt,
+    ConfigError, ControlLoop, Controller, Float,
+};
+
+// Dead-time compensation: the controller is fed the measurement plus the
+// difference between an undelayed and a delayed first-order model of the
+// process, so it acts on a prediction of the measurement one dead time ahead
+// and can be tuned as though the dead time were not there. Model errors still
+// reach the controller through the real measurement.
+//
+// The delay line holds one model output per sample time of the controller,
+// with the dead time rounded to the nearest sample; call `set_model` again
+// after changing the sample time, which must not be zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmithPredictor<T: Float> {
+    controller: Controller<T>,
+    model: Fopdt<T>,
+    lag: LowPass<T>,
+    delay_line: VecDeque<T>,
+}
+impl<T: Float> SmithPredictor<T> {
+    pub fn new(controller: Controller<T>, model: Fopdt<T>) -> Result<Self, ConfigError> {
+        let mut predictor = Self {
+            controller,
+            model,
+            lag: LowPass::new(model.time_constant),
+            delay_line: VecDeque::new(),
+        };
+        predictor.set_model(model)?;
+        Ok(predictor)
+    }
+    pub fn controller(&self) -> &Controller<T> {
+        &self.controller
+    }
+    pub fn controller_mut(&mut self) -> &mut Controller<T> {
+        &mut self.controller
+    }
+    pub fn model(&self) -> &Fopdt<T> {
+        &self.model
+    }
+    // Replacing the model restarts it from rest.
+    pub fn set_model(&mut self, model: Fopdt<T>) -> Result<(), ConfigError> {
+        if !model.gain.is_finite() {
+            return Err(ConfigError::NonFiniteGain);
+        }
+        if !(model.time_constant.is_finite() && model.dead_time.is_finite()) {
+            return Err(ConfigError::NonFiniteTimeConstant);
+        }
+        if model.time_constant < T::zero() || model.dead_time < T::zero() {
+            return Err(ConfigError::NegativeTimeConstant);
+        }
+        if self.controller.sample_time.is_zero() {
+            return Err(ConfigError::ZeroSampleTime);
+        }
+        self.model = model;
+        self.lag = LowPass::try_new(model.time_constant)?;
+        self.init_model();
+        Ok(())
+    }
+    fn init_model(&mut self) {
+        let dt = T::from_duration(&self.controller.sample_time);
+        let mut samples = 0;
+        // With a zero sample time no dead time can be represented.
+        while dt > T::zero() && T::from_f64(samples as f64 + 0.5) * dt < self.model.dead_time {
+            samples += 1;
+        }
+        self.lag.reset_to(T::zero());
+        self.delay_line.clear();
+        self.delay_line.resize(samples, T::zero());
+    }
+    pub fn init(&mut self) {
+        self.controller.init();
+        self.init_model();
+    }
+    pub fn output(&self) -> T {
+        self.controller.output()
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        // The line holds the last `samples` predictions, so after this one
+        // is added the oldest is exactly one dead time old.
+        let predicted = self.lag.value().unwrap_or(T::zero());
+        self.delay_line.push_back(predicted);
+        let delayed = self.delay_line.pop_front().unwrap_or(predicted);
+        let output = self
+            .controller
+            .update(setpoint, measurement + predicted - delayed);
+        let dt = T::from_duration(&self.controller.sample_time);
+        self.lag.update(self.model.gain * output, dt);
+        output
+    }
+}
+impl<T: Float> ControlLoop<T> for SmithPredictor<T> {
+    fn init(&mut self) {
+        SmithPredictor::init(self);
+    }
+    fn output(&self) -> T {
+        SmithPredictor::output(self)
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        SmithPredictor::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::{Differentiator, Integrator, OutputLimits, Proportional};
+
+    const SAMPLE_TIME: Duration = Duration::from_millis(100);
+
+    fn pi_controller() -> Controller<f64> {
+        Controller::new(
+            OutputLimits::Unbounded,
+            SAMPLE_TIME,
+            Proportional::new(2.0),
+            Integrator::new(1.5, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        )
+    }
+
+    // With a perfect model the controller sees the undelayed process, so it
+    // must act exactly as the same controller on the process without its
+    // dead time.
+    #[test]
+    fn perfect_model_removes_the_dead_time() {
+        for delay in [0, 1, 4] {
+            let model = Fopdt::new(1.5, 0.8, delay as f64 * 0.1);
+            let mut predictor = SmithPredictor::new(pi_controller(), model).unwrap();
+            let mut plant = LowPass::new(0.8);
+            plant.reset_to(0.0);
+            let mut dead_time = VecDeque::from(alloc::vec![0.0; delay]);
+
+            let mut reference = pi_controller();
+            let mut undelayed = LowPass::new(0.8);
+            undelayed.reset_to(0.0);
+
+            for k in 0..100 {
+                dead_time.push_back(plant.value().unwrap());
+                let measurement = dead_time.pop_front().unwrap();
+                let output = predictor.update(1.0, measurement);
+                plant.update(1.5 * output, 0.1);
+
+                let expected = reference.update(1.0, undelayed.value().unwrap());
+                undelayed.update(1.5 * expected, 0.1);
+                assert!((output - expected).abs() < 1e-12, "delay {delay} step {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_a_zero_sample_time() {
+        let controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::ZERO,
+            Proportional::new(2.0),
+            Integrator::new(1.5, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        assert_eq!(
+            SmithPredictor::new(controller, Fopdt::new(1.0, 1.0, 0.5)),
+            Err(ConfigError::ZeroSampleTime)
+        );
+    }
+}
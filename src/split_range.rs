@@ -0,0 +1,104 @@
+use crate::{ConfigError, Float, OutputLimits};
+
+// Actuator positions as fractions from 0 (closed) to 1 (fully open).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitOutput<T: Float> {
+    pub low: T,
+    pub high: T,
+}
+
+// Divides one controller output across two actuators. The low actuator opens
+// as the output falls from the breakpoint to the lower output limit, the high
+// one as it rises from the breakpoint to the upper limit; e.g. with limits of
+// 0 to 100 and a breakpoint at 50, a cooling valve on the low side and a
+// heating valve on the high side. Within the deadband around the breakpoint
+// both stay closed, so they never fight each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitRange<T: Float> {
+    lower: T,
+    upper: T,
+    low_end: T,
+    high_start: T,
+}
+impl<T: Float> SplitRange<T> {
+    // Takes the output limits of the controller that drives it, which must
+    // be bounded on both sides, with the deadband inside them.
+    pub fn try_new(
+        output_limit: impl Into<OutputLimits<T>>,
+        breakpoint: T,
+        deadband: T,
+    ) -> Result<Self, ConfigError> {
+        let OutputLimits::Inclusive(lower, upper) = output_limit.into() else {
+            return Err(ConfigError::InvalidSplitRange);
+        };
+        let low_end = breakpoint - T::half() * deadband;
+        let high_start = breakpoint + T::half() * deadband;
+        if !(deadband >= T::zero() && lower < low_end && high_start < upper) {
+            return Err(ConfigError::InvalidSplitRange);
+        }
+        Ok(Self {
+            lower,
+            upper,
+            low_end,
+            high_start,
+        })
+    }
+    pub fn map(&self, output: T) -> SplitOutput<T> {
+        let fraction = |value: T, span: T| {
+            if value <= T::zero() {
+                T::zero()
+            } else if value >= span {
+                T::one()
+            } else {
+                value / span
+            }
+        };
+        SplitOutput {
+            low: fraction(self.low_end - output, self.low_end - self.lower),
+            high: fraction(output - self.high_start, self.upper - self.high_start),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(low: f64, high: f64) -> SplitOutput<f64> {
+        SplitOutput { low, high }
+    }
+
+    #[test]
+    fn output_is_divided_at_the_breakpoint() {
+        let range = SplitRange::try_new(0.0..=100.0, 50.0, 10.0).unwrap();
+        assert_eq!(range.map(0.0), split(1.0, 0.0));
+        assert_eq!(range.map(-20.0), split(1.0, 0.0));
+        assert_eq!(range.map(22.5), split(0.5, 0.0));
+        // Both actuators stay closed inside the deadband.
+        assert_eq!(range.map(45.0), split(0.0, 0.0));
+        assert_eq!(range.map(55.0), split(0.0, 0.0));
+        assert_eq!(range.map(77.5), split(0.0, 0.5));
+        assert_eq!(range.map(120.0), split(0.0, 1.0));
+    }
+
+    #[test]
+    fn ranges_must_be_bounded_with_room_around_the_deadband() {
+        assert_eq!(
+            SplitRange::try_new(0.0.., 50.0, 0.0),
+            Err(ConfigError::InvalidSplitRange)
+        );
+        assert_eq!(
+            SplitRange::try_new(0.0..=100.0, 50.0, -1.0),
+            Err(ConfigError::InvalidSplitRange)
+        );
+        assert_eq!(
+            SplitRange::try_new(0.0..=100.0, 95.0, 10.0),
+            Err(ConfigError::InvalidSplitRange)
+        );
+        assert_eq!(
+            SplitRange::try_new(0.0..=100.0, 50.0, f64::NAN),
+            Err(ConfigError::InvalidSplitRange)
+        );
+        assert!(SplitRange::try_new(-1.0..=1.0, 0.0, 0.0).is_ok());
+    }
+}
@@ -0,0 +1,221 @@
+use alloc::vec::Vec;
+
+use crate::{
+    optimize::nan_to_worst,
+    simulation::{Cost, Plant, Simulation},
+    tuning::Gains,
+    ConfigError, Float,
+};
+
+type Point<T> = [T; 3];
+
+// xorshift64* is plenty for scattering particles and keeps the tuner
+// deterministic for a given seed.
+struct Random {
+    state: u64,
+}
+impl Random {
+    fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                seed
+            },
+        }
+    }
+    fn next<T: Float>(&mut self) -> T {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let value = self.state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11;
+        T::from_f64(value as f64 / (1u64 << 53) as f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleSwarm<T: Float> {
+    particles: usize,
+    iterations: usize,
+    inertia: T,
+    cognitive: T,
+    social: T,
+    lower: Point<T>,
+    upper: Point<T>,
+    seed: u64,
+}
+impl<T: Float> ParticleSwarm<T> {
+    pub fn new(
+        particles: usize,
+        iterations: usize,
+        lower: Gains<T>,
+        upper: Gains<T>,
+    ) -> Result<Self, ConfigError> {
+        if particles == 0 {
+            return Err(ConfigError::EmptySwarm);
+        }
+        if upper.kp < lower.kp || upper.ki < lower.ki || upper.kd < lower.kd {
+            return Err(ConfigError::InvertedGainLimit);
+        }
+        Ok(Self {
+            particles,
+            iterations,
+            inertia: T::from_f64(0.7),
+            cognitive: T::from_f64(1.5),
+            social: T::from_f64(1.5),
+            lower: [lower.kp, lower.ki, lower.kd],
+            upper: [upper.kp, upper.ki, upper.kd],
+            seed: 1,
+        })
+    }
+    pub fn set_coefficients(&mut self, inertia: T, cognitive: T, social: T) {
+        self.inertia = inertia;
+        self.cognitive = cognitive;
+        self.social = social;
+    }
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+    fn clamp(&self, point: &mut Point<T>) {
+        for ((value, lower), upper) in point.iter_mut().zip(self.lower).zip(self.upper) {
+            if *value < lower {
+                *value = lower;
+            } else if *value > upper {
+                *value = upper;
+            }
+        }
+    }
+    pub fn minimize<F: FnMut(&Gains<T>) -> T>(&self, mut cost: F) -> Gains<T> {
+        let evaluate = |cost: &mut F, point: &Point<T>| {
+            nan_to_worst(cost(&Gains::new(point[0], point[1], point[2])))
+        };
+        let mut random = Random::new(self.seed);
+
+        let mut positions = Vec::with_capacity(self.particles);
+        let mut velocities = Vec::with_capacity(self.particles);
+        for _ in 0..self.particles {
+            let mut position = [T::zero(); 3];
+            let mut velocity = [T::zero(); 3];
+            for axis in 0..3 {
+                let span = self.upper[axis] - self.lower[axis];
+                position[axis] = self.lower[axis] + random.next::<T>() * span;
+                velocity[axis] = (random.next::<T>() - T::half()) * span;
+            }
+            positions.push(position);
+            velocities.push(velocity);
+        }
+        let mut best_positions = positions.clone();
+        let mut best_costs: Vec<T> = positions
+            .iter()
+            .map(|position| evaluate(&mut cost, position))
+            .collect();
+
+        let mut global = 0;
+        for particle in 1..self.particles {
+            if best_costs[particle] < best_costs[global] {
+                global = particle;
+            }
+        }
+        let mut global_position = best_positions[global];
+        let mut global_cost = best_costs[global];
+
+        for _ in 0..self.iterations {
+            for particle in 0..self.particles {
+                let position = &mut positions[particle];
+                let velocity = &mut velocities[particle];
+                for axis in 0..3 {
+                    velocity[axis] = self.inertia * velocity[axis]
+                        + self.cognitive
+                            * random.next::<T>()
+                            * (best_positions[particle][axis] - position[axis])
+                        + self.social
+                            * random.next::<T>()
+                            * (global_position[axis] - position[axis]);
+                    position[axis] = position[axis] + velocity[axis];
+                }
+                self.clamp(position);
+
+                let position_cost = evaluate(&mut cost, position);
+                if position_cost < best_costs[particle] {
+                    best_costs[particle] = position_cost;
+                    best_positions[particle] = *position;
+                    if position_cost < global_cost {
+                        global_cost = position_cost;
+                        global_position = *position;
+                    }
+                }
+            }
+        }
+
+        Gains::new(global_position[0], global_position[1], global_position[2])
+    }
+}
+
+pub fn optimize_swarm<T: Float, P: Plant<T>>(
+    simulation: &Simulation<T>,
+    plant: &mut P,
+    cost: &Cost<T>,
+    swarm: &ParticleSwarm<T>,
+) -> Gains<T> {
+    swarm.minimize(|gains| simulation.run(plant, gains, cost))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swarm() -> ParticleSwarm<f64> {
+        ParticleSwarm::new(
+            20,
+            100,
+            Gains::new(0.0, 0.0, 0.0),
+            Gains::new(5.0, 5.0, 5.0),
+        )
+        .unwrap()
+    }
+
+    fn quadratic(gains: &Gains<f64>) -> f64 {
+        (gains.kp - 1.0).powi(2) + (gains.ki - 2.0).powi(2) + (gains.kd - 0.5).powi(2)
+    }
+
+    #[test]
+    fn finds_minimum_of_quadratic() {
+        let gains = swarm().minimize(quadratic);
+        assert!((gains.kp - 1.0).abs() < 1e-2);
+        assert!((gains.ki - 2.0).abs() < 1e-2);
+        assert!((gains.kd - 0.5).abs() < 1e-2);
+    }
+
+    #[test]
+    fn never_settles_on_nan_costs() {
+        // Gains with kp above 1.5, most of the search space, stand in for a
+        // loop that goes unstable.
+        let cost = |gains: &Gains<f64>| {
+            if gains.kp > 1.5 {
+                f64::NAN
+            } else {
+                quadratic(gains)
+            }
+        };
+        for seed in 1..=8 {
+            let mut swarm = swarm();
+            swarm.set_seed(seed);
+            let gains = swarm.minimize(cost);
+            assert!(gains.kp <= 1.5);
+            assert!((gains.kp - 1.0).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_swarm_and_inverted_bounds() {
+        let bounds = Gains::new(0.0, 0.0, 0.0);
+        assert_eq!(
+            ParticleSwarm::new(0, 10, bounds, bounds),
+            Err(ConfigError::EmptySwarm)
+        );
+        assert_eq!(
+            ParticleSwarm::new(10, 10, Gains::new(1.0, 0.0, 0.0), bounds),
+            Err(ConfigError::InvertedGainLimit)
+        );
+    }
+}
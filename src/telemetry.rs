@@ -0,0 +1,236 @@
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::{
+    setpoint::SetpointSource, tuning::Gains, voting::Voter, Clock, ControlLoop, ControlOutput,
+    Controller, Float, Mode, Timestamp, Timestep,
+};
+
+// The state of a controller after one update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot<T: Float> {
+    // Counts the updates since the last init, starting at zero.
+    pub sequence: u64,
+    // Time since the first update after the last init, as measured by the
+    // timestamped update methods; untimestamped updates count as the nominal
+    // sample time. Gaps show here even when the gap policy integrates over
+    // less.
+    pub time: Duration,
+    // The time step this update integrated over.
+    pub dt: Duration,
+    pub mode: Mode,
+    pub gains: Gains<T>,
+    pub output: ControlOutput<T>,
+}
+
+// Receives a snapshot after every update, to forward it to RTT, a serial
+// port, shared memory or a database. Closures taking a snapshot are sinks.
+pub trait TelemetrySink<T: Float> {
+    fn record(&mut self, snapshot: &Snapshot<T>);
+}
+impl<T: Float, F: FnMut(&Snapshot<T>)> TelemetrySink<T> for F {
+    fn record(&mut self, snapshot: &Snapshot<T>) {
+        self(snapshot)
+    }
+}
+
+// A controller with a sink attached by `Controller::attach_sink`. Every
+// update entry point of the controller is forwarded, and records a snapshot
+// when it runs an update; timestamped updates that arrive too soon to run
+// and decimated updates without samples record nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitored<T: Float, S: TelemetrySink<T>> {
+    controller: Controller<T>,
+    sink: S,
+    sequence: u64,
+    time: Duration,
+}
+impl<T: Float, S: TelemetrySink<T>> Monitored<T, S> {
+    pub fn new(controller: Controller<T>, sink: S) -> Self {
+        Self {
+            controller,
+            sink,
+            sequence: 0,
+            time: Duration::ZERO,
+        }
+    }
+    pub fn controller(&self) -> &Controller<T> {
+        &self.controller
+    }
+    pub fn controller_mut(&mut self) -> &mut Controller<T> {
+        &mut self.controller
+    }
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+    pub fn into_parts(self) -> (Controller<T>, S) {
+        (self.controller, self.sink)
+    }
+    pub fn init(&mut self) {
+        self.controller.init();
+        self.sequence = 0;
+        self.time = Duration::ZERO;
+    }
+    // Runs one of the controller's update methods and records the update it
+    // made, if any.
+    fn monitor<R>(&mut self, update: impl FnOnce(&mut Controller<T>) -> R) -> R {
+        self.controller.last_step = None;
+        let result = update(&mut self.controller);
+        if let Some((output, dt, interval)) = self.controller.last_step.take() {
+            if self.sequence > 0 {
+                self.time += interval;
+            }
+            self.sink.record(&Snapshot {
+                sequence: self.sequence,
+                time: self.time,
+                dt,
+                mode: self.controller.mode(),
+                gains: self.controller.gains(),
+                output,
+            });
+            self.sequence += 1;
+        }
+        result
+    }
+    pub fn update(&mut self, setpoint: T, measurement: T) -> T {
+        self.monitor(|controller| controller.update(setpoint, measurement))
+    }
+    pub fn update_detailed(&mut self, setpoint: T, measurement: T) -> ControlOutput<T> {
+        self.monitor(|controller| controller.update_detailed(setpoint, measurement))
+    }
+    pub fn update_decimated(&mut self, setpoint: T) -> T {
+        self.monitor(|controller| controller.update_decimated(setpoint))
+    }
+    pub fn update_with_feedforward(&mut self, setpoint: T, measurement: T, feedforward: T) -> T {
+        self.monitor(|controller| {
+            controller.update_with_feedforward(setpoint, measurement, feedforward)
+        })
+    }
+    pub fn update_with_disturbance(&mut self, setpoint: T, measurement: T, disturbance: T) -> T {
+        self.monitor(|controller| {
+            controller.update_with_disturbance(setpoint, measurement, disturbance)
+        })
+    }
+    pub fn update_voted<const N: usize>(
+        &mut self,
+        setpoint: T,
+        voter: &mut Voter<T, N>,
+        measurements: &[T; N],
+    ) -> ControlOutput<T> {
+        self.monitor(|controller| controller.update_voted(setpoint, voter, measurements))
+    }
+    pub fn update_tracking(&mut self, setpoint: T, measurement: T, tracked_output: T) -> T {
+        self.monitor(|controller| controller.update_tracking(setpoint, measurement, tracked_output))
+    }
+    pub fn update_from<P: SetpointSource<T>>(&mut self, source: &mut P, measurement: T) -> T {
+        self.monitor(|controller| controller.update_from(source, measurement))
+    }
+    pub fn update_with_dt<D: Timestep<T>>(&mut self, setpoint: T, measurement: T, dt: D) -> T {
+        self.monitor(|controller| controller.update_with_dt(setpoint, measurement, dt))
+    }
+    #[cfg(feature = "std")]
+    pub fn update_at(&mut self, setpoint: T, measurement: T, now: Instant) -> T {
+        self.monitor(|controller| controller.update_at(setpoint, measurement, now))
+    }
+    pub fn update_with_clock<C: Clock>(&mut self, setpoint: T, measurement: T, clock: &C) -> T {
+        self.monitor(|controller| controller.update_with_clock(setpoint, measurement, clock))
+    }
+    pub fn update_at_timestamp<P: Timestamp>(&mut self, setpoint: T, measurement: T, now: &P) -> T {
+        self.monitor(|controller| controller.update_at_timestamp(setpoint, measurement, now))
+    }
+}
+impl<T: Float, S: TelemetrySink<T>> ControlLoop<T> for Monitored<T, S> {
+    fn init(&mut self) {
+        Monitored::init(self);
+    }
+    fn output(&self) -> T {
+        self.controller.output()
+    }
+    fn update(&mut self, setpoint: T, measurement: T) -> T {
+        Monitored::update(self, setpoint, measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        history::History, Differentiator, GapPolicy, Integrator, OutputLimits, Proportional, Ticks,
+    };
+
+    fn monitored() -> Monitored<f64, History<f64, 8>> {
+        Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(100),
+            Proportional::new(1.0),
+            Integrator::new(0.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        )
+        .attach_sink(History::new())
+    }
+
+    #[test]
+    fn every_update_path_is_recorded_with_its_time_step() {
+        let mut controller = monitored();
+        controller.update(1.0, 0.0);
+        controller.update_with_dt(1.0, 0.0, Duration::from_millis(30));
+        controller.update_with_feedforward(1.0, 0.0, 2.0);
+        controller.update_with_dt(1.0, 0.0, Ticks::<1, 1000>::new(5));
+        controller.update_at_timestamp(1.0, 0.0, &Duration::from_secs(10));
+        controller.update_at_timestamp(1.0, 0.0, &Duration::from_millis(10_120));
+        controller.update_tracking(1.0, 0.0, 4.0);
+
+        // Each update is stamped with the time since the previous one; the
+        // first timestamped update has no previous timestamp and counts as
+        // one sample time.
+        let times: [u64; 7] = core::array::from_fn(|index| {
+            controller.sink().get(index).unwrap().timestamp.as_millis() as u64
+        });
+        assert_eq!(times, [0, 30, 130, 135, 235, 355, 455]);
+        let steps: [u64; 7] = core::array::from_fn(|index| {
+            controller.sink().get(index).unwrap().dt.as_millis() as u64
+        });
+        assert_eq!(steps, [100, 30, 100, 5, 100, 120, 100]);
+        assert_eq!(controller.sink().get(2).unwrap().output, 3.0);
+        assert_eq!(controller.sink().latest().unwrap().output, 4.0);
+    }
+
+    #[test]
+    fn skipped_updates_are_not_recorded() {
+        let mut controller = monitored();
+        assert_eq!(controller.update_decimated(1.0), 0.0);
+        controller.update_at_timestamp(1.0, 0.0, &Duration::from_secs(1));
+        controller.update_at_timestamp(1.0, 0.0, &Duration::from_secs(1));
+        assert_eq!(controller.sink().len(), 1);
+
+        controller.controller_mut().push_measurement(0.5);
+        controller.update_decimated(1.0);
+        assert_eq!(controller.sink().len(), 2);
+        assert_eq!(controller.sink().latest().unwrap().measurement, 0.5);
+    }
+
+    #[test]
+    fn gaps_show_in_the_timestamps_whatever_the_policy() {
+        for policy in [GapPolicy::Nominal, GapPolicy::Cap(2)] {
+            let mut controller = monitored();
+            controller
+                .controller_mut()
+                .set_max_gap(Duration::from_millis(150));
+            controller.controller_mut().set_gap_policy(policy);
+            for millis in [0, 100, 1100, 1200] {
+                controller.update_at_timestamp(1.0, 0.0, &Duration::from_millis(millis));
+            }
+            let history = controller.sink();
+            let times: [u64; 4] = core::array::from_fn(|index| {
+                history.get(index).unwrap().timestamp.as_millis() as u64
+            });
+            assert_eq!(times, [0, 100, 1100, 1200]);
+            let stalled = history.get(2).unwrap().dt;
+            assert!(stalled < Duration::from_millis(1000), "{policy:?}");
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{ControlOutput, Float, Mode};
+
+// What a controller reports with the `trace-hook` feature. The crate does
+// not depend on any tracing library; it only hands each event to its
+// `Tracer` callback, a plain function that forwards it wherever the
+// application wants, e.g. as `tracing::trace!` fields inside the span the
+// application opens around its update call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceEvent<T: Float> {
+    // Every update, with the terms, output and saturation it produced.
+    Update {
+        mode: Mode,
+        output: ControlOutput<T>,
+    },
+    ModeChange {
+        from: Mode,
+        to: Mode,
+    },
+}
+
+// A function pointer rather than a closure, like `ErrorShaping`.
+#[derive(Debug, Clone, Copy)]
+pub struct Tracer<T: Float>(fn(&TraceEvent<T>));
+impl<T: Float> Tracer<T> {
+    pub const fn new(trace: fn(&TraceEvent<T>)) -> Self {
+        Self(trace)
+    }
+    pub fn trace(&self, event: &TraceEvent<T>) {
+        (self.0)(event)
+    }
+}
+impl<T: Float> PartialEq for Tracer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::fn_addr_eq(self.0, other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{Controller, Differentiator, Integrator, OutputLimits, Proportional};
+
+    static UPDATES: AtomicU32 = AtomicU32::new(0);
+    static MODE_CHANGES: AtomicU32 = AtomicU32::new(0);
+    fn count(event: &TraceEvent<f64>) {
+        match event {
+            TraceEvent::Update { mode, output } => {
+                assert_eq!(*mode, Mode::Auto);
+                assert_eq!(output.output, 2.0);
+                UPDATES.fetch_add(1, Ordering::Relaxed);
+            }
+            TraceEvent::ModeChange { from, to } => {
+                assert_eq!((*from, *to), (Mode::Auto, Mode::Manual));
+                MODE_CHANGES.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    #[test]
+    fn updates_and_mode_changes_are_traced() {
+        let mut controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(2.0),
+            Integrator::new(0.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        controller.set_tracer(Some(Tracer::new(count)));
+        for _ in 0..3 {
+            controller.update(1.0, 0.0);
+        }
+        controller.set_mode(Mode::Manual);
+        controller.set_mode(Mode::Manual);
+        assert_eq!(UPDATES.load(Ordering::Relaxed), 3);
+        assert_eq!(MODE_CHANGES.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,252 @@
+use core::time::Duration;
+
+use crate::{setpoint::SetpointSource, ConfigError, Float};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionLimits<T: Float> {
+    pub velocity: T,
+    pub acceleration: T,
+    pub jerk: T,
+}
+impl<T: Float> MotionLimits<T> {
+    pub fn new(velocity: T, acceleration: T, jerk: T) -> Self {
+        Self {
+            velocity,
+            acceleration,
+            jerk,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionSetpoint<T: Float> {
+    pub position: T,
+    pub velocity: T,
+    pub acceleration: T,
+}
+
+// One stretch of constant jerk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Segment<T: Float> {
+    duration: T,
+    jerk: T,
+}
+
+// Advances a position, velocity, acceleration state through `time` seconds
+// of constant jerk.
+fn advance<T: Float>(state: MotionSetpoint<T>, jerk: T, time: T) -> MotionSetpoint<T> {
+    let time2 = time * time;
+    let sixth = T::one() / T::from_f64(6.0);
+    MotionSetpoint {
+        position: state.position
+            + state.velocity * time
+            + T::half() * state.acceleration * time2
+            + sixth * jerk * time2 * time,
+        velocity: state.velocity + state.acceleration * time + T::half() * jerk * time2,
+        acceleration: state.acceleration + jerk * time,
+    }
+}
+
+// Rest-to-rest point-to-point moves with bounded velocity, acceleration and
+// jerk: the classic seven-segment S-curve. A trapezoidal profile is the
+// special case of a very large jerk limit. The peak velocity is lowered for
+// moves too short to reach the limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory<T: Float> {
+    limits: MotionLimits<T>,
+    start: T,
+    target: T,
+    segments: [Segment<T>; 7],
+    time: T,
+}
+impl<T: Float> Trajectory<T> {
+    pub fn new(limits: MotionLimits<T>, position: T) -> Result<Self, ConfigError> {
+        let positive = |value: T| value > T::zero() && value.is_finite();
+        if !(positive(limits.velocity) && positive(limits.acceleration) && positive(limits.jerk)) {
+            return Err(ConfigError::InvalidMotionLimits);
+        }
+        Ok(Self {
+            limits,
+            start: position,
+            target: position,
+            segments: [Segment {
+                duration: T::zero(),
+                jerk: T::zero(),
+            }; 7],
+            time: T::zero(),
+        })
+    }
+    // The length of each jerk phase and the total time to accelerate from
+    // rest to `velocity` within the acceleration and jerk limits.
+    fn ramp(&self, velocity: T) -> (T, T) {
+        let MotionLimits {
+            acceleration, jerk, ..
+        } = self.limits;
+        let (jerk_time, accel_time) = if velocity * jerk < acceleration * acceleration {
+            // The acceleration limit is never reached.
+            let jerk_time = (velocity / jerk).sqrt();
+            (jerk_time, T::double() * jerk_time)
+        } else {
+            let jerk_time = acceleration / jerk;
+            (jerk_time, jerk_time + velocity / acceleration)
+        };
+        (jerk_time, accel_time)
+    }
+    // Plans a move from the current position, which must be at rest, to
+    // `target`.
+    pub fn plan(&mut self, target: T) {
+        self.start = self.position();
+        self.target = target;
+        self.time = T::zero();
+        let distance = (target - self.start).abs();
+        let direction = if target < self.start {
+            T::negative()
+        } else {
+            T::one()
+        };
+
+        // Both ramps together cover velocity * accel_time, which grows with
+        // the velocity, so bisect for the peak velocity of short moves.
+        let mut velocity = self.limits.velocity;
+        if velocity * self.ramp(velocity).1 > distance {
+            let (mut low, mut high) = (T::zero(), velocity);
+            for _ in 0..64 {
+                let middle = T::half() * (low + high);
+                if middle * self.ramp(middle).1 > distance {
+                    high = middle;
+                } else {
+                    low = middle;
+                }
+            }
+            velocity = low;
+        }
+        let (jerk_time, accel_time) = self.ramp(velocity);
+        let cruise_distance = distance - velocity * accel_time;
+        let cruise_time = if velocity > T::zero() && cruise_distance > T::zero() {
+            cruise_distance / velocity
+        } else {
+            T::zero()
+        };
+        let jerk = direction * self.limits.jerk;
+        let constant = accel_time - T::double() * jerk_time;
+        let segment = |duration: T, jerk: T| Segment { duration, jerk };
+        self.segments = [
+            segment(jerk_time, jerk),
+            segment(constant, T::zero()),
+            segment(jerk_time, T::zero() - jerk),
+            segment(cruise_time, T::zero()),
+            segment(jerk_time, T::zero() - jerk),
+            segment(constant, T::zero()),
+            segment(jerk_time, jerk),
+        ];
+    }
+    pub fn target(&self) -> T {
+        self.target
+    }
+    pub fn duration(&self) -> T {
+        self.segments
+            .iter()
+            .fold(T::zero(), |total, segment| total + segment.duration)
+    }
+    pub fn is_finished(&self) -> bool {
+        self.time >= self.duration()
+    }
+    // The setpoint at the current time; exactly the target once finished.
+    pub fn setpoint(&self) -> MotionSetpoint<T> {
+        if self.is_finished() {
+            return MotionSetpoint {
+                position: self.target,
+                velocity: T::zero(),
+                acceleration: T::zero(),
+            };
+        }
+        let mut state = MotionSetpoint {
+            position: self.start,
+            velocity: T::zero(),
+            acceleration: T::zero(),
+        };
+        let mut remaining = self.time;
+        for segment in &self.segments {
+            if remaining <= segment.duration {
+                return advance(state, segment.jerk, remaining);
+            }
+            state = advance(state, segment.jerk, segment.duration);
+            remaining = remaining - segment.duration;
+        }
+        state
+    }
+    pub fn position(&self) -> T {
+        self.setpoint().position
+    }
+    pub fn next(&mut self, sample_time: &Duration) -> MotionSetpoint<T> {
+        self.time = self.time + T::from_duration(sample_time);
+        self.setpoint()
+    }
+}
+impl<T: Float> SetpointSource<T> for Trajectory<T> {
+    fn next_setpoint(&mut self, sample_time: &Duration) -> T {
+        self.next(sample_time).position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Steps through the move, checking the limits and that the position
+    // never jumps, and returns the peak velocity.
+    fn follow(trajectory: &mut Trajectory<f64>, limits: &MotionLimits<f64>) -> f64 {
+        let sample_time = Duration::from_millis(1);
+        let mut previous = trajectory.position();
+        let mut peak: f64 = 0.0;
+        while !trajectory.is_finished() {
+            let setpoint = trajectory.next(&sample_time);
+            assert!(setpoint.velocity.abs() <= limits.velocity + 1e-9);
+            assert!(setpoint.acceleration.abs() <= limits.acceleration + 1e-9);
+            assert!((setpoint.position - previous).abs() <= limits.velocity * 1e-3 + 1e-9);
+            peak = peak.max(setpoint.velocity.abs());
+            previous = setpoint.position;
+        }
+        assert!((previous - trajectory.target()).abs() < 1e-9);
+        peak
+    }
+
+    #[test]
+    fn long_move_cruises_at_the_velocity_limit() {
+        let limits = MotionLimits::new(1.0, 2.0, 10.0);
+        let mut trajectory = Trajectory::new(limits, 0.0).unwrap();
+        trajectory.plan(5.0);
+        // 0.2 s of jerk and 0.3 s at full acceleration each way, then
+        // 4.3 m at 1 m/s.
+        assert!((trajectory.duration() - 5.7).abs() < 1e-12);
+        assert!((follow(&mut trajectory, &limits) - 1.0).abs() < 1e-9);
+        assert_eq!(trajectory.next_setpoint(&Duration::from_millis(1)), 5.0);
+    }
+
+    #[test]
+    fn short_moves_lower_the_peak_velocity() {
+        let limits = MotionLimits::new(1.0, 2.0, 10.0);
+        let mut trajectory = Trajectory::new(limits, 1.0).unwrap();
+        trajectory.plan(0.9);
+        let peak = follow(&mut trajectory, &limits);
+        assert!(peak < 0.5);
+        trajectory.plan(0.9);
+        assert_eq!(trajectory.duration(), 0.0);
+        assert!(trajectory.is_finished());
+    }
+
+    #[test]
+    fn limits_must_be_positive_and_finite() {
+        for limits in [
+            MotionLimits::new(0.0, 1.0, 1.0),
+            MotionLimits::new(1.0, -1.0, 1.0),
+            MotionLimits::new(1.0, 1.0, f64::INFINITY),
+            MotionLimits::new(f64::NAN, 1.0, 1.0),
+        ] {
+            assert_eq!(
+                Trajectory::new(limits, 0.0),
+                Err(ConfigError::InvalidMotionLimits)
+            );
+        }
+    }
+}
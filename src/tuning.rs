@@ -0,0 +1,453 @@
+use core::time::Duration;
+
+use crate::{
+    autotune::UltimateGain, ConfigError, Controller, Differentiator, Float, Integrator,
+    OutputLimits, Proportional,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gains<T: Float> {
+    pub kp: T,
+    pub ki: T,
+    pub kd: T,
+}
+impl<T: Float> Gains<T> {
+    pub fn new(kp: T, ki: T, kd: T) -> Self {
+        Self { kp, ki, kd }
+    }
+    fn from_times(kp: T, integral_time: Option<T>, derivative_time: T) -> Self {
+        let ki = match integral_time {
+            Some(integral_time) => kp / integral_time,
+            None => T::zero(),
+        };
+        Self::new(kp, ki, kp * derivative_time)
+    }
+    pub fn into_controller(
+        self,
+        output_limit: impl Into<OutputLimits<T>>,
+        sample_time: Duration,
+        time_constant: T,
+    ) -> Controller<T> {
+        let output_limit = output_limit.into();
+        Controller::new(
+            output_limit,
+            sample_time,
+            Proportional::new(self.kp),
+            Integrator::new(self.ki, output_limit),
+            Differentiator::new(self.kd, time_constant),
+        )
+    }
+}
+
+// ISA standard (ideal) form: Kc (1 + 1 / (Ti s) + Td s). An infinite
+// integral time disables integral action.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StandardGains<T: Float> {
+    pub kc: T,
+    pub integral_time: T,
+    pub derivative_time: T,
+}
+impl<T: Float> StandardGains<T> {
+    pub fn new(kc: T, integral_time: T, derivative_time: T) -> Self {
+        Self {
+            kc,
+            integral_time,
+            derivative_time,
+        }
+    }
+    // The series form only exists when Ti >= 4 Td. Without integral action
+    // both forms are Kc (1 + Td s).
+    pub fn to_series(&self) -> Option<SeriesGains<T>> {
+        if self.integral_time == T::infinity() {
+            return Some(SeriesGains::new(
+                self.kc,
+                self.integral_time,
+                self.derivative_time,
+            ));
+        }
+        let ratio = T::from_f64(4.0) * self.derivative_time / self.integral_time;
+        if ratio > T::one() {
+            return None;
+        }
+        let root = (T::one() - ratio).sqrt();
+        Some(SeriesGains::new(
+            T::half() * self.kc * (T::one() + root),
+            T::half() * self.integral_time * (T::one() + root),
+            T::half() * self.integral_time * (T::one() - root),
+        ))
+    }
+}
+impl<T: Float> From<StandardGains<T>> for Gains<T> {
+    fn from(standard: StandardGains<T>) -> Self {
+        Gains::new(
+            standard.kc,
+            standard.kc / standard.integral_time,
+            standard.kc * standard.derivative_time,
+        )
+    }
+}
+impl<T: Float> From<Gains<T>> for StandardGains<T> {
+    fn from(gains: Gains<T>) -> Self {
+        StandardGains::new(gains.kp, gains.kp / gains.ki, gains.kd / gains.kp)
+    }
+}
+
+// Series (interacting) form: Kc' (1 + 1 / (Ti' s)) (1 + Td' s).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeriesGains<T: Float> {
+    pub kc: T,
+    pub integral_time: T,
+    pub derivative_time: T,
+}
+impl<T: Float> SeriesGains<T> {
+    pub fn new(kc: T, integral_time: T, derivative_time: T) -> Self {
+        Self {
+            kc,
+            integral_time,
+            derivative_time,
+        }
+    }
+}
+impl<T: Float> From<SeriesGains<T>> for StandardGains<T> {
+    fn from(series: SeriesGains<T>) -> Self {
+        if series.integral_time == T::infinity() {
+            return StandardGains::new(series.kc, series.integral_time, series.derivative_time);
+        }
+        let sum = series.integral_time + series.derivative_time;
+        StandardGains::new(
+            series.kc * sum / series.integral_time,
+            sum,
+            series.integral_time * series.derivative_time / sum,
+        )
+    }
+}
+impl<T: Float> From<SeriesGains<T>> for Gains<T> {
+    fn from(series: SeriesGains<T>) -> Self {
+        StandardGains::from(series).into()
+    }
+}
+
+// Tuning rules specify the derivative filter as N, the limit on the
+// high-frequency gain of the derivative term relative to kp, which gives a
+// filter time constant of Td / N = kd / (kp * N). Practical values of N lie
+// between 2 and 20; below 1 the filter is too slow to leave any derivative
+// action and above 100 too fast to do any filtering, so only those are
+// rejected.
+pub const MIN_DERIVATIVE_FILTER: f64 = 1.0;
+pub const MAX_DERIVATIVE_FILTER: f64 = 100.0;
+
+pub fn derivative_filter_time_constant<T: Float>(kp: T, kd: T, n: T) -> Result<T, ConfigError> {
+    if !(n >= T::from_f64(MIN_DERIVATIVE_FILTER) && n <= T::from_f64(MAX_DERIVATIVE_FILTER)) {
+        return Err(ConfigError::DerivativeFilterOutOfRange);
+    }
+    if kp == T::zero() {
+        return Err(ConfigError::ZeroProportionalGain);
+    }
+    let time_constant = kd / (kp * n);
+    if time_constant < T::zero() {
+        return Err(ConfigError::NegativeTimeConstant);
+    }
+    Ok(time_constant)
+}
+// None when the filter is disabled or there is no proportional gain to
+// relate it to.
+pub fn derivative_filter_coefficient<T: Float>(kp: T, kd: T, time_constant: T) -> Option<T> {
+    if time_constant == T::zero() || kp == T::zero() {
+        return None;
+    }
+    Some(kd / (kp * time_constant))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fopdt<T: Float> {
+    pub gain: T,
+    pub time_constant: T,
+    pub dead_time: T,
+}
+impl<T: Float> Fopdt<T> {
+    pub fn new(gain: T, time_constant: T, dead_time: T) -> Self {
+        Self {
+            gain,
+            time_constant,
+            dead_time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZieglerNichols {
+    P,
+    PI,
+    PD,
+    PID,
+    PessenIntegral,
+    SomeOvershoot,
+    NoOvershoot,
+}
+
+pub fn ziegler_nichols<T: Float>(ultimate: &UltimateGain<T>, rule: ZieglerNichols) -> Gains<T> {
+    let ku = ultimate.gain;
+    let tu = ultimate.period;
+    let c = T::from_f64;
+    match rule {
+        ZieglerNichols::P => Gains::from_times(c(0.5) * ku, None, T::zero()),
+        ZieglerNichols::PI => Gains::from_times(c(0.45) * ku, Some(tu / c(1.2)), T::zero()),
+        ZieglerNichols::PD => Gains::from_times(c(0.8) * ku, None, c(0.125) * tu),
+        ZieglerNichols::PID => Gains::from_times(c(0.6) * ku, Some(c(0.5) * tu), c(0.125) * tu),
+        ZieglerNichols::PessenIntegral => {
+            Gains::from_times(c(0.7) * ku, Some(c(0.4) * tu), c(0.15) * tu)
+        }
+        ZieglerNichols::SomeOvershoot => {
+            Gains::from_times(ku / c(3.0), Some(c(0.5) * tu), tu / c(3.0))
+        }
+        ZieglerNichols::NoOvershoot => {
+            Gains::from_times(c(0.2) * ku, Some(c(0.5) * tu), tu / c(3.0))
+        }
+    }
+}
+
+// The reaction-curve rules are the ultimate-point rules evaluated at
+// Ku = 2T / (K L) and Tu = 4L.
+pub fn ziegler_nichols_step<T: Float>(model: &Fopdt<T>, rule: ZieglerNichols) -> Gains<T> {
+    let ultimate = UltimateGain {
+        gain: T::double() * model.time_constant / (model.gain * model.dead_time),
+        period: T::from_f64(4.0) * model.dead_time,
+    };
+    ziegler_nichols(&ultimate, rule)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CohenCoon {
+    P,
+    PI,
+    PD,
+    PID,
+}
+
+pub fn cohen_coon<T: Float>(model: &Fopdt<T>, rule: CohenCoon) -> Gains<T> {
+    let c = T::from_f64;
+    let theta = model.dead_time;
+    let r = theta / model.time_constant;
+    let base = model.time_constant / (model.gain * theta);
+    match rule {
+        CohenCoon::P => Gains::from_times(base * (T::one() + r / c(3.0)), None, T::zero()),
+        CohenCoon::PI => Gains::from_times(
+            base * (c(0.9) + r / c(12.0)),
+            Some(theta * (c(30.0) + c(3.0) * r) / (c(9.0) + c(20.0) * r)),
+            T::zero(),
+        ),
+        CohenCoon::PD => Gains::from_times(
+            base * (c(1.25) + r / c(6.0)),
+            None,
+            theta * (c(6.0) - T::double() * r) / (c(22.0) + c(3.0) * r),
+        ),
+        CohenCoon::PID => Gains::from_times(
+            base * (c(4.0 / 3.0) + r / c(4.0)),
+            Some(theta * (c(32.0) + c(6.0) * r) / (c(13.0) + c(8.0) * r)),
+            c(4.0) * theta / (c(11.0) + T::double() * r),
+        ),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lambda {
+    PI,
+    PID,
+}
+
+pub fn lambda<T: Float>(model: &Fopdt<T>, closed_loop_time_constant: T, rule: Lambda) -> Gains<T> {
+    let theta = model.dead_time;
+    let tau = model.time_constant;
+    match rule {
+        Lambda::PI => Gains::from_times(
+            tau / (model.gain * (closed_loop_time_constant + theta)),
+            Some(tau),
+            T::zero(),
+        ),
+        Lambda::PID => {
+            let half_theta = T::half() * theta;
+            Gains::from_times(
+                (tau + half_theta) / (model.gain * (closed_loop_time_constant + half_theta)),
+                Some(tau + half_theta),
+                tau * theta / (T::double() * tau + theta),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Amigo {
+    PI,
+    PID,
+}
+
+pub fn amigo<T: Float>(model: &Fopdt<T>, rule: Amigo) -> Gains<T> {
+    let c = T::from_f64;
+    let k = model.gain;
+    let l = model.dead_time;
+    let t = model.time_constant;
+    match rule {
+        Amigo::PI => {
+            let sum = l + t;
+            Gains::from_times(
+                c(0.15) / k + (c(0.35) - l * t / (sum * sum)) * t / (k * l),
+                Some(
+                    c(0.35) * l + c(13.0) * l * t * t / (t * t + c(12.0) * l * t + c(7.0) * l * l),
+                ),
+                T::zero(),
+            )
+        }
+        Amigo::PID => Gains::from_times(
+            (c(0.2) + c(0.45) * t / l) / k,
+            Some((c(0.4) * l + c(0.8) * t) / (l + c(0.1) * t) * l),
+            T::half() * l * t / (c(0.3) * l + t),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-12 * expected.abs().max(1.0),
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn standard_and_series_forms_round_trip() {
+        let standard = StandardGains::new(2.0, 8.0, 1.5);
+        let series = standard.to_series().unwrap();
+        let back = StandardGains::from(series);
+        assert_close(back.kc, standard.kc);
+        assert_close(back.integral_time, standard.integral_time);
+        assert_close(back.derivative_time, standard.derivative_time);
+    }
+
+    #[test]
+    fn series_form_needs_integral_time_of_four_derivative_times() {
+        assert!(StandardGains::new(2.0, 3.0, 1.0).to_series().is_none());
+        assert!(StandardGains::new(2.0, 4.0, 1.0).to_series().is_some());
+    }
+
+    #[test]
+    fn pd_controller_converts_without_nan() {
+        let standard = StandardGains::new(2.0, f64::INFINITY, 0.5);
+        let series = standard.to_series().unwrap();
+        assert_eq!(series, SeriesGains::new(2.0, f64::INFINITY, 0.5));
+        assert_eq!(StandardGains::from(series), standard);
+        let gains = Gains::from(series);
+        assert_eq!(gains, Gains::new(2.0, 0.0, 1.0));
+    }
+
+    fn assert_gains(gains: Gains<f64>, kp: f64, ki: f64, kd: f64) {
+        assert_close(gains.kp, kp);
+        assert_close(gains.ki, ki);
+        assert_close(gains.kd, kd);
+    }
+
+    #[test]
+    fn ziegler_nichols_matches_the_ultimate_gain_table() {
+        // Ku = 10, Tu = 2 s.
+        let ultimate = UltimateGain {
+            gain: 10.0,
+            period: 2.0,
+        };
+        let rule = |rule| ziegler_nichols(&ultimate, rule);
+        assert_gains(rule(ZieglerNichols::P), 5.0, 0.0, 0.0);
+        assert_gains(rule(ZieglerNichols::PI), 4.5, 2.7, 0.0);
+        assert_gains(rule(ZieglerNichols::PD), 8.0, 0.0, 2.0);
+        assert_gains(rule(ZieglerNichols::PID), 6.0, 6.0, 1.5);
+        assert_gains(rule(ZieglerNichols::PessenIntegral), 7.0, 8.75, 2.1);
+        assert_gains(
+            rule(ZieglerNichols::SomeOvershoot),
+            10.0 / 3.0,
+            10.0 / 3.0,
+            20.0 / 9.0,
+        );
+        assert_gains(rule(ZieglerNichols::NoOvershoot), 2.0, 2.0, 4.0 / 3.0);
+    }
+
+    #[test]
+    fn ziegler_nichols_step_matches_the_reaction_curve_table() {
+        // K = 2, T = 10 s, L = 2 s: kp = 1.2 T / (K L), Ti = 2 L, Td = L / 2 for PID
+        // and kp = 0.9 T / (K L), Ti = L / 0.3 for PI.
+        let model = Fopdt::new(2.0, 10.0, 2.0);
+        assert_gains(
+            ziegler_nichols_step(&model, ZieglerNichols::P),
+            2.5,
+            0.0,
+            0.0,
+        );
+        assert_gains(
+            ziegler_nichols_step(&model, ZieglerNichols::PI),
+            2.25,
+            0.3375,
+            0.0,
+        );
+        assert_gains(
+            ziegler_nichols_step(&model, ZieglerNichols::PID),
+            3.0,
+            0.75,
+            3.0,
+        );
+    }
+
+    #[test]
+    fn cohen_coon_matches_the_reference_gains() {
+        // K = 2, T = 10 s, L = 2 s, so T / (K L) = 2.5 and L / T = 0.2.
+        let model = Fopdt::new(2.0, 10.0, 2.0);
+        assert_gains(cohen_coon(&model, CohenCoon::P), 8.0 / 3.0, 0.0, 0.0);
+        let kp = 55.0 / 24.0;
+        assert_gains(
+            cohen_coon(&model, CohenCoon::PI),
+            kp,
+            kp / (61.2 / 13.0),
+            0.0,
+        );
+        let kp = 77.0 / 24.0;
+        assert_gains(cohen_coon(&model, CohenCoon::PD), kp, 0.0, kp * 11.2 / 22.6);
+        let kp = 83.0 / 24.0;
+        assert_gains(
+            cohen_coon(&model, CohenCoon::PID),
+            kp,
+            kp / (66.4 / 14.6),
+            kp * 8.0 / 11.4,
+        );
+    }
+
+    #[test]
+    fn lambda_matches_the_imc_gains() {
+        // K = 2, T = 10 s, L = 2 s with a closed-loop time constant of 5 s.
+        let model = Fopdt::new(2.0, 10.0, 2.0);
+        assert_gains(lambda(&model, 5.0, Lambda::PI), 5.0 / 7.0, 1.0 / 14.0, 0.0);
+        assert_gains(
+            lambda(&model, 5.0, Lambda::PID),
+            11.0 / 12.0,
+            1.0 / 12.0,
+            5.0 / 6.0,
+        );
+    }
+
+    #[test]
+    fn amigo_matches_the_reference_gains() {
+        // K = 2, T = 10 s, L = 2 s.
+        let model = Fopdt::new(2.0, 10.0, 2.0);
+        let kp = 0.075 + (0.35 - 20.0 / 144.0) * 2.5;
+        assert_gains(
+            amigo(&model, Amigo::PI),
+            kp,
+            kp / (0.7 + 2600.0 / 368.0),
+            0.0,
+        );
+        let kp = 1.225;
+        assert_gains(
+            amigo(&model, Amigo::PID),
+            kp,
+            kp / (8.8 / 3.0 * 2.0),
+            kp * 10.0 / 10.6,
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{ConfigError, Float, OutputLimits};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vote {
+    // The median of the good channels; with two, their mean.
+    Median,
+    // The mean of the good channels.
+    AverageOfGood,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Voted<T: Float, const N: usize> {
+    // `None` when no channel is good.
+    pub value: Option<T>,
+    pub good: [bool; N],
+}
+
+// Votes redundant measurements of one quantity down to a single value for
+// the controller. A channel is good for a sample when it is enabled, finite,
+// within the plausible range and, once there is a median to compare with,
+// within the maximum deviation of the median of all candidates, itself
+// included. Each channel counts its consecutive bad samples, so a failing
+// sensor can be flagged for maintenance or switched to standby.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voter<T: Float, const N: usize> {
+    vote: Vote,
+    range: OutputLimits<T>,
+    max_deviation: Option<T>,
+    enabled: [bool; N],
+    bad_counts: [u32; N],
+}
+impl<T: Float, const N: usize> Voter<T, N> {
+    pub fn new(vote: Vote) -> Self {
+        Self {
+            vote,
+            range: OutputLimits::Unbounded,
+            max_deviation: None,
+            enabled: [true; N],
+            bad_counts: [0; N],
+        }
+    }
+    pub fn set_range(&mut self, range: impl Into<OutputLimits<T>>) -> Result<(), ConfigError> {
+        let range = range.into();
+        if range.is_inverted() {
+            return Err(ConfigError::InvertedMeasurementRange);
+        }
+        self.range = range;
+        Ok(())
+    }
+    pub fn set_max_deviation(&mut self, max_deviation: Option<T>) {
+        self.max_deviation = max_deviation;
+    }
+    // Disabled channels, such as a standby sensor, never take part.
+    pub fn set_enabled(&mut self, channel: usize, enabled: bool) {
+        self.enabled[channel] = enabled;
+    }
+    pub fn bad_counts(&self) -> &[u32; N] {
+        &self.bad_counts
+    }
+    pub fn init(&mut self) {
+        self.bad_counts = [0; N];
+    }
+    pub fn vote(&mut self, measurements: &[T; N]) -> Voted<T, N> {
+        let mut good: [bool; N] = core::array::from_fn(|channel| {
+            let value = measurements[channel];
+            self.enabled[channel]
+                && value.is_finite()
+                && !self.range.is_above(value)
+                && !self.range.is_below(value)
+        });
+        if let Some(max_deviation) = self.max_deviation {
+            if let Some(median) = median(measurements, &good) {
+                for (channel, good) in good.iter_mut().enumerate() {
+                    *good = *good && (measurements[channel] - median).abs() <= max_deviation;
+                }
+            }
+        }
+        for (count, &good) in self.bad_counts.iter_mut().zip(&good) {
+            *count = if good { 0 } else { count.saturating_add(1) };
+        }
+        let value = match self.vote {
+            Vote::Median => median(measurements, &good),
+            Vote::AverageOfGood => {
+                let (sum, count) = measurements
+                    .iter()
+                    .zip(&good)
+                    .filter(|(_, &good)| good)
+                    .fold((T::zero(), T::zero()), |(sum, count), (&value, _)| {
+                        (sum + value, count + T::one())
+                    });
+                (count > T::zero()).then(|| sum / count)
+            }
+        };
+        Voted { value, good }
+    }
+}
+
+fn median<T: Float, const N: usize>(values: &[T; N], good: &[bool; N]) -> Option<T> {
+    let mut sorted = [T::zero(); N];
+    let mut len = 0;
+    for (&value, _) in values.iter().zip(good).filter(|(_, &good)| good) {
+        // Insertion sort, which is the fastest for the handful of channels
+        // redundant sensors have.
+        let mut index = len;
+        while index > 0 && sorted[index - 1] > value {
+            sorted[index] = sorted[index - 1];
+            index -= 1;
+        }
+        sorted[index] = value;
+        len += 1;
+    }
+    match len {
+        0 => None,
+        len if len % 2 == 1 => Some(sorted[len / 2]),
+        len => Some(T::half() * (sorted[len / 2 - 1] + sorted[len / 2])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outliers_are_voted_out() {
+        let mut median = Voter::<f64, 3>::new(Vote::Median);
+        assert_eq!(median.vote(&[1.0, 1.2, 50.0]).value, Some(1.2));
+
+        let mut average = Voter::<f64, 3>::new(Vote::AverageOfGood);
+        assert_eq!(average.vote(&[1.0, 2.0, 51.0]).value, Some(18.0));
+        average.set_max_deviation(Some(1.0));
+        let voted = average.vote(&[1.0, 1.2, 50.0]);
+        assert!((voted.value.unwrap() - 1.1).abs() < 1e-12);
+        assert_eq!(voted.good, [true, true, false]);
+    }
+
+    #[test]
+    fn bad_channels_are_excluded_and_counted() {
+        let mut voter = Voter::<f64, 4>::new(Vote::Median);
+        voter.set_range(0.0..=10.0).unwrap();
+        voter.set_enabled(3, false);
+        for _ in 0..3 {
+            let voted = voter.vote(&[2.0, f64::NAN, 11.0, 4.0]);
+            assert_eq!(voted.value, Some(2.0));
+            assert_eq!(voted.good, [true, false, false, false]);
+        }
+        assert_eq!(voter.bad_counts(), &[0, 3, 3, 3]);
+        // Two good channels vote their mean, and a recovered channel's
+        // count restarts.
+        assert_eq!(voter.vote(&[2.0, 3.0, 11.0, 4.0]).value, Some(2.5));
+        assert_eq!(voter.bad_counts(), &[0, 0, 4, 4]);
+        voter.init();
+        assert_eq!(voter.bad_counts(), &[0; 4]);
+    }
+
+    #[test]
+    fn no_good_channel_gives_no_value() {
+        let mut voter = Voter::<f64, 2>::new(Vote::AverageOfGood);
+        assert_eq!(voter.vote(&[f64::NAN, f64::INFINITY]).value, None);
+        assert_eq!(
+            voter.set_range(1.0..=0.0),
+            Err(ConfigError::InvertedMeasurementRange)
+        );
+    }
+}
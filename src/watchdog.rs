@@ -0,0 +1,141 @@
+use core::time::Duration;
+
+use crate::Float;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    // Any update counts as fresh, so only a stalled update stream trips the
+    // watchdog; `Controller::tick_watchdog` advances it between updates.
+    Update,
+    // Only a measurement that differs from the previous one counts, which
+    // also catches a sensor frozen at its last value.
+    Change,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleAction {
+    // Drop stale samples, holding the output and the integrator, and resume
+    // without a derivative step once fresh measurements return.
+    Hold,
+    // Trip into fault mode.
+    Fault,
+}
+
+// Trips when no fresh measurement has been seen for longer than the timeout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watchdog<T: Float> {
+    timeout: T,
+    freshness: Freshness,
+    action: StaleAction,
+    since_fresh: T,
+    previous: Option<T>,
+}
+impl<T: Float> Watchdog<T> {
+    pub fn new(timeout: Duration, freshness: Freshness, action: StaleAction) -> Self {
+        Self {
+            timeout: T::from_duration(&timeout),
+            freshness,
+            action,
+            since_fresh: T::zero(),
+            previous: None,
+        }
+    }
+    pub fn action(&self) -> StaleAction {
+        self.action
+    }
+    pub fn is_stale(&self) -> bool {
+        self.since_fresh > self.timeout
+    }
+    pub fn init(&mut self) {
+        self.since_fresh = T::zero();
+        self.previous = None;
+    }
+    pub fn tick(&mut self, elapsed: T) {
+        self.since_fresh = self.since_fresh + elapsed;
+    }
+    // Records a measurement taken `dt` after the previous one and returns
+    // whether the input is stale.
+    pub fn feed(&mut self, measurement: T, dt: T) -> bool {
+        let fresh = match self.freshness {
+            Freshness::Update => true,
+            Freshness::Change => self.previous != Some(measurement),
+        };
+        self.previous = Some(measurement);
+        if fresh {
+            self.since_fresh = T::zero();
+        } else {
+            self.tick(dt);
+        }
+        self.is_stale()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Controller, Differentiator, FaultCause, Integrator, Mode, OutputLimits, Proportional,
+    };
+
+    fn watchdog(freshness: Freshness, action: StaleAction) -> Watchdog<f64> {
+        Watchdog::new(Duration::from_millis(50), freshness, action)
+    }
+
+    #[test]
+    fn frozen_measurement_goes_stale() {
+        let mut watchdog = watchdog(Freshness::Change, StaleAction::Hold);
+        let stale: [bool; 7] = core::array::from_fn(|_| watchdog.feed(1.0, 0.01));
+        assert_eq!(stale, [false, false, false, false, false, false, true]);
+        assert!(!watchdog.feed(1.5, 0.01));
+        watchdog.init();
+        assert!(!watchdog.is_stale());
+    }
+
+    #[test]
+    fn stalled_updates_go_stale() {
+        let mut watchdog = watchdog(Freshness::Update, StaleAction::Fault);
+        for _ in 0..10 {
+            assert!(!watchdog.feed(1.0, 0.01));
+        }
+        watchdog.tick(0.05);
+        assert!(!watchdog.is_stale());
+        watchdog.tick(0.01);
+        assert!(watchdog.is_stale());
+        assert!(!watchdog.feed(1.0, 0.01));
+    }
+
+    fn controller(action: StaleAction) -> Controller<f64> {
+        let mut controller = Controller::new(
+            OutputLimits::Unbounded,
+            Duration::from_millis(10),
+            Proportional::new(1.0),
+            Integrator::new(1.0, OutputLimits::Unbounded),
+            Differentiator::new(0.0, 0.0),
+        );
+        controller.set_watchdog(Some(watchdog(Freshness::Change, action)));
+        controller
+    }
+
+    #[test]
+    fn stale_measurements_hold_the_output() {
+        let mut controller = controller(StaleAction::Hold);
+        let mut output = 0.0;
+        for _ in 0..6 {
+            output = controller.update(1.0, 0.0);
+        }
+        let report = controller.update_detailed(1.0, 0.0);
+        assert!(report.measurement_stale);
+        assert_eq!(report.output, output);
+        assert_eq!(controller.mode(), Mode::Auto);
+        assert!(!controller.update_detailed(1.0, 0.1).measurement_stale);
+    }
+
+    #[test]
+    fn stale_measurements_can_trip_a_fault() {
+        let mut controller = controller(StaleAction::Fault);
+        controller.update(1.0, 0.0);
+        controller.tick_watchdog(Duration::from_millis(60));
+        assert_eq!(controller.mode(), Mode::Fault);
+        assert_eq!(controller.fault_cause(), Some(FaultCause::StaleMeasurement));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{ConfigError, Float};
+
+// Periods a controller's unwrapped measurement may travel before it is
+// re-centred on the raw measurement.
+pub const RECENTRE_TURNS: f64 = 65536.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapPolicy {
+    // The error takes the shorter way around, in [-period / 2, period / 2).
+    Shortest,
+    // The error is always in [0, period), for processes that can only move
+    // forward, such as a conveyor or a clock.
+    Forward,
+    // The error is always in (-period, 0].
+    Backward,
+}
+
+// Describes a periodic process variable: angles, encoder counts per
+// revolution, phase, time of day. Measurements are unwrapped sample to sample
+// by the shortest path; the policy decides which equivalent of the setpoint
+// the error is measured against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wrapping<T: Float> {
+    period: T,
+    policy: WrapPolicy,
+}
+impl<T: Float> Wrapping<T> {
+    pub fn new(period: T, policy: WrapPolicy) -> Self {
+        Self { period, policy }
+    }
+    pub fn try_new(period: T, policy: WrapPolicy) -> Result<Self, ConfigError> {
+        if !(period > T::zero() && period.is_finite()) {
+            return Err(ConfigError::InvalidWrapPeriod);
+        }
+        Ok(Self::new(period, policy))
+    }
+    pub fn radians() -> Self {
+        Self::new(T::from_f64(core::f64::consts::TAU), WrapPolicy::Shortest)
+    }
+    pub fn degrees() -> Self {
+        Self::new(T::from_f64(360.0), WrapPolicy::Shortest)
+    }
+    pub fn period(&self) -> T {
+        self.period
+    }
+    pub fn policy(&self) -> WrapPolicy {
+        self.policy
+    }
+    // Maps a difference into [-period / 2, period / 2).
+    pub fn shortest(&self, difference: T) -> T {
+        let half = T::half() * self.period;
+        self.reduce(difference, T::zero() - half, half)
+    }
+    // Maps a setpoint error into the range given by the policy.
+    pub fn error(&self, error: T) -> T {
+        match self.policy {
+            WrapPolicy::Shortest => self.shortest(error),
+            WrapPolicy::Forward => self.reduce(error, T::zero(), self.period),
+            WrapPolicy::Backward => {
+                T::zero() - self.reduce(T::zero() - error, T::zero(), self.period)
+            }
+        }
+    }
+    // Removes whole periods until the value is in [lower, upper), where
+    // upper - lower is one period. A pass only leaves the rounding error of
+    // period * turns behind, so huge values settle within a few passes.
+    fn reduce(&self, value: T, lower: T, upper: T) -> T {
+        if !(value.is_finite() && self.period > T::zero() && self.period.is_finite()) {
+            return value;
+        }
+        let mut value = value;
+        while value >= upper || value < lower {
+            let turns = ((value - lower) / self.period).floor();
+            value = if turns != T::zero() {
+                value - self.period * turns
+            } else if value < lower {
+                value + self.period
+            } else {
+                value - self.period
+            };
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortest_takes_the_short_way_round() {
+        let wrapping = Wrapping::<f64>::degrees();
+        assert_eq!(wrapping.shortest(350.0), -10.0);
+        assert_eq!(wrapping.shortest(-190.0), 170.0);
+        assert_eq!(wrapping.shortest(180.0), -180.0);
+        assert_eq!(wrapping.shortest(720.0 + 5.0), 5.0);
+    }
+
+    #[test]
+    fn policies_pick_the_direction() {
+        let forward = Wrapping::<f64>::new(360.0, WrapPolicy::Forward);
+        assert_eq!(forward.error(-10.0), 350.0);
+        assert_eq!(forward.error(0.0), 0.0);
+        let backward = Wrapping::<f64>::new(360.0, WrapPolicy::Backward);
+        assert_eq!(backward.error(10.0), -350.0);
+        assert_eq!(backward.error(-10.0), -10.0);
+    }
+
+    #[test]
+    fn huge_values_reduce_into_range() {
+        for wrapping in [
+            Wrapping::<f64>::radians(),
+            Wrapping::new(360.0, WrapPolicy::Forward),
+        ] {
+            for value in [1e20, -1e20, 1e300, -3.5e15, f64::MAX] {
+                let error = wrapping.error(value);
+                let half = 0.5 * wrapping.period();
+                match wrapping.policy() {
+                    WrapPolicy::Shortest => assert!((-half..half).contains(&error), "{value}"),
+                    _ => assert!((0.0..wrapping.period()).contains(&error), "{value}"),
+                }
+            }
+        }
+        let wrapping = Wrapping::<f32>::degrees();
+        let error = wrapping.shortest(1e20);
+        assert!((-180.0..180.0).contains(&error));
+    }
+}